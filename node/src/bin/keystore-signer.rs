@@ -0,0 +1,276 @@
+//! Reference signer for the remote keystore protocol.
+//!
+//! Serves the protocol described in `global_network::remote_keystore` from a local keystore
+//! directory. With `--tls-cert`, `--tls-key` and `--client-ca` only clients presenting a
+//! certificate issued by `--client-ca` are accepted. `--insecure-http` serves plain HTTP and is
+//! meant for local tests only.
+//!
+//! VRFs are only signed over the randomness beacon's transcript, so a compromised node cannot
+//! obtain VRFs of its author keys for any other use.
+
+use std::{
+	io::{Read, Write},
+	net::{SocketAddr, TcpListener, TcpStream},
+	path::PathBuf,
+	sync::Arc,
+	thread,
+};
+
+use clap::Parser;
+use global_network::remote_keystore::{transport, Transcript, TranscriptValue, Vrf};
+use pallet_randomness_beacon::vrf::transcript_data;
+use sc_keystore::LocalKeystore;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{ByteArray, CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519, Bytes, H256,
+};
+use sp_keystore::{vrf::VRFTranscriptData, SyncCryptoStore};
+
+#[derive(Debug, Parser)]
+struct Args {
+	/// Address to listen on.
+	#[arg(long, default_value = "127.0.0.1:9955")]
+	listen: SocketAddr,
+
+	/// Directory of the keystore holding the keys.
+	#[arg(long, value_name = "PATH")]
+	keystore_path: PathBuf,
+
+	/// Server certificate chain (PEM).
+	#[arg(long, value_name = "PATH", requires_all = ["tls_key", "client_ca"])]
+	tls_cert: Option<PathBuf>,
+
+	/// Private key (PEM) of `--tls-cert`.
+	#[arg(long, value_name = "PATH", requires = "tls_cert")]
+	tls_key: Option<PathBuf>,
+
+	/// CA certificate (PEM) that client certificates must chain to.
+	#[arg(long, value_name = "PATH", requires = "tls_cert")]
+	client_ca: Option<PathBuf>,
+
+	/// Serve plain HTTP. Only for local tests.
+	#[arg(long, conflicts_with = "tls_cert", required_unless_present = "tls_cert")]
+	insecure_http: bool,
+}
+
+fn main() -> Result<(), String> {
+	let args = Args::parse();
+
+	let keystore = Arc::new(
+		LocalKeystore::open(&args.keystore_path, None)
+			.map_err(|e| format!("failed to open keystore: {}", e))?,
+	);
+	let tls = match (&args.tls_cert, &args.tls_key, &args.client_ca) {
+		(Some(cert), Some(key), Some(ca)) => Some(server_config(cert, key, ca)?),
+		_ => None,
+	};
+
+	let listener = TcpListener::bind(args.listen).map_err(|e| e.to_string())?;
+	eprintln!("keystore-signer listening on {}", args.listen);
+
+	for stream in listener.incoming() {
+		let stream = match stream {
+			Ok(stream) => stream,
+			Err(e) => {
+				eprintln!("failed to accept connection: {}", e);
+				continue
+			},
+		};
+		let keystore = keystore.clone();
+		let tls = tls.clone();
+		thread::spawn(move || {
+			if let Err(e) = serve_connection(&keystore, tls, stream) {
+				eprintln!("connection failed: {}", e);
+			}
+		});
+	}
+	Ok(())
+}
+
+fn server_config(
+	cert: &PathBuf,
+	key: &PathBuf,
+	ca: &PathBuf,
+) -> Result<Arc<rustls::ServerConfig>, String> {
+	let roots = transport::load_roots(ca).map_err(|e| e.to_string())?;
+	let certs = transport::load_certs(cert).map_err(|e| e.to_string())?;
+	let key = transport::load_private_key(key).map_err(|e| e.to_string())?;
+
+	let config = rustls::ServerConfig::builder()
+		.with_safe_defaults()
+		.with_client_cert_verifier(rustls::server::AllowAnyAuthenticatedClient::new(roots))
+		.with_single_cert(certs, key)
+		.map_err(|e| e.to_string())?;
+	Ok(Arc::new(config))
+}
+
+fn serve_connection(
+	keystore: &LocalKeystore,
+	tls: Option<Arc<rustls::ServerConfig>>,
+	stream: TcpStream,
+) -> std::io::Result<()> {
+	match tls {
+		Some(config) => {
+			let connection = rustls::ServerConnection::new(config)
+				.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+			let mut stream = rustls::StreamOwned::new(connection, stream);
+			respond(keystore, &mut stream)?;
+			stream.conn.send_close_notify();
+			stream.flush()
+		},
+		None => {
+			let mut stream = stream;
+			respond(keystore, &mut stream)
+		},
+	}
+}
+
+fn respond<S: Read + Write>(keystore: &LocalKeystore, stream: &mut S) -> std::io::Result<()> {
+	let (_, body) = transport::read_message(&mut *stream)?;
+
+	let response = match serde_json::from_slice::<Value>(&body) {
+		Ok(request) => {
+			let id = request.get("id").cloned().unwrap_or(Value::Null);
+			let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+			let params = match request.get("params") {
+				Some(Value::Array(params)) => params.clone(),
+				_ => Vec::new(),
+			};
+			match handle(keystore, method, &params) {
+				Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+				Err(message) => json!({
+					"jsonrpc": "2.0",
+					"id": id,
+					"error": { "code": -32000, "message": message },
+				}),
+			}
+		},
+		Err(e) => json!({
+			"jsonrpc": "2.0",
+			"id": null,
+			"error": { "code": -32700, "message": e.to_string() },
+		}),
+	};
+
+	let body = serde_json::to_vec(&response)?;
+	transport::write_message(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json", &body)
+}
+
+fn handle(keystore: &LocalKeystore, method: &str, params: &[Value]) -> Result<Value, String> {
+	let result = match method {
+		"keystore_publicKeys" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let keys: Vec<Bytes> = match crypto_type(&param::<String>(params, 1)?)? {
+				sr25519::CRYPTO_ID => raw(SyncCryptoStore::sr25519_public_keys(keystore, id)),
+				ed25519::CRYPTO_ID => raw(SyncCryptoStore::ed25519_public_keys(keystore, id)),
+				ecdsa::CRYPTO_ID => raw(SyncCryptoStore::ecdsa_public_keys(keystore, id)),
+				other => return Err(format!("unsupported crypto type {:?}", other)),
+			};
+			json!(keys)
+		},
+		"keystore_generate" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let seed = param::<Option<String>>(params, 2)?;
+			let seed = seed.as_deref();
+			let public = match crypto_type(&param::<String>(params, 1)?)? {
+				sr25519::CRYPTO_ID =>
+					SyncCryptoStore::sr25519_generate_new(keystore, id, seed).map(|k| k.to_raw_vec()),
+				ed25519::CRYPTO_ID =>
+					SyncCryptoStore::ed25519_generate_new(keystore, id, seed).map(|k| k.to_raw_vec()),
+				ecdsa::CRYPTO_ID =>
+					SyncCryptoStore::ecdsa_generate_new(keystore, id, seed).map(|k| k.to_raw_vec()),
+				other => return Err(format!("unsupported crypto type {:?}", other)),
+			}
+			.map_err(|e| e.to_string())?;
+			json!(Bytes(public))
+		},
+		"keystore_insert" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let suri = param::<String>(params, 1)?;
+			let public = param::<Bytes>(params, 2)?;
+			SyncCryptoStore::insert_unknown(keystore, id, &suri, &public)
+				.map_err(|_| "failed to insert key".to_string())?;
+			Value::Null
+		},
+		"keystore_hasKeys" => {
+			let keys = param::<Vec<(Bytes, String)>>(params, 0)?
+				.into_iter()
+				.map(|(public, id)| Ok((public.0, key_type(&id)?)))
+				.collect::<Result<Vec<_>, String>>()?;
+			json!(SyncCryptoStore::has_keys(keystore, &keys))
+		},
+		"keystore_sign" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let crypto = crypto_type(&param::<String>(params, 1)?)?;
+			let public = param::<Bytes>(params, 2)?;
+			let message = param::<Bytes>(params, 3)?;
+			let key = CryptoTypePublicPair(crypto, public.0);
+			SyncCryptoStore::sign_with(keystore, id, &key, &message)
+				.map(|signature| json!(signature.map(Bytes)))
+				.map_err(|e| e.to_string())?
+		},
+		"keystore_signPrehashed" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let public = ecdsa::Public::from_slice(&param::<Bytes>(params, 1)?)
+				.map_err(|_| "invalid ecdsa public key".to_string())?;
+			let hash: [u8; 32] = param::<Bytes>(params, 2)?
+				.0
+				.try_into()
+				.map_err(|_| "hash must be 32 bytes".to_string())?;
+			SyncCryptoStore::ecdsa_sign_prehashed(keystore, id, &public, &hash)
+				.map(|signature| json!(signature.map(|s| Bytes(s.0.to_vec()))))
+				.map_err(|e| e.to_string())?
+		},
+		"keystore_vrfSign" => {
+			let id = key_type(&param::<String>(params, 0)?)?;
+			let public = sr25519::Public::from_slice(&param::<Bytes>(params, 1)?)
+				.map_err(|_| "invalid sr25519 public key".to_string())?;
+			let transcript = beacon_transcript(&param::<Transcript>(params, 2)?)?;
+			SyncCryptoStore::sr25519_vrf_sign(keystore, id, &public, transcript)
+				.map(|signature| {
+					json!(signature.map(|s| Vrf {
+						output: Bytes(s.output.to_bytes().to_vec()),
+						proof: Bytes(s.proof.to_bytes().to_vec()),
+					}))
+				})
+				.map_err(|e| e.to_string())?
+		},
+		other => return Err(format!("unknown method {}", other)),
+	};
+	Ok(result)
+}
+
+/// `transcript`, if it is the randomness beacon's transcript of some slot.
+fn beacon_transcript(transcript: &Transcript) -> Result<VRFTranscriptData, String> {
+	let beacon = match &transcript.items[..] {
+		[(_, TranscriptValue::U64(slot)), (_, TranscriptValue::Bytes(previous))]
+			if previous.len() == 32 =>
+			transcript_data(*slot, &H256::from_slice(previous)),
+		_ => return Err("only the randomness beacon's VRF is signed".into()),
+	};
+	if Transcript::from(&beacon) != *transcript {
+		return Err("only the randomness beacon's VRF is signed".into())
+	}
+	Ok(beacon)
+}
+
+fn param<T: DeserializeOwned>(params: &[Value], index: usize) -> Result<T, String> {
+	serde_json::from_value(params.get(index).cloned().unwrap_or(Value::Null))
+		.map_err(|e| format!("invalid parameter {}: {}", index, e))
+}
+
+fn key_type(id: &str) -> Result<KeyTypeId, String> {
+	KeyTypeId::try_from(id).map_err(|_| format!("invalid key type {}", id))
+}
+
+fn crypto_type(id: &str) -> Result<CryptoTypeId, String> {
+	<[u8; 4]>::try_from(id.as_bytes())
+		.map(CryptoTypeId)
+		.map_err(|_| format!("invalid crypto type {}", id))
+}
+
+fn raw<P: ByteArray>(keys: Vec<P>) -> Vec<Bytes> {
+	keys.into_iter().map(|key| Bytes(key.to_raw_vec())).collect()
+}
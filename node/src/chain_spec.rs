@@ -1,5 +1,7 @@
 use hex_literal::hex;
+use sc_chain_spec::ChainSpecExtension;
 use sc_service::{ChainType, Properties};
+use serde::{Deserialize, Serialize};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -8,18 +10,91 @@ use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use sp_core::crypto::UncheckedInto;
 
 use global_network_runtime::{
-	currency::*, opaque::SessionKeys, AccountId, AuraConfig, BalancesConfig,
-	CouncilConfig, DemocracyConfig, GenesisConfig, GrandpaConfig,
+	currency::*, opaque::SessionKeys, AccountId, AuraConfig, Balance, BalancesConfig,
+	CouncilConfig, DemocracyConfig, EVMConfig, GenesisAccount, GenesisConfig, GrandpaConfig,
 	ImOnlineConfig, SessionConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig,
-	ValidatorSetConfig, WASM_BINARY,
+	system_contracts::{PRECOMPILES_WITH_CODE, SYSTEM_CONTRACTS}, ValidatorSetConfig,
+	PRECOMPILE_CODE, WASM_BINARY,
 };
-use std::{ default::Default};
+use sp_core::{Bytes, H160, H256, U256};
+use std::{borrow::Cow, collections::BTreeMap, default::Default, path::Path};
+
+use crate::rpc::dev_signer::EthDevSigner;
+use fc_rpc::EthSigner;
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Node-side settings carried in the chain spec, next to the genesis config.
+#[derive(Default, Clone, Serialize, Deserialize, ChainSpecExtension)]
+#[serde(rename_all = "camelCase")]
+pub struct Extensions {
+	/// GRANDPA finality gadget settings.
+	#[serde(default)]
+	pub grandpa: GrandpaParams,
+}
+
+/// GRANDPA settings that used to be hard-coded in the service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GrandpaParams {
+	/// How often vote messages are gossiped, in milliseconds.
+	pub gossip_duration_ms: u64,
+	/// Produce a justification at least every `justification_period` blocks.
+	pub justification_period: u32,
+	/// Whether the GRANDPA observer protocol is live on the network.
+	pub observer_enabled: bool,
+	/// Rules restricting which blocks voters may vote on, applied in order.
+	pub voting_rules: Vec<VotingRule>,
+}
+
+impl Default for GrandpaParams {
+	fn default() -> Self {
+		Self {
+			gossip_duration_ms: 333,
+			justification_period: 512,
+			observer_enabled: false,
+			// Same rules as `VotingRulesBuilder::default()`.
+			voting_rules: vec![
+				VotingRule::BeforeBestBlockBy(2),
+				VotingRule::ThreeQuartersOfTheUnfinalizedChain,
+			],
+		}
+	}
+}
+
+/// A GRANDPA voting rule, see `sc_finality_grandpa::VotingRule`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VotingRule {
+	/// Vote on a block at least this many blocks behind the best block.
+	BeforeBestBlockBy(u32),
+	/// Vote at most three quarters of the way into the unfinalized chain.
+	ThreeQuartersOfTheUnfinalizedChain,
+}
+
+impl std::str::FromStr for VotingRule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once('=') {
+			Some(("before-best-block-by", blocks)) => blocks
+				.parse()
+				.map(VotingRule::BeforeBestBlockBy)
+				.map_err(|_| format!("Invalid block count in voting rule: {}", s)),
+			None if s == "three-quarters-of-unfinalized-chain" =>
+				Ok(VotingRule::ThreeQuartersOfTheUnfinalizedChain),
+			_ => Err(format!(
+				"Unknown voting rule {}; expected `before-best-block-by=<blocks>` or \
+				 `three-quarters-of-unfinalized-chain`",
+				s
+			)),
+		}
+	}
+}
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
-pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
+pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig, Extensions>;
 
 // type AccountPublic = <Signature as Verify>::Signer;
 
@@ -35,6 +110,9 @@ const BALTATHAR: &str = "0xF3c25Ea246B52a901b47CDAE1ecD3039246Ab31d";
 const CHARLETH: &str = "0xac0103172516afe69E9F3D3EB451cb6382b3A0EB";
 const DOROTHY: &str = "0x2651E1424Fa908982eBAA6aaCdf899E8783B028f";
 
+/// Balance of each development `eth_sendTransaction` account, well above the existential deposit.
+const DEV_ACCOUNT_BALANCE: Balance = 1000 * GNF;
+
 pub fn public_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
@@ -78,6 +156,8 @@ pub fn public_config() -> Result<ChainSpec, String> {
 					array_bytes::hex_n_into_unchecked(CHARLETH),
 					array_bytes::hex_n_into_unchecked(DOROTHY),
 				],
+				// Keep the live genesis unchanged
+				BTreeMap::new(),
 				true,
 			)
 		},
@@ -91,7 +171,7 @@ pub fn public_config() -> Result<ChainSpec, String> {
 		None,
 		None,
 		Some(chainspec_properties()),
-		None,
+		Default::default(),
 	))
 }
 
@@ -107,7 +187,13 @@ pub fn chainspec_properties() -> Properties {
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
+	development_config_with(EVM_GENESIS_ARTIFACTS)
+}
+
+/// The development chain, with the EVM accounts of the genesis artifacts `evm_artifacts`.
+pub fn development_config_with(evm_artifacts: &str) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+	let evm_accounts = evm_genesis_accounts(evm_artifacts)?;
 
 	Ok(ChainSpec::from_genesis(
 		// Names
@@ -132,7 +218,11 @@ pub fn development_config() -> Result<ChainSpec, String> {
 					array_bytes::hex_n_into_unchecked(ALITH),
 					array_bytes::hex_n_into_unchecked(BALTATHAR),
 					array_bytes::hex_n_into_unchecked(CHARLETH),
-					array_bytes::hex_n_into_unchecked(DOROTHY),				],
+					array_bytes::hex_n_into_unchecked(DOROTHY),
+				],
+				// Accounts of the development `eth_sendTransaction` signer
+				EthDevSigner::new().accounts().into_iter().map(AccountId::from).collect(),
+				evm_accounts.clone(),
 				true,
 			)
 		},
@@ -146,12 +236,18 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		// Properties
 		Some(chainspec_properties()),
 		// Extensions
-		None,
+		Default::default(),
 	))
 }
 
 pub fn testnet_config() -> Result<ChainSpec, String> {
+	testnet_config_with(EVM_GENESIS_ARTIFACTS)
+}
+
+/// The testnet, with the EVM accounts of the genesis artifacts `evm_artifacts`.
+pub fn testnet_config_with(evm_artifacts: &str) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+	let evm_accounts = evm_genesis_accounts(evm_artifacts)?;
 
 	Ok(ChainSpec::from_genesis(
 		// Name
@@ -195,6 +291,8 @@ pub fn testnet_config() -> Result<ChainSpec, String> {
 					array_bytes::hex_n_into_unchecked(CHARLETH),
 					array_bytes::hex_n_into_unchecked(DOROTHY),
 				],
+				vec![],
+				evm_accounts.clone(),
 				true,
 			)
 		},
@@ -213,16 +311,95 @@ pub fn testnet_config() -> Result<ChainSpec, String> {
 		None,
 		Some(chainspec_properties()),
 		// Extensions
-		None,
+		Default::default(),
 	))
 }
 
+/// EVM accounts bundled with the node for the development and testnet genesis.
+const EVM_GENESIS_ARTIFACTS: &str = include_str!("../res/evm-genesis.json");
+
+/// The EVM genesis artifacts in the file at `path`, or the bundled ones without a path.
+pub fn evm_genesis_artifacts(path: Option<&Path>) -> Result<Cow<'static, str>, String> {
+	match path {
+		Some(path) => std::fs::read_to_string(path)
+			.map(Cow::Owned)
+			.map_err(|e| format!("Cannot read EVM genesis artifacts {}: {}", path.display(), e)),
+		None => Ok(Cow::Borrowed(EVM_GENESIS_ARTIFACTS)),
+	}
+}
+
+/// An EVM account as written in a genesis artifact. Numbers are `0x`-prefixed hex strings and
+/// every field is optional.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EvmGenesisArtifact {
+	#[serde(default)]
+	nonce: U256,
+	#[serde(default)]
+	balance: U256,
+	#[serde(default)]
+	code: Bytes,
+	#[serde(default)]
+	storage: BTreeMap<H256, H256>,
+}
+
+/// Parse EVM genesis accounts from a JSON object keyed by address, e.g.
+/// `{ "0x…": { "code": "0x…", "storage": { "0x…": "0x…" }, "balance": "0x0", "nonce": "0x1" } }`.
+pub fn evm_accounts_from_json(json: &str) -> Result<BTreeMap<H160, GenesisAccount>, String> {
+	let artifacts: BTreeMap<H160, EvmGenesisArtifact> =
+		serde_json::from_str(json).map_err(|e| format!("Invalid EVM genesis artifact: {}", e))?;
+
+	Ok(artifacts
+		.into_iter()
+		.map(|(address, artifact)| {
+			let account = GenesisAccount {
+				nonce: artifact.nonce,
+				balance: artifact.balance,
+				storage: artifact.storage,
+				code: artifact.code.0,
+			};
+			(address, account)
+		})
+		.collect())
+}
+
+/// Accounts present in the EVM from genesis: the precompiles Solidity calls, which get
+/// placeholder code so that contracts can call them like any deployed contract, the system
+/// contracts, and the accounts in `artifacts`.
+pub fn evm_genesis_accounts(artifacts: &str) -> Result<BTreeMap<H160, GenesisAccount>, String> {
+	let mut reserved = Vec::new();
+	for address in PRECOMPILES_WITH_CODE {
+		reserved.push((address, PRECOMPILE_CODE.to_vec(), U256::zero()));
+	}
+	for (address, code) in SYSTEM_CONTRACTS {
+		// Contract accounts start at nonce 1 (EIP-161).
+		reserved.push((address, code.to_vec(), U256::one()));
+	}
+
+	let mut accounts = evm_accounts_from_json(artifacts)?;
+	for (address, code, nonce) in reserved {
+		if accounts.contains_key(&address) {
+			return Err(format!("EVM genesis artifact overrides reserved address {:?}", address))
+		}
+		let account = GenesisAccount {
+			nonce,
+			balance: Default::default(),
+			storage: Default::default(),
+			code,
+		};
+		accounts.insert(address, account);
+	}
+	Ok(accounts)
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AccountId, AuraId, GrandpaId, ImOnlineId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	dev_accounts: Vec<AccountId>,
+	evm_accounts: BTreeMap<H160, GenesisAccount>,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	let num_endowed_accounts = endowed_accounts.len();
@@ -248,6 +425,7 @@ fn testnet_genesis(
 						(k.clone(), 0 * GNF)
 					}
 				})
+				.chain(dev_accounts.into_iter().map(|k| (k, DEV_ACCOUNT_BALANCE)))
 				.collect(),
 		},
 
@@ -285,7 +463,7 @@ fn testnet_genesis(
 		treasury: Default::default(),
 		transaction_payment: Default::default(),
 
-		evm : Default::default(),
+		evm: EVMConfig { accounts: evm_accounts },
 
 		session: SessionConfig {
 			keys: initial_authorities
@@ -304,6 +482,8 @@ fn testnet_genesis(
 
 		ethereum: Default::default(),
 		base_fee: Default::default(),
+		evm_deployers: Default::default(),
+		assets: Default::default(),
 		
 	}
 }
@@ -313,6 +493,7 @@ fn mainnet_genesis(
 	initial_authorities: Vec<(AccountId, AuraId, GrandpaId, ImOnlineId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	evm_accounts: BTreeMap<H160, GenesisAccount>,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	let num_endowed_accounts = endowed_accounts.len();
@@ -377,7 +558,7 @@ fn mainnet_genesis(
 		},
 
 		transaction_payment: Default::default(),
-		evm : Default::default(),
+		evm: EVMConfig { accounts: evm_accounts },
 
 		session: SessionConfig {
 			keys: initial_authorities
@@ -393,5 +574,7 @@ fn mainnet_genesis(
 
 		ethereum: Default::default(),
 		base_fee: Default::default(),
+		evm_deployers: Default::default(),
+		assets: Default::default(),
 	}
 }
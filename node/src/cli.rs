@@ -1,4 +1,7 @@
 use sc_cli::RunCmd;
+use std::path::PathBuf;
+
+use crate::chain_spec::{GrandpaParams, VotingRule};
 
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
@@ -7,6 +10,64 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	#[clap(flatten)]
+	pub remote_keystore: crate::remote_keystore::RemoteKeystoreParams,
+
+	#[clap(flatten)]
+	pub grandpa: GrandpaCli,
+
+	#[clap(flatten)]
+	pub eth: crate::eth::EthConfiguration,
+
+	/// EVM genesis artifacts loaded into the `dev` and `testnet` genesis instead of the bundled
+	/// `res/evm-genesis.json`.
+	///
+	/// A JSON object keyed by address, e.g. `{ "0x…": { "code": "0x…", "nonce": "0x1" } }`, with
+	/// the optional fields `storage`, `balance` and `nonce` next to `code`.
+	#[arg(long, value_name = "PATH", global = true)]
+	pub evm_genesis: Option<PathBuf>,
+}
+
+/// Overrides for the GRANDPA settings of the chain spec.
+#[derive(Debug, Clone, clap::Args)]
+pub struct GrandpaCli {
+	/// How often GRANDPA vote messages are gossiped, in milliseconds.
+	#[arg(long, value_name = "MS")]
+	pub grandpa_gossip_duration: Option<u64>,
+
+	/// Produce a GRANDPA justification at least every this many blocks.
+	#[arg(long, value_name = "BLOCKS", value_parser = clap::value_parser!(u32).range(1..))]
+	pub grandpa_justification_period: Option<u32>,
+
+	/// Whether the GRANDPA observer protocol is live on the network.
+	#[arg(long, value_name = "BOOL")]
+	pub grandpa_observer_enabled: Option<bool>,
+
+	/// GRANDPA voting rule, replacing the chain spec rules when given. May be repeated.
+	///
+	/// Either `before-best-block-by=<blocks>` or `three-quarters-of-unfinalized-chain`.
+	#[arg(long, value_name = "RULE")]
+	pub grandpa_voting_rule: Vec<VotingRule>,
+}
+
+impl GrandpaCli {
+	/// Apply the overrides given on the command line to `params`.
+	pub fn apply(&self, mut params: GrandpaParams) -> GrandpaParams {
+		if let Some(gossip_duration_ms) = self.grandpa_gossip_duration {
+			params.gossip_duration_ms = gossip_duration_ms;
+		}
+		if let Some(justification_period) = self.grandpa_justification_period {
+			params.justification_period = justification_period;
+		}
+		if let Some(observer_enabled) = self.grandpa_observer_enabled {
+			params.observer_enabled = observer_enabled;
+		}
+		if !self.grandpa_voting_rule.is_empty() {
+			params.voting_rules = self.grandpa_voting_rule.clone();
+		}
+		params
+	}
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -50,4 +111,18 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Session key management for validators.
+	#[clap(subcommand)]
+	Session(crate::session::SessionCmd),
+
+	/// State snapshots for `upgrade-check`.
+	#[clap(subcommand)]
+	Snapshot(crate::upgrade_check::SnapshotCmd),
+
+	/// Run the migrations of a new runtime against a snapshot, offline.
+	UpgradeCheck(crate::upgrade_check::UpgradeCheckCmd),
+
+	/// Compare the metadata of two runtimes and check their versions were bumped.
+	MetadataDiff(crate::upgrade_check::MetadataDiffCmd),
 }
@@ -3,6 +3,7 @@ use crate::{
 	chain_spec,
 	cli::{Cli, Subcommand},
 	service,
+	upgrade_check::SnapshotCmd,
 };
 use frame_benchmarking_cli::{BenchmarkCmd, SUBSTRATE_REFERENCE_HARDWARE};
 use global_network_runtime::{Block};
@@ -36,9 +37,13 @@ impl SubstrateCli for Cli {
 	}
 
 	fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		if self.evm_genesis.is_some() && !matches!(id, "dev" | "testnet") {
+			return Err("--evm-genesis only applies to the dev and testnet chains".into())
+		}
+		let evm_artifacts = chain_spec::evm_genesis_artifacts(self.evm_genesis.as_deref())?;
 		Ok(match id {
-			"dev" => Box::new(chain_spec::development_config()?),
-			"testnet" => Box::new(chain_spec::testnet_config()?),
+			"dev" => Box::new(chain_spec::development_config_with(&evm_artifacts)?),
+			"testnet" => Box::new(chain_spec::testnet_config_with(&evm_artifacts)?),
 			"live" => Box::new(chain_spec::public_config()?),
 			path =>
 				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
@@ -64,21 +69,23 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, .. } =
-					service::new_partial(&config)?;
+					service::new_partial(&config, &cli.eth)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
 		Some(Subcommand::ExportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				let PartialComponents { client, task_manager, .. } =
+					service::new_partial(&config, &cli.eth)?;
 				Ok((cmd.run(client, config.database), task_manager))
 			})
 		},
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
-				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				let PartialComponents { client, task_manager, .. } =
+					service::new_partial(&config, &cli.eth)?;
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
@@ -86,7 +93,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, .. } =
-					service::new_partial(&config)?;
+					service::new_partial(&config, &cli.eth)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
@@ -98,7 +105,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, backend, .. } =
-					service::new_partial(&config)?;
+					service::new_partial(&config, &cli.eth)?;
 				let aux_revert = Box::new(|client, _, blocks| {
 					sc_finality_grandpa::revert(client, blocks)?;
 					Ok(())
@@ -125,7 +132,8 @@ pub fn run() -> sc_cli::Result<()> {
 						cmd.run::<Block, service::ExecutorDispatch>(config)
 					},
 					BenchmarkCmd::Block(cmd) => {
-						let PartialComponents { client, .. } = service::new_partial(&config)?;
+						let PartialComponents { client, .. } =
+							service::new_partial(&config, &cli.eth)?;
 						cmd.run(client)
 					},
 					#[cfg(not(feature = "runtime-benchmarks"))]
@@ -136,14 +144,14 @@ pub fn run() -> sc_cli::Result<()> {
 					#[cfg(feature = "runtime-benchmarks")]
 					BenchmarkCmd::Storage(cmd) => {
 						let PartialComponents { client, backend, .. } =
-							service::new_partial(&config)?;
+							service::new_partial(&config, &cli.eth)?;
 						let db = backend.expose_db();
 						let storage = backend.expose_storage();
 
 						cmd.run(config, client, db, storage)
 					},
 					BenchmarkCmd::Overhead(_cmd) => {
-						// let PartialComponents { client, .. } = service::new_partial(&config)?;
+						// let PartialComponents { client, .. } = service::new_partial(&config, &cli.eth)?;
 						// let ext_builder = RemarkBuilder::new(client.clone());
 						//
 						// cmd.run(
@@ -156,7 +164,7 @@ pub fn run() -> sc_cli::Result<()> {
 						Ok(())
 					},
 					BenchmarkCmd::Extrinsic(_cmd) => {
-						// let PartialComponents { client, .. } = service::new_partial(&config)?;
+						// let PartialComponents { client, .. } = service::new_partial(&config, &cli.eth)?;
 						// // Register the *Remark* and *TKA* builders.
 						// let ext_factory = ExtrinsicFactory(vec![
 						// 	Box::new(RemarkBuilder::new(client.clone())),
@@ -196,10 +204,24 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::Session(cmd)) => cmd.run(),
+		Some(Subcommand::Snapshot(SnapshotCmd::Create(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config, &cli.eth)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::UpgradeCheck(cmd)) => cmd.run(),
+		Some(Subcommand::MetadataDiff(cmd)) => cmd.run(),
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let remote_keystore = cli.remote_keystore.clone();
+			let grandpa = cli.grandpa.clone();
+			let eth = cli.eth.clone();
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				service::new_full(config, remote_keystore, grandpa, eth)
+					.map_err(sc_cli::Error::Service)
 			})
 		},
 	}
@@ -0,0 +1,48 @@
+//! Ethereum compatibility settings of the node.
+
+pub mod log_index;
+
+/// Options of the Frontier database and the Ethereum RPC.
+#[derive(Debug, Clone, clap::Args)]
+pub struct EthConfiguration {
+	/// Cache size of the Frontier mapping database, in MiB. Only used by RocksDB.
+	#[arg(long, value_name = "MiB", default_value_t = 0)]
+	pub frontier_db_cache_size: usize,
+
+	/// Keep an SQLite index of the logs of canonical blocks and answer `eth_getLogs` from it.
+	///
+	/// Queries answered by the index are not bound by `--max-log-block-range`. The index reads
+	/// the state of every block, so the node has to keep it with `--state-pruning archive` or
+	/// `archive-canonical`.
+	#[arg(long)]
+	pub frontier_log_index: bool,
+
+	/// Maximum number of logs returned by a single `eth_getLogs` query.
+	#[arg(long, default_value_t = 10000)]
+	pub max_past_logs: u32,
+
+	/// Maximum number of blocks an `eth_getLogs` query may span.
+	#[arg(long, default_value_t = 1024)]
+	pub max_log_block_range: u32,
+
+	/// Maximum number of blocks kept in the `eth_feeHistory` cache.
+	#[arg(long, default_value_t = 2048)]
+	pub fee_history_limit: u64,
+
+	/// Size in bytes of the LRU cache for block data.
+	#[arg(long, default_value_t = 50_000_000)]
+	pub eth_log_block_cache: usize,
+
+	/// Size in bytes of the LRU cache for transaction statuses data.
+	#[arg(long, default_value_t = 50_000_000)]
+	pub eth_statuses_cache: usize,
+
+	/// Multiplier of the block gas limit that `eth_call` and `eth_estimateGas` may use.
+	#[arg(long, default_value_t = 10)]
+	pub execute_gas_limit_multiplier: u64,
+
+	/// Sign `eth_sendTransaction` with the well-known development accounts. Always enabled on
+	/// development chains.
+	#[arg(long)]
+	pub enable_dev_signer: bool,
+}
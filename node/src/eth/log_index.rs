@@ -0,0 +1,347 @@
+//! SQLite index of Ethereum logs.
+//!
+//! Frontier answers `eth_getLogs` by reading the transaction statuses of every block in the
+//! requested range. With `--frontier-log-index` the node also keeps the logs of canonical blocks
+//! in an SQLite database under `frontier/sql`, indexed by address and by each topic position, so
+//! such queries become a single indexed lookup.
+//!
+//! Indexing reads each block's Ethereum block and transaction statuses from its state, so the
+//! index is only available on archive nodes. With pruned state, an old block would be recorded
+//! as having no Ethereum block and queries over it would come back empty.
+//!
+//! The index follows the best chain: [`sync_task`] indexes new best blocks and drops blocks that
+//! were retracted by a re-org. Queries over blocks that are not indexed yet fall back to
+//! Frontier.
+
+use std::{
+	path::Path,
+	sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use fc_rpc::{frontier_backend_client, OverrideHandle};
+use fc_rpc_core::types::{Bytes, Log};
+use fp_rpc::TransactionStatus;
+use futures::StreamExt;
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
+use sc_client_api::{
+	backend::{Backend, StateBackend, StorageProvider},
+	client::BlockchainEvents,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Block as BlockT, UniqueSaturatedInto},
+};
+
+const LOG_TARGET: &str = "frontier-log-index";
+
+/// Blocks indexed in one database transaction.
+const BATCH_SIZE: u64 = 256;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		block_number INTEGER PRIMARY KEY,
+		substrate_hash BLOB NOT NULL,
+		ethereum_hash BLOB
+	);
+	CREATE TABLE IF NOT EXISTS logs (
+		block_number INTEGER NOT NULL,
+		log_index INTEGER NOT NULL,
+		transaction_hash BLOB NOT NULL,
+		transaction_index INTEGER NOT NULL,
+		transaction_log_index INTEGER NOT NULL,
+		address BLOB NOT NULL,
+		topic_0 BLOB,
+		topic_1 BLOB,
+		topic_2 BLOB,
+		topic_3 BLOB,
+		data BLOB NOT NULL,
+		PRIMARY KEY (block_number, log_index)
+	);
+	CREATE INDEX IF NOT EXISTS logs_address ON logs (address, block_number);
+	CREATE INDEX IF NOT EXISTS logs_topic_0 ON logs (topic_0, block_number);
+	CREATE INDEX IF NOT EXISTS logs_topic_1 ON logs (topic_1, block_number);
+	CREATE INDEX IF NOT EXISTS logs_topic_2 ON logs (topic_2, block_number);
+	CREATE INDEX IF NOT EXISTS logs_topic_3 ON logs (topic_3, block_number);
+";
+
+/// The logs an `eth_getLogs` query asks for, in terms the index understands.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+	/// First block of the range.
+	pub from_block: u64,
+	/// Last block of the range, inclusive.
+	pub to_block: u64,
+	/// Emitting contracts, any of them. Empty matches every address.
+	pub addresses: Vec<H160>,
+	/// Accepted values for each topic position. An empty set matches any topic.
+	pub topics: Vec<Vec<H256>>,
+}
+
+/// An SQLite database of the logs of canonical blocks.
+pub struct LogIndex {
+	connection: Mutex<Connection>,
+}
+
+impl LogIndex {
+	/// Open or create the index at `path`.
+	pub fn open(path: &Path) -> Result<Self, String> {
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)
+				.map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+		}
+		let connection = Connection::open(path)
+			.map_err(|e| format!("Failed to open log index {}: {}", path.display(), e))?;
+		connection
+			.execute_batch("PRAGMA journal_mode = WAL;")
+			.and_then(|()| connection.execute_batch(SCHEMA))
+			.map_err(|e| format!("Failed to set up log index: {}", e))?;
+		Ok(Self { connection: Mutex::new(connection) })
+	}
+
+	fn connection(&self) -> MutexGuard<'_, Connection> {
+		self.connection.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// The last indexed block, as its number and Substrate hash.
+	pub fn tip(&self) -> Result<Option<(u64, H256)>, String> {
+		self.connection()
+			.query_row(
+				"SELECT block_number, substrate_hash FROM blocks
+					ORDER BY block_number DESC LIMIT 1",
+				[],
+				|row| Ok((row.get::<_, i64>(0)? as u64, h256(row.get(1)?))),
+			)
+			.optional()
+			.map_err(sql_err)
+	}
+
+	/// Substrate hash of the indexed block `number`.
+	pub fn substrate_hash(&self, number: u64) -> Result<Option<H256>, String> {
+		self.connection()
+			.query_row(
+				"SELECT substrate_hash FROM blocks WHERE block_number = ?1",
+				[number as i64],
+				|row| Ok(h256(row.get(0)?)),
+			)
+			.optional()
+			.map_err(sql_err)
+	}
+
+	/// Add blocks on top of the index. `blocks` holds, in order, each block's number, Substrate
+	/// hash, and Ethereum hash and transaction statuses if it has an Ethereum block.
+	pub fn insert_blocks(
+		&self,
+		blocks: &[(u64, H256, Option<(H256, Vec<TransactionStatus>)>)],
+	) -> Result<(), String> {
+		let mut connection = self.connection();
+		let transaction = connection.transaction().map_err(sql_err)?;
+		{
+			let mut insert_block = transaction
+				.prepare_cached(
+					"INSERT OR REPLACE INTO blocks (block_number, substrate_hash, ethereum_hash)
+						VALUES (?1, ?2, ?3)",
+				)
+				.map_err(sql_err)?;
+			let mut insert_log = transaction
+				.prepare_cached(
+					"INSERT OR REPLACE INTO logs (
+						block_number, log_index, transaction_hash, transaction_index,
+						transaction_log_index, address, topic_0, topic_1, topic_2, topic_3, data
+					) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+				)
+				.map_err(sql_err)?;
+
+			for (number, substrate_hash, ethereum) in blocks {
+				let number = *number as i64;
+				let ethereum_hash = ethereum.as_ref().map(|(hash, _)| hash.as_bytes());
+				insert_block
+					.execute(params![number, substrate_hash.as_bytes(), ethereum_hash])
+					.map_err(sql_err)?;
+
+				let statuses = ethereum.iter().flat_map(|(_, statuses)| statuses);
+				let logs = statuses.flat_map(|status| {
+					status.logs.iter().enumerate().map(move |(index, log)| (status, index, log))
+				});
+				for (log_index, (status, transaction_log_index, log)) in logs.enumerate() {
+					let topic = |position: usize| log.topics.get(position).map(H256::as_bytes);
+					insert_log
+						.execute(params![
+							number,
+							log_index as i64,
+							status.transaction_hash.as_bytes(),
+							status.transaction_index as i64,
+							transaction_log_index as i64,
+							log.address.as_bytes(),
+							topic(0),
+							topic(1),
+							topic(2),
+							topic(3),
+							log.data,
+						])
+						.map_err(sql_err)?;
+				}
+			}
+		}
+		transaction.commit().map_err(sql_err)
+	}
+
+	/// Forget every block above `number`.
+	pub fn revert_to(&self, number: u64) -> Result<(), String> {
+		let mut connection = self.connection();
+		let transaction = connection.transaction().map_err(sql_err)?;
+		transaction
+			.execute("DELETE FROM logs WHERE block_number > ?1", [number as i64])
+			.and_then(|_| {
+				transaction.execute("DELETE FROM blocks WHERE block_number > ?1", [number as i64])
+			})
+			.map_err(sql_err)?;
+		transaction.commit().map_err(sql_err)
+	}
+
+	/// The logs matching `query`, in chain order. Fails if there are more than `limit`.
+	pub fn logs(&self, query: &LogQuery, limit: u32) -> Result<Vec<Log>, String> {
+		let mut sql = String::from(
+			"SELECT logs.block_number, blocks.ethereum_hash, logs.log_index,
+				logs.transaction_hash, logs.transaction_index, logs.transaction_log_index,
+				logs.address, logs.topic_0, logs.topic_1, logs.topic_2, logs.topic_3, logs.data
+			FROM logs JOIN blocks ON blocks.block_number = logs.block_number
+			WHERE logs.block_number BETWEEN ? AND ?",
+		);
+		let mut values =
+			vec![Value::Integer(query.from_block as i64), Value::Integer(query.to_block as i64)];
+
+		let mut any_of = |column: &str, accepted: Vec<&[u8]>| {
+			if accepted.is_empty() {
+				return
+			}
+			let placeholders = vec!["?"; accepted.len()].join(", ");
+			sql.push_str(&format!(" AND logs.{} IN ({})", column, placeholders));
+			values.extend(accepted.into_iter().map(|value| Value::Blob(value.to_vec())));
+		};
+		any_of("address", query.addresses.iter().map(H160::as_bytes).collect());
+		for (position, topics) in query.topics.iter().enumerate().take(4) {
+			any_of(&format!("topic_{}", position), topics.iter().map(H256::as_bytes).collect());
+		}
+		// One more than allowed, to tell an exact fit from an overflow.
+		sql.push_str(" ORDER BY logs.block_number, logs.log_index LIMIT ?");
+		values.push(Value::Integer(limit as i64 + 1));
+
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(&sql).map_err(sql_err)?;
+		let logs = statement
+			.query_map(params_from_iter(values), |row| {
+				let topics = (7_usize..11)
+					.map(|column| row.get::<_, Option<Vec<u8>>>(column))
+					.collect::<Result<Vec<_>, _>>()?;
+				Ok(Log {
+					address: H160::from_slice(&row.get::<_, Vec<u8>>(6)?),
+					topics: topics.into_iter().flatten().map(h256).collect(),
+					data: Bytes(row.get(11)?),
+					block_hash: row.get::<_, Option<Vec<u8>>>(1)?.map(h256),
+					block_number: Some(U256::from(row.get::<_, i64>(0)?)),
+					transaction_hash: Some(h256(row.get(3)?)),
+					transaction_index: Some(U256::from(row.get::<_, i64>(4)?)),
+					log_index: Some(U256::from(row.get::<_, i64>(2)?)),
+					transaction_log_index: Some(U256::from(row.get::<_, i64>(5)?)),
+					removed: false,
+				})
+			})
+			.and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+			.map_err(sql_err)?;
+
+		if logs.len() > limit as usize {
+			return Err(format!("query returned more than {} results", limit))
+		}
+		Ok(logs)
+	}
+}
+
+fn h256(bytes: Vec<u8>) -> H256 {
+	H256::from_slice(&bytes)
+}
+
+fn sql_err(e: rusqlite::Error) -> String {
+	format!("Log index error: {}", e)
+}
+
+/// Index the best chain into `index`, then keep following it.
+pub async fn sync_task<B, C, BE>(
+	client: Arc<C>,
+	overrides: Arc<OverrideHandle<B>>,
+	index: Arc<LogIndex>,
+) where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: HeaderBackend<B> + StorageProvider<B, BE> + BlockchainEvents<B> + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let mut notifications = client.import_notification_stream();
+	loop {
+		if let Err(e) = sync(client.as_ref(), overrides.as_ref(), index.as_ref()) {
+			log::warn!(target: LOG_TARGET, "Failed to index logs: {}", e);
+		}
+		loop {
+			match notifications.next().await {
+				Some(notification) if notification.is_new_best => break,
+				Some(_) => continue,
+				None => return,
+			}
+		}
+	}
+}
+
+/// Bring `index` in line with the current best chain of `client`.
+pub fn sync<B, C, BE>(
+	client: &C,
+	overrides: &OverrideHandle<B>,
+	index: &LogIndex,
+) -> Result<(), String>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: HeaderBackend<B> + StorageProvider<B, BE> + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let canonical = |number: u64| {
+		client
+			.hash(number.unique_saturated_into())
+			.map_err(|e| format!("Failed to read the block hash of #{}: {}", number, e))
+	};
+
+	// Drop the blocks retracted since the last run.
+	let mut next = 0;
+	if let Some((mut number, hash)) = index.tip()? {
+		let mut hash = Some(hash);
+		while number > 0 && canonical(number)? != hash {
+			number -= 1;
+			hash = index.substrate_hash(number)?;
+		}
+		index.revert_to(number)?;
+		next = if canonical(number)? == hash { number + 1 } else { number };
+	}
+
+	let best: u64 = client.info().best_number.unique_saturated_into();
+	while next <= best {
+		let last = best.min(next + BATCH_SIZE - 1);
+		let mut blocks = Vec::new();
+		for number in next..=last {
+			let hash = match canonical(number)? {
+				Some(hash) => hash,
+				None => return Ok(()),
+			};
+			let id = BlockId::Hash(hash);
+			let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(client, id);
+			let handler = overrides.schemas.get(&schema).unwrap_or(&overrides.fallback);
+			let ethereum = handler
+				.current_block(&id)
+				.zip(handler.current_transaction_statuses(&id))
+				.map(|(block, statuses)| (block.header.hash(), statuses));
+			blocks.push((number, hash, ethereum));
+		}
+		index.insert_blocks(&blocks)?;
+		next = last + 1;
+	}
+	Ok(())
+}
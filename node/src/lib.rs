@@ -1,4 +1,9 @@
 pub mod chain_spec;
 pub mod rpc;
+pub mod remote_keystore;
 pub mod service;
 pub mod cli;
+pub mod eth;
+pub mod session;
+pub mod metadata_diff;
+pub mod upgrade_check;
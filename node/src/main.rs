@@ -7,7 +7,12 @@ mod service;
 mod benchmarking;
 mod cli;
 mod command;
+mod eth;
+mod metadata_diff;
 mod rpc;
+mod remote_keystore;
+mod session;
+mod upgrade_check;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
@@ -0,0 +1,360 @@
+//! Compare the metadata of two runtimes.
+//!
+//! Types are compared by shape rather than by id, since ids are only meaningful inside one
+//! metadata's type registry. A shape spells out the fields and variants of a type, down to the
+//! primitives, so a renamed or reordered field shows up as a change of every call or storage
+//! entry that uses the type. A type nested in itself is only named the second time, as its
+//! first occurrence already spells it out. Shapes cut short at [`MAX_SHAPE_DEPTH`] cannot be
+//! compared in full, and are reported.
+
+use std::{collections::BTreeMap, fmt};
+
+use frame_metadata::{
+	v14::{PalletMetadata, StorageEntryMetadata, StorageEntryType},
+	RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef};
+use sp_core::Decode;
+
+/// How deep type shapes are spelled out.
+const MAX_SHAPE_DEPTH: usize = 32;
+
+/// Marks a shape cut short at [`MAX_SHAPE_DEPTH`].
+const TRUNCATED: &str = "<truncated>";
+
+/// Metadata of a runtime, as returned by `Metadata_metadata`.
+pub struct Metadata(frame_metadata::v14::RuntimeMetadataV14);
+
+impl Metadata {
+	/// Decode the SCALE encoded `OpaqueMetadata` returned by the runtime.
+	pub fn decode(encoded: &[u8]) -> Result<Self, String> {
+		let opaque = Vec::<u8>::decode(&mut &encoded[..])
+			.map_err(|e| format!("invalid OpaqueMetadata: {}", e))?;
+		let prefixed = RuntimeMetadataPrefixed::decode(&mut &opaque[..])
+			.map_err(|e| format!("invalid runtime metadata: {}", e))?;
+		match prefixed.1 {
+			RuntimeMetadata::V14(metadata) => Ok(Self(metadata)),
+			other => Err(format!("unsupported metadata version {}", other.version())),
+		}
+	}
+
+	/// The SCALE encoded value of constant `name` of `pallet`.
+	pub fn constant(&self, pallet: &str, name: &str) -> Option<&[u8]> {
+		self.pallet(pallet)?
+			.constants
+			.iter()
+			.find(|constant| constant.name == name)
+			.map(|constant| &constant.value[..])
+	}
+
+	fn pallet(&self, name: &str) -> Option<&PalletMetadata<PortableForm>> {
+		self.0.pallets.iter().find(|pallet| pallet.name == name)
+	}
+
+	fn types(&self) -> &PortableRegistry {
+		&self.0.types
+	}
+
+	/// Calls of `pallet` by name, with their index and argument shapes.
+	fn calls(&self, pallet: &PalletMetadata<PortableForm>) -> BTreeMap<String, (u8, String)> {
+		let Some(calls) = &pallet.calls else { return BTreeMap::new() };
+		let Some(TypeDef::Variant(variants)) =
+			self.types().resolve(calls.ty.id()).map(|ty| ty.type_def())
+		else {
+			return BTreeMap::new()
+		};
+		variants
+			.variants()
+			.iter()
+			.map(|call| {
+				(call.name().clone(), (call.index(), self.fields_shape(call.fields(), &[])))
+			})
+			.collect()
+	}
+
+	/// Storage entries of `pallet` by name, with their shapes.
+	fn storage(&self, pallet: &PalletMetadata<PortableForm>) -> BTreeMap<String, String> {
+		pallet
+			.storage
+			.iter()
+			.flat_map(|storage| storage.entries.iter())
+			.map(|entry| (entry.name.clone(), self.entry_shape(entry)))
+			.collect()
+	}
+
+	fn signed_extensions(&self) -> Vec<String> {
+		self.0
+			.extrinsic
+			.signed_extensions
+			.iter()
+			.map(|extension| {
+				format!(
+					"{}({}; {})",
+					extension.identifier,
+					self.shape(extension.ty.id(), &[]),
+					self.shape(extension.additional_signed.id(), &[]),
+				)
+			})
+			.collect()
+	}
+
+	fn entry_shape(&self, entry: &StorageEntryMetadata<PortableForm>) -> String {
+		match &entry.ty {
+			StorageEntryType::Plain(value) =>
+				format!("{:?} {}", entry.modifier, self.shape(value.id(), &[])),
+			StorageEntryType::Map { hashers, key, value } => format!(
+				"{:?} map {:?} {} => {}",
+				entry.modifier,
+				hashers,
+				self.shape(key.id(), &[]),
+				self.shape(value.id(), &[]),
+			),
+		}
+	}
+
+	fn fields_shape(&self, fields: &[Field<PortableForm>], outer: &[u32]) -> String {
+		let fields = fields
+			.iter()
+			.map(|field| match field.name() {
+				Some(name) => format!("{}: {}", name, self.shape(field.ty().id(), outer)),
+				None => self.shape(field.ty().id(), outer),
+			})
+			.collect::<Vec<_>>();
+		format!("({})", fields.join(", "))
+	}
+
+	/// Spell out the type `id`, nested in the types `outer`.
+	///
+	/// Call enums nested in other types are only named: their calls are compared on their own,
+	/// and spelling them out would report a change of `Sudo::sudo` for every changed call.
+	fn shape(&self, id: u32, outer: &[u32]) -> String {
+		let Some(ty) = self.types().resolve(id) else { return format!("<type {}>", id) };
+		let path = ty.path().segments().join("::");
+		let is_call = ty.path().segments().last().map_or(false, |name| name.ends_with("Call"));
+		if (!outer.is_empty() && is_call) || outer.contains(&id) {
+			return path
+		}
+		if outer.len() >= MAX_SHAPE_DEPTH {
+			return format!("{}{}", path, TRUNCATED)
+		}
+
+		let outer = &[outer, &[id]].concat()[..];
+		match ty.type_def() {
+			TypeDef::Composite(composite) =>
+				format!("{}{}", path, self.fields_shape(composite.fields(), outer)),
+			TypeDef::Variant(variants) => {
+				let variants = variants
+					.variants()
+					.iter()
+					.map(|variant| {
+						format!(
+							"{}@{}{}",
+							variant.name(),
+							variant.index(),
+							self.fields_shape(variant.fields(), outer)
+						)
+					})
+					.collect::<Vec<_>>();
+				format!("{}{{{}}}", path, variants.join(" | "))
+			},
+			TypeDef::Sequence(sequence) =>
+				format!("Vec<{}>", self.shape(sequence.type_param().id(), outer)),
+			TypeDef::Array(array) =>
+				format!("[{}; {}]", self.shape(array.type_param().id(), outer), array.len()),
+			TypeDef::Tuple(tuple) => {
+				let fields = tuple
+					.fields()
+					.iter()
+					.map(|field| self.shape(field.id(), outer))
+					.collect::<Vec<_>>();
+				format!("({})", fields.join(", "))
+			},
+			TypeDef::Primitive(primitive) => format!("{:?}", primitive).to_lowercase(),
+			TypeDef::Compact(compact) =>
+				format!("Compact<{}>", self.shape(compact.type_param().id(), outer)),
+			TypeDef::BitSequence(_) => "BitSequence".into(),
+		}
+	}
+}
+
+/// A difference between two runtimes' metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+	PalletAdded { pallet: String, index: u8 },
+	PalletRemoved { pallet: String, had_calls: bool },
+	PalletIndexChanged { pallet: String, old: u8, new: u8 },
+	CallAdded { pallet: String, call: String, index: u8 },
+	CallRemoved { pallet: String, call: String },
+	CallIndexChanged { pallet: String, call: String, old: u8, new: u8 },
+	CallArgumentsChanged { pallet: String, call: String, old: String, new: String },
+	CallArgumentsTruncated { pallet: String, call: String },
+	StorageAdded { pallet: String, entry: String },
+	StorageRemoved { pallet: String, entry: String },
+	StorageChanged { pallet: String, entry: String, old: String, new: String },
+	StorageTruncated { pallet: String, entry: String },
+	SignedExtensionsChanged { old: Vec<String>, new: Vec<String> },
+}
+
+impl Change {
+	/// Whether transactions signed against the old runtime stop decoding, or decode to a
+	/// different call, under the new one. Such changes need a `transaction_version` bump, so
+	/// offline signers know to refresh their metadata.
+	///
+	/// Call arguments whose shape was cut short count as breaking, as a change below the cut
+	/// cannot be ruled out.
+	pub fn breaks_transactions(&self) -> bool {
+		match self {
+			Change::PalletRemoved { had_calls, .. } => *had_calls,
+			Change::PalletIndexChanged { .. } |
+			Change::CallRemoved { .. } |
+			Change::CallIndexChanged { .. } |
+			Change::CallArgumentsChanged { .. } |
+			Change::CallArgumentsTruncated { .. } |
+			Change::SignedExtensionsChanged { .. } => true,
+			Change::PalletAdded { .. } |
+			Change::CallAdded { .. } |
+			Change::StorageAdded { .. } |
+			Change::StorageRemoved { .. } |
+			Change::StorageChanged { .. } |
+			Change::StorageTruncated { .. } => false,
+		}
+	}
+}
+
+impl fmt::Display for Change {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Change::PalletAdded { pallet, index } =>
+				write!(f, "pallet {} added at {}", pallet, index),
+			Change::PalletRemoved { pallet, had_calls: false } =>
+				write!(f, "pallet {} removed", pallet),
+			Change::PalletRemoved { pallet, had_calls: true } =>
+				write!(f, "pallet {} removed with its calls", pallet),
+			Change::PalletIndexChanged { pallet, old, new } =>
+				write!(f, "pallet {} moved from index {} to {}", pallet, old, new),
+			Change::CallAdded { pallet, call, index } =>
+				write!(f, "call {}::{} added at {}", pallet, call, index),
+			Change::CallRemoved { pallet, call } => write!(f, "call {}::{} removed", pallet, call),
+			Change::CallIndexChanged { pallet, call, old, new } =>
+				write!(f, "call {}::{} moved from index {} to {}", pallet, call, old, new),
+			Change::CallArgumentsChanged { pallet, call, old, new } => write!(
+				f,
+				"call {}::{} arguments changed\n    old: {}\n    new: {}",
+				pallet, call, old, new
+			),
+			Change::CallArgumentsTruncated { pallet, call } => write!(
+				f,
+				"call {}::{} arguments nest types deeper than {} levels, not compared below",
+				pallet, call, MAX_SHAPE_DEPTH
+			),
+			Change::StorageAdded { pallet, entry } =>
+				write!(f, "storage {}::{} added", pallet, entry),
+			Change::StorageRemoved { pallet, entry } =>
+				write!(f, "storage {}::{} removed", pallet, entry),
+			Change::StorageChanged { pallet, entry, old, new } => write!(
+				f,
+				"storage {}::{} changed\n    old: {}\n    new: {}",
+				pallet, entry, old, new
+			),
+			Change::StorageTruncated { pallet, entry } => write!(
+				f,
+				"storage {}::{} nests types deeper than {} levels, not compared below",
+				pallet, entry, MAX_SHAPE_DEPTH
+			),
+			Change::SignedExtensionsChanged { old, new } => write!(
+				f,
+				"signed extensions changed\n    old: {}\n    new: {}",
+				old.join(", "),
+				new.join(", ")
+			),
+		}
+	}
+}
+
+/// Everything that changed from `old` to `new`, pallet by pallet in `new`'s order, removed
+/// pallets last.
+pub fn diff(old: &Metadata, new: &Metadata) -> Vec<Change> {
+	let mut changes = Vec::new();
+
+	for new_pallet in &new.0.pallets {
+		let pallet = new_pallet.name.clone();
+		let Some(old_pallet) = old.pallet(&pallet) else {
+			changes.push(Change::PalletAdded { pallet, index: new_pallet.index });
+			continue
+		};
+		if old_pallet.index != new_pallet.index {
+			changes.push(Change::PalletIndexChanged {
+				pallet: pallet.clone(),
+				old: old_pallet.index,
+				new: new_pallet.index,
+			});
+		}
+
+		let (old_calls, new_calls) = (old.calls(old_pallet), new.calls(new_pallet));
+		for (call, (new_index, new_args)) in &new_calls {
+			let (pallet, call) = (pallet.clone(), call.clone());
+			match old_calls.get(&call) {
+				None => changes.push(Change::CallAdded { pallet, call, index: *new_index }),
+				Some((old_index, old_args)) => {
+					if old_index != new_index {
+						changes.push(Change::CallIndexChanged {
+							pallet: pallet.clone(),
+							call: call.clone(),
+							old: *old_index,
+							new: *new_index,
+						});
+					}
+					if old_args != new_args {
+						changes.push(Change::CallArgumentsChanged {
+							pallet,
+							call,
+							old: old_args.clone(),
+							new: new_args.clone(),
+						});
+					} else if new_args.contains(TRUNCATED) {
+						changes.push(Change::CallArgumentsTruncated { pallet, call });
+					}
+				},
+			}
+		}
+		for call in old_calls.keys().filter(|call| !new_calls.contains_key(*call)) {
+			changes.push(Change::CallRemoved { pallet: pallet.clone(), call: call.clone() });
+		}
+
+		let (old_storage, new_storage) = (old.storage(old_pallet), new.storage(new_pallet));
+		for (entry, new_shape) in &new_storage {
+			let (pallet, entry) = (pallet.clone(), entry.clone());
+			match old_storage.get(&entry) {
+				None => changes.push(Change::StorageAdded { pallet, entry }),
+				Some(old_shape) if old_shape != new_shape => changes.push(Change::StorageChanged {
+					pallet,
+					entry,
+					old: old_shape.clone(),
+					new: new_shape.clone(),
+				}),
+				Some(_) if new_shape.contains(TRUNCATED) =>
+					changes.push(Change::StorageTruncated { pallet, entry }),
+				Some(_) => {},
+			}
+		}
+		for entry in old_storage.keys().filter(|entry| !new_storage.contains_key(*entry)) {
+			changes.push(Change::StorageRemoved { pallet: pallet.clone(), entry: entry.clone() });
+		}
+	}
+
+	for old_pallet in &old.0.pallets {
+		if new.pallet(&old_pallet.name).is_none() {
+			changes.push(Change::PalletRemoved {
+				pallet: old_pallet.name.clone(),
+				had_calls: !old.calls(old_pallet).is_empty(),
+			});
+		}
+	}
+
+	let (old_extensions, new_extensions) = (old.signed_extensions(), new.signed_extensions());
+	if old_extensions != new_extensions {
+		changes.push(Change::SignedExtensionsChanged { old: old_extensions, new: new_extensions });
+	}
+
+	changes
+}
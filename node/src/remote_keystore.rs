@@ -0,0 +1,468 @@
+//! Remote keystore client.
+//!
+//! Lets a validator keep its Aura, GRANDPA and ImOnline keys on a separate signer host. The node
+//! is started with `--keystore-uri https://signer:9955` plus the `--keystore-remote-*` TLS options
+//! and forwards every keystore operation to the signer as a JSON-RPC 2.0 request, one request per
+//! connection. Both sides present certificates issued by the same CA (mutual TLS).
+//!
+//! # Protocol
+//!
+//! Requests are `POST`ed to the path of the keystore URI. Key types and crypto types are sent as
+//! their four character ids (`aura`, `gran`, `imon` and `sr25`, `ed25`, `ecds`). Byte strings are
+//! `0x`-prefixed hex.
+//!
+//! | method                   | params                                    | result              |
+//! |--------------------------|-------------------------------------------|---------------------|
+//! | `keystore_publicKeys`    | `[keyType, cryptoType]`                   | `[public]`          |
+//! | `keystore_generate`      | `[keyType, cryptoType, seed \| null]`     | `public`            |
+//! | `keystore_insert`        | `[keyType, suri, public]`                 | `null`              |
+//! | `keystore_hasKeys`       | `[[[public, keyType], ...]]`              | `bool`              |
+//! | `keystore_sign`          | `[keyType, cryptoType, public, message]`  | `signature \| null` |
+//! | `keystore_signPrehashed` | `[keyType, public, hash]`                 | `signature \| null` |
+//! | `keystore_vrfSign`       | `[keyType, public, transcript]`           | `vrf \| null`       |
+//!
+//! A `null` signature means the signer does not hold the key. VRF transcripts are sent as
+//! `{"label": bytes, "items": [[name, {"u64": n} | {"bytes": bytes}], ...]}` and signed with
+//! sr25519 keys; the VRF comes back as `{"output": bytes, "proof": bytes}`. Block authors sign
+//! the randomness beacon's VRF with their Aura key, which is the only transcript the reference
+//! signer accepts.
+//!
+//! The `keystore-signer` binary is a reference signer backed by a local keystore directory.
+
+pub mod transport;
+
+use std::{
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{ByteArray, CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519, Bytes,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+	CryptoStore, Error, SyncCryptoStore,
+};
+
+/// TLS options for `--keystore-uri`.
+#[derive(Debug, Clone, clap::Args)]
+pub struct RemoteKeystoreParams {
+	/// CA certificate (PEM) that the remote keystore's certificate must chain to.
+	#[arg(long, value_name = "PATH")]
+	pub keystore_remote_ca: Option<PathBuf>,
+
+	/// Client certificate chain (PEM) presented to the remote keystore.
+	#[arg(long, value_name = "PATH")]
+	pub keystore_remote_cert: Option<PathBuf>,
+
+	/// Private key (PEM) of `--keystore-remote-cert`.
+	#[arg(long, value_name = "PATH")]
+	pub keystore_remote_key: Option<PathBuf>,
+}
+
+impl RemoteKeystoreParams {
+	/// Build the client TLS configuration, if TLS options were given.
+	pub fn tls_config(&self) -> Result<Option<Arc<rustls::ClientConfig>>, String> {
+		match (&self.keystore_remote_ca, &self.keystore_remote_cert, &self.keystore_remote_key) {
+			(Some(ca), Some(cert), Some(key)) => transport::client_config(ca, cert, key)
+				.map(Some)
+				.map_err(|e| format!("Invalid remote keystore TLS configuration: {}", e)),
+			(None, None, None) => Ok(None),
+			_ => Err("--keystore-remote-ca, --keystore-remote-cert and --keystore-remote-key \
+				must be used together"
+				.into()),
+		}
+	}
+}
+
+/// A keystore that forwards every operation to a remote signer.
+///
+/// Requests are blocking socket I/O. The `CryptoStore` methods run them on tokio's blocking
+/// thread pool, so they must be awaited within a tokio runtime.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+	endpoint: Arc<transport::Endpoint>,
+	next_id: Arc<AtomicU64>,
+}
+
+impl RemoteKeystore {
+	/// Connect to the signer at `uri`.
+	///
+	/// `https` URIs need a TLS configuration; `http` is only meant for local testing.
+	pub fn open(uri: &str, tls: Option<Arc<rustls::ClientConfig>>) -> Result<Self, String> {
+		let endpoint = transport::Endpoint::parse(uri, tls)?;
+		if !endpoint.is_tls() {
+			log::warn!("⚠️ Remote keystore {} is not protected by TLS", uri);
+		}
+		Ok(Self { endpoint: Arc::new(endpoint), next_id: Arc::new(AtomicU64::new(0)) })
+	}
+
+	/// Run `f` on the blocking thread pool, off the async executor.
+	async fn spawn_blocking<R, F>(&self, f: F) -> R
+	where
+		R: Send + 'static,
+		F: FnOnce(&Self) -> R + Send + 'static,
+	{
+		let keystore = self.clone();
+		match tokio::task::spawn_blocking(move || f(&keystore)).await {
+			Ok(result) => result,
+			Err(e) => std::panic::resume_unwind(e.into_panic()),
+		}
+	}
+
+	fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R, Error> {
+		#[derive(Deserialize)]
+		struct Response {
+			#[serde(default)]
+			result: Value,
+			error: Option<ErrorObject>,
+		}
+
+		#[derive(Deserialize)]
+		struct ErrorObject {
+			message: String,
+		}
+
+		let request = json!({
+			"jsonrpc": "2.0",
+			"id": self.next_id.fetch_add(1, Ordering::Relaxed),
+			"method": method,
+			"params": params,
+		});
+		let body = serde_json::to_vec(&request).map_err(|e| Error::Other(e.to_string()))?;
+		let response = self
+			.endpoint
+			.post(&body)
+			.map_err(|e| Error::Other(format!("remote keystore {} failed: {}", method, e)))?;
+
+		let response: Response =
+			serde_json::from_slice(&response).map_err(|e| Error::Other(e.to_string()))?;
+		if let Some(error) = response.error {
+			return Err(Error::Other(format!("remote keystore {}: {}", method, error.message)))
+		}
+		serde_json::from_value(response.result).map_err(|e| Error::Other(e.to_string()))
+	}
+
+	fn public_keys<P: ByteArray>(&self, id: KeyTypeId, crypto: CryptoTypeId) -> Vec<P> {
+		self.call::<Vec<Bytes>>("keystore_publicKeys", json!([key_type(id), crypto_type(crypto)]))
+			.map(|keys| keys.into_iter().filter_map(|key| P::from_slice(&key).ok()).collect())
+			.unwrap_or_else(|e| {
+				log::warn!("Failed to list remote keystore keys: {}", e);
+				Vec::new()
+			})
+	}
+
+	fn generate<P: ByteArray>(
+		&self,
+		id: KeyTypeId,
+		crypto: CryptoTypeId,
+		seed: Option<&str>,
+	) -> Result<P, Error> {
+		let public: Bytes =
+			self.call("keystore_generate", json!([key_type(id), crypto_type(crypto), seed]))?;
+		P::from_slice(&public)
+			.map_err(|_| Error::Other("remote keystore returned an invalid public key".into()))
+	}
+}
+
+/// A VRF transcript, as sent with `keystore_vrfSign`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+	pub label: Bytes,
+	pub items: Vec<(String, TranscriptValue)>,
+}
+
+/// The value of a [`Transcript`] item.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptValue {
+	U64(u64),
+	Bytes(Bytes),
+}
+
+impl From<&VRFTranscriptData> for Transcript {
+	fn from(data: &VRFTranscriptData) -> Self {
+		let items = data
+			.items
+			.iter()
+			.map(|(name, value)| {
+				let value = match value {
+					VRFTranscriptValue::U64(value) => TranscriptValue::U64(*value),
+					VRFTranscriptValue::Bytes(bytes) =>
+						TranscriptValue::Bytes(Bytes(bytes.clone())),
+				};
+				(name.to_string(), value)
+			})
+			.collect();
+		Self { label: Bytes(data.label.to_vec()), items }
+	}
+}
+
+/// A VRF output and its proof, as returned by `keystore_vrfSign`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Vrf {
+	pub output: Bytes,
+	pub proof: Bytes,
+}
+
+fn key_type(id: KeyTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+fn crypto_type(id: CryptoTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+#[async_trait::async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::sr25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		let seed = seed.map(str::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::sr25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::ed25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		let seed = seed.map(str::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::ed25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::ecdsa_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		let seed = seed.map(str::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::ecdsa_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let (suri, public) = (suri.to_owned(), public.to_vec());
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::insert_unknown(keystore, id, &suri, &public)
+		})
+		.await
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::supported_keys(keystore, id, keys))
+			.await
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::keys(keystore, id)).await
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys.to_vec();
+		self.spawn_blocking(move |keystore| SyncCryptoStore::has_keys(keystore, &public_keys))
+			.await
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let (key, msg) = (key.clone(), msg.to_vec());
+		self.spawn_blocking(move |keystore| SyncCryptoStore::sign_with(keystore, id, &key, &msg))
+			.await
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		let public = *public;
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::sr25519_vrf_sign(keystore, key_type, &public, transcript_data)
+		})
+		.await
+	}
+
+	async fn ecdsa_sign_prehashed(
+		&self,
+		id: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		let (public, msg) = (*public, *msg);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::ecdsa_sign_prehashed(keystore, id, &public, &msg)
+		})
+		.await
+	}
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let mut keys = Vec::new();
+		for crypto in [sr25519::CRYPTO_ID, ed25519::CRYPTO_ID, ecdsa::CRYPTO_ID] {
+			let public: Vec<Bytes> =
+				self.call("keystore_publicKeys", json!([key_type(id), crypto_type(crypto)]))?;
+			keys.extend(public.into_iter().map(|key| CryptoTypePublicPair(crypto, key.0)));
+		}
+		Ok(keys)
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let all = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| all.contains(key)).collect())
+	}
+
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		self.generate(id, sr25519::CRYPTO_ID, seed)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		self.generate(id, ed25519::CRYPTO_ID, seed)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		self.generate(id, ecdsa::CRYPTO_ID, seed)
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call::<Value>("keystore_insert", json!([key_type(id), suri, Bytes(public.to_vec())]))
+			.map(|_| ())
+			.map_err(|e| log::warn!("Failed to insert key into remote keystore: {}", e))
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let keys: Vec<_> = public_keys
+			.iter()
+			.map(|(public, id)| json!([Bytes(public.clone()), key_type(*id)]))
+			.collect();
+		self.call("keystore_hasKeys", json!([keys])).unwrap_or_else(|e| {
+			log::warn!("Failed to query remote keystore: {}", e);
+			false
+		})
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let signature: Option<Bytes> = self.call(
+			"keystore_sign",
+			json!([key_type(id), crypto_type(key.0), Bytes(key.1.clone()), Bytes(msg.to_vec())]),
+		)?;
+		Ok(signature.map(|signature| signature.0))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type_id: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		let vrf: Option<Vrf> = self.call(
+			"keystore_vrfSign",
+			json!([
+				key_type(key_type_id),
+				Bytes(public.to_raw_vec()),
+				Transcript::from(&transcript_data)
+			]),
+		)?;
+		let invalid = |_| Error::Other("remote keystore returned an invalid VRF".into());
+		vrf.map(|vrf| {
+			Ok(VRFSignature {
+				output: schnorrkel::vrf::VRFOutput::from_bytes(&vrf.output).map_err(invalid)?,
+				proof: schnorrkel::vrf::VRFProof::from_bytes(&vrf.proof).map_err(invalid)?,
+			})
+		})
+		.transpose()
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		id: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		let signature: Option<Bytes> = self.call(
+			"keystore_signPrehashed",
+			json!([key_type(id), Bytes(public.to_raw_vec()), Bytes(msg.to_vec())]),
+		)?;
+		signature
+			.map(|signature| {
+				ecdsa::Signature::from_slice(&signature).ok_or_else(|| {
+					Error::Other("remote keystore returned an invalid signature".into())
+				})
+			})
+			.transpose()
+	}
+}
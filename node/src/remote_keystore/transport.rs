@@ -0,0 +1,183 @@
+//! HTTP/1.1 framing and TLS setup shared by the remote keystore client and signer.
+//!
+//! Every request uses a fresh connection (`Connection: close`), which keeps both sides free of
+//! connection state. Keystore calls are rare enough that the handshake cost does not matter.
+
+use std::{
+	fs::File,
+	io::{self, BufRead, BufReader, Read, Write},
+	net::TcpStream,
+	path::Path,
+	sync::Arc,
+	time::Duration,
+};
+
+use rustls::{Certificate, ClientConfig, ClientConnection, PrivateKey, RootCertStore, ServerName};
+
+/// Upper bound for a request or response body.
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Read and write timeout of a single request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the remote keystore is listening.
+pub struct Endpoint {
+	host: String,
+	port: u16,
+	path: String,
+	tls: Option<Arc<ClientConfig>>,
+}
+
+impl Endpoint {
+	/// Parse an `http://` or `https://` keystore URI.
+	pub fn parse(uri: &str, tls: Option<Arc<ClientConfig>>) -> Result<Self, String> {
+		let (rest, default_port, tls) = if let Some(rest) = uri.strip_prefix("https://") {
+			let tls = tls.ok_or_else(|| {
+				"https keystore URIs need --keystore-remote-ca, --keystore-remote-cert and \
+				 --keystore-remote-key"
+					.to_string()
+			})?;
+			(rest, 443, Some(tls))
+		} else if let Some(rest) = uri.strip_prefix("http://") {
+			(rest, 80, None)
+		} else {
+			return Err(format!("Unsupported remote keystore URI: {}", uri))
+		};
+
+		let (authority, path) = match rest.find('/') {
+			Some(index) => (&rest[..index], &rest[index..]),
+			None => (rest, "/"),
+		};
+		let (host, port) = match authority.rsplit_once(':') {
+			Some((host, port)) =>
+				(host, port.parse().map_err(|_| format!("Invalid port in keystore URI: {}", uri))?),
+			None => (authority, default_port),
+		};
+
+		Ok(Self { host: host.into(), port, path: path.into(), tls })
+	}
+
+	/// Whether requests are sent over TLS.
+	pub fn is_tls(&self) -> bool {
+		self.tls.is_some()
+	}
+
+	/// `POST` a JSON body and return the response body.
+	pub fn post(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+		let tcp = TcpStream::connect((self.host.as_str(), self.port))?;
+		tcp.set_read_timeout(Some(TIMEOUT))?;
+		tcp.set_write_timeout(Some(TIMEOUT))?;
+
+		let head = format!(
+			"POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json",
+			self.path, self.host
+		);
+		let (status, response) = match &self.tls {
+			Some(config) => {
+				let name = ServerName::try_from(self.host.as_str())
+					.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+				let connection = ClientConnection::new(config.clone(), name).map_err(other)?;
+				let mut stream = rustls::StreamOwned::new(connection, tcp);
+				write_message(&mut stream, &head, body)?;
+				read_message(&mut stream)?
+			},
+			None => {
+				let mut stream = tcp;
+				write_message(&mut stream, &head, body)?;
+				read_message(&mut stream)?
+			},
+		};
+
+		if !status.starts_with("HTTP/1.1 200") {
+			return Err(other(format!("unexpected response: {}", status)))
+		}
+		Ok(response)
+	}
+}
+
+/// Write an HTTP message with the given start line and headers, followed by `body`.
+pub fn write_message<W: Write>(stream: &mut W, head: &str, body: &[u8]) -> io::Result<()> {
+	write!(stream, "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", head, body.len())?;
+	stream.write_all(body)?;
+	stream.flush()
+}
+
+/// Read an HTTP message and return its start line and body.
+pub fn read_message<R: Read>(stream: R) -> io::Result<(String, Vec<u8>)> {
+	let mut reader = BufReader::new(stream);
+
+	let mut start = String::new();
+	reader.read_line(&mut start)?;
+
+	let mut length = None;
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line)?;
+		let line = line.trim_end();
+		if line.is_empty() {
+			break
+		}
+		if let Some((name, value)) = line.split_once(':') {
+			if name.eq_ignore_ascii_case("content-length") {
+				length = Some(value.trim().parse::<usize>().map_err(other)?);
+			}
+		}
+	}
+
+	let length = length.ok_or_else(|| other("missing Content-Length"))?;
+	if length > MAX_MESSAGE_SIZE {
+		return Err(other("message too large"))
+	}
+	let mut body = vec![0; length];
+	reader.read_exact(&mut body)?;
+
+	Ok((start.trim_end().into(), body))
+}
+
+/// Load all certificates from a PEM file.
+pub fn load_certs(path: &Path) -> io::Result<Vec<Certificate>> {
+	let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+	if certs.is_empty() {
+		return Err(other(format!("no certificate in {}", path.display())))
+	}
+	Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Load the first private key from a PEM file.
+pub fn load_private_key(path: &Path) -> io::Result<PrivateKey> {
+	let mut reader = BufReader::new(File::open(path)?);
+	while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+		match item {
+			rustls_pemfile::Item::PKCS8Key(key) |
+			rustls_pemfile::Item::RSAKey(key) |
+			rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+			_ => {},
+		}
+	}
+	Err(other(format!("no private key in {}", path.display())))
+}
+
+/// Load a CA bundle into a root store.
+pub fn load_roots(path: &Path) -> io::Result<RootCertStore> {
+	let mut roots = RootCertStore::empty();
+	for cert in load_certs(path)? {
+		roots
+			.add(&cert)
+			.map_err(|e| other(format!("invalid CA certificate: {:?}", e)))?;
+	}
+	Ok(roots)
+}
+
+/// Client configuration that trusts `ca` and authenticates with `cert` and `key`.
+pub fn client_config(ca: &Path, cert: &Path, key: &Path) -> io::Result<Arc<ClientConfig>> {
+	let config = ClientConfig::builder()
+		.with_safe_defaults()
+		.with_root_certificates(load_roots(ca)?)
+		.with_single_cert(load_certs(cert)?, load_private_key(key)?)
+		.map_err(other)?;
+	Ok(Arc::new(config))
+}
+
+fn other<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, e)
+}
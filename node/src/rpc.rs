@@ -5,11 +5,24 @@
 
 #![warn(missing_docs)]
 
+pub mod balances;
+pub mod contracts;
+pub mod dev_signer;
+pub mod eth;
+pub mod pause;
+pub mod pool;
+pub mod simulation;
+pub mod txpool;
+pub mod validators;
+
 use std::sync::Arc;
 use std::collections::BTreeMap;
 
 use jsonrpsee::RpcModule;
-use global_network_runtime::{opaque::Block, AccountId, Balance, Index, Hash};
+use global_network_runtime::{
+	opaque::{Block, SessionKeys},
+	AccountId, Balance, BlockNumber, Index, Hash,
+};
 use sc_client_api::{backend::{Backend, StorageProvider, StateBackend},
 					client::BlockchainEvents,
 					AuxStore};
@@ -23,22 +36,23 @@ use fc_rpc::{
 	EthBlockDataCacheTask, OverrideHandle, RuntimeApiStorageOverride, SchemaV1Override,
 	SchemaV2Override, SchemaV3Override, StorageOverride,
 };
-use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit};
+use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
 use fp_storage::EthereumStorageSchema;
 
 pub use sc_rpc_api::DenyUnsafe;
-use sp_runtime::traits::BlakeTwo256;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT};
 
-/// EVM overrides
-pub fn overrides_handle<C, BE>(client: Arc<C>) -> Arc<OverrideHandle<Block>>
+/// Storage overrides for every `EthereumStorageSchema` that `pallet_ethereum` has written.
+pub fn storage_overrides<B, C, BE>(
+	client: Arc<C>,
+) -> BTreeMap<EthereumStorageSchema, Box<dyn StorageOverride<B> + Send + Sync>>
 	where
-		C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE> + AuxStore,
-		C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError>,
+		B: BlockT<Hash = H256> + Send + Sync + 'static,
+		C: ProvideRuntimeApi<B> + StorageProvider<B, BE> + AuxStore,
+		C: HeaderBackend<B> + HeaderMetadata<B, Error=BlockChainError>,
 		C: Send + Sync + 'static,
-		C::Api: sp_api::ApiExt<Block>
-		+ fp_rpc::EthereumRuntimeRPCApi<Block>
-		+ fp_rpc::ConvertTransactionRuntimeApi<Block>,
-		BE: Backend<Block> + 'static,
+		BE: Backend<B> + 'static,
 		BE::State: StateBackend<BlakeTwo256>,
 {
 	let mut overrides_map = BTreeMap::new();
@@ -54,12 +68,26 @@ pub fn overrides_handle<C, BE>(client: Arc<C>) -> Arc<OverrideHandle<Block>>
 	);
 	overrides_map.insert(
 		EthereumStorageSchema::V3,
-		Box::new(SchemaV3Override::new(client.clone()))
+		Box::new(SchemaV3Override::new(client))
 			as Box<dyn StorageOverride<_> + Send + Sync>,
 	);
+	overrides_map
+}
 
+/// EVM overrides
+pub fn overrides_handle<C, BE>(client: Arc<C>) -> Arc<OverrideHandle<Block>>
+	where
+		C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE> + AuxStore,
+		C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError>,
+		C: Send + Sync + 'static,
+		C::Api: sp_api::ApiExt<Block>
+		+ fp_rpc::EthereumRuntimeRPCApi<Block>
+		+ fp_rpc::ConvertTransactionRuntimeApi<Block>,
+		BE: Backend<Block> + 'static,
+		BE::State: StateBackend<BlakeTwo256>,
+{
 	Arc::new(OverrideHandle {
-		schemas: overrides_map,
+		schemas: storage_overrides(client.clone()),
 		fallback: Box::new(RuntimeApiStorageOverride::new(client)),
 	})
 }
@@ -85,8 +113,22 @@ pub struct FullDeps<C, P, A: ChainApi> {
 	pub fee_history_cache: FeeHistoryCache,
 	/// Maximum fee history cache size.
 	pub fee_history_cache_limit: FeeHistoryCacheLimit,
+	/// Storage overrides, shared with the block data cache.
+	pub overrides: Arc<OverrideHandle<Block>>,
 	/// Cache for Ethereum Block Data
 	pub block_data_cache: Arc<EthBlockDataCacheTask<Block>>,
+	/// Installed `eth_newFilter` filters. The filter API is disabled if `None`.
+	pub filter_pool: Option<FilterPool>,
+	/// Maximum number of logs in a query.
+	pub max_past_logs: u32,
+	/// Maximum number of blocks a log query may span.
+	pub max_log_block_range: u32,
+	/// SQLite index answering `eth_getLogs`, if enabled.
+	pub log_index: Option<Arc<crate::eth::log_index::LogIndex>>,
+	/// Multiplier of the block gas limit for `eth_call` and `eth_estimateGas`.
+	pub execute_gas_limit_multiplier: u64,
+	/// Whether to sign `eth_sendTransaction` with the development accounts.
+	pub enable_dev_signer: bool,
 }
 
 /// Instantiate all full RPC extensions.
@@ -107,40 +149,65 @@ pub fn create_full<C, P, BE, A>(
 		C::Api: BlockBuilder<Block>,
 		C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
 		C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
+		C::Api: global_network_runtime::call_pause::CallPauseApi<Block, BlockNumber>,
+		C::Api: global_network_runtime::contract_metadata::ContractMetadataApi<
+			Block,
+			AccountId,
+			Balance,
+		>,
+		C::Api: global_network_runtime::evm_balances::EvmBalancesApi<Block, Balance, Index>,
+		C::Api: global_network_runtime::evm_simulation::EvmSimulationApi<Block>,
+		C::Api: global_network_runtime::validators::ValidatorSetApi<
+			Block,
+			AccountId,
+			SessionKeys,
+			BlockNumber,
+		>,
 		P: TransactionPool<Block=Block> + 'static,
 		A: ChainApi<Block=Block> + 'static,
 {
-	use fc_rpc::{Eth, EthApiServer, Net, NetApiServer};
+	use eth::{IndexedLogs, LogRangeLimit};
+	use fc_rpc::{
+		Eth, EthApiServer, EthFilter, EthFilterApiServer, EthSigner, Net, NetApiServer,
+	};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
+	use balances::{Balances, BalancesApiServer};
+	use contracts::{Contracts, ContractsApiServer};
+	use pause::{CallPause, CallPauseApiServer};
+	use pool::{PoolApiServer, PoolDiagnostics};
+	use simulation::{EthSimulation, EthSimulationApiServer};
+	use txpool::{TxPool, TxPoolApiServer};
+	use validators::{Validators, ValidatorsApiServer};
 
 	let mut module = RpcModule::new(());
 	let FullDeps {
 		client, pool, graph,
 		deny_unsafe, is_authority, network, backend,
 		fee_history_cache, fee_history_cache_limit,
-		block_data_cache
+		overrides, block_data_cache, filter_pool,
+		max_past_logs, max_log_block_range, log_index, execute_gas_limit_multiplier,
+		enable_dev_signer,
 	} = deps;
-	// We won't use the override feature
-	let overrides = Arc::new(OverrideHandle {
-		schemas: BTreeMap::new(),
-		fallback: Box::new(RuntimeApiStorageOverride::new(client.clone())),
-	});
-
-	// Nor any signers
-	let signers = Vec::new();
-
-	// Limit the number of queryable logs.
-	// let max_past_logs: u32 = 1024;
+	let mut signers = Vec::new();
+	if enable_dev_signer {
+		signers.push(Box::new(dev_signer::EthDevSigner::new()) as Box<dyn EthSigner>);
+	}
 
 	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
 	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(Validators::new(client.clone()).into_rpc())?;
+	module.merge(Balances::new(client.clone()).into_rpc())?;
+	module.merge(Contracts::new(client.clone()).into_rpc())?;
+	module.merge(CallPause::new(client.clone()).into_rpc())?;
+	module.merge(PoolDiagnostics::new(client.clone(), graph.clone(), deny_unsafe).into_rpc())?;
+	module.merge(TxPool::new(client.clone(), graph.clone()).into_rpc())?;
 	module.merge(Net::new(client.clone(), network.clone(), true).into_rpc())?;
-	module.merge(
+	let new_eth = |signers: Vec<Box<dyn EthSigner>>| {
 		Eth::new(
 			client.clone(),
 			pool.clone(),
-			graph,
+			graph.clone(),
 			Some(global_network_runtime::TransactionConverter),
 			network.clone(),
 			signers,
@@ -149,21 +216,51 @@ pub fn create_full<C, P, BE, A>(
 			// Is authority.
 			is_authority,
 			block_data_cache.clone(),
-			fee_history_cache,
+			fee_history_cache.clone(),
 			fee_history_cache_limit,
-			10,
+			execute_gas_limit_multiplier,
 		)
-			.into_rpc())?;
+	};
+	let mut eth = new_eth(signers).into_rpc();
+	// Served by `EthSimulation` below, which passes calls without state overrides back to a
+	// Frontier `Eth`.
+	eth.remove_method("eth_call");
+	module.merge(eth)?;
+	module.merge(
+		EthSimulation::new(
+			new_eth(Vec::new()),
+			client.clone(),
+			backend.clone(),
+			execute_gas_limit_multiplier,
+		)
+		.into_rpc(),
+	)?;
+
+	if let Some(filter_pool) = filter_pool {
+		let filter = LogRangeLimit::new(
+			EthFilter::new(
+				client.clone(),
+				backend,
+				filter_pool,
+				500_usize, // max stored filters
+				max_past_logs,
+				block_data_cache,
+			),
+			client.clone(),
+			max_log_block_range,
+		);
+		match log_index {
+			Some(index) => module.merge(
+				IndexedLogs::new(filter, client.clone(), index, max_past_logs).into_rpc(),
+			)?,
+			None => module.merge(filter.into_rpc())?,
+		}
+	}
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
 	// `module.merge(YourRpcTrait::into_rpc(YourRpcStruct::new(ReferenceToClient, ...)))?;`
 
-
-	// Reasonable default caching inspired by the frontier template
-	// let block_data_cache = Arc::new(EthBlockDataCacheTask::new(50, 50));
-
-
 	Ok(module)
 }
@@ -0,0 +1,103 @@
+//! `balances_*` RPC namespace backed by the `EvmBalancesApi` runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use global_network_runtime::evm_balances::{EvmAccountBalance, EvmBalancesApi};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, U256};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{codec::Codec, generic::BlockId, traits::Block as BlockT};
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+
+/// [`EvmAccountBalance`] as returned over RPC. Balances may not fit a JSON number, so they are
+/// serialized as [`NumberOrHex`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmAccount<Index> {
+	/// Free balance, including the frozen part.
+	pub free: NumberOrHex,
+	/// Reserved balance, never visible to the EVM.
+	pub reserved: NumberOrHex,
+	/// Part of the free balance locked for transfers.
+	pub misc_frozen: NumberOrHex,
+	/// Part of the free balance locked for paying fees.
+	pub fee_frozen: NumberOrHex,
+	/// Balance reported by `eth_getBalance`.
+	pub evm_balance: U256,
+	/// Balance an EVM transaction can transfer out of the account.
+	pub evm_spendable: NumberOrHex,
+	/// Account nonce, shared by Substrate extrinsics and Ethereum transactions.
+	pub nonce: Index,
+}
+
+impl<Balance: Into<NumberOrHex>, Index> From<EvmAccountBalance<Balance, Index>>
+	for EvmAccount<Index>
+{
+	fn from(balance: EvmAccountBalance<Balance, Index>) -> Self {
+		Self {
+			free: balance.free.into(),
+			reserved: balance.reserved.into(),
+			misc_frozen: balance.misc_frozen.into(),
+			fee_frozen: balance.fee_frozen.into(),
+			evm_balance: balance.evm_balance,
+			evm_spendable: balance.evm_spendable.into(),
+			nonce: balance.nonce,
+		}
+	}
+}
+
+/// Balance RPC methods, for the runtime balance type `Balance`.
+#[rpc(server)]
+pub trait BalancesApi<BlockHash, Balance, Index> {
+	/// Free, reserved, frozen and EVM-spendable balance of an Ethereum address.
+	#[method(name = "balances_evmAccount")]
+	fn evm_account(&self, address: H160, at: Option<BlockHash>) -> RpcResult<EvmAccount<Index>>;
+}
+
+/// Implements the [`BalancesApiServer`] RPC trait.
+pub struct Balances<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Balances<C, B> {
+	/// Create new `Balances` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, Balance, Index> BalancesApiServer<<Block as BlockT>::Hash, Balance, Index>
+	for Balances<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: EvmBalancesApi<Block, Balance, Index>,
+	Balance: Codec + Into<NumberOrHex> + Send + Sync + 'static,
+	Index: Codec + Serialize + Send + Sync + 'static,
+{
+	fn evm_account(
+		&self,
+		address: H160,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<EvmAccount<Index>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let balance = self.client.runtime_api().evm_account_balance(&at, address).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the account balance.",
+				Some(e.to_string()),
+			))
+		})?;
+		Ok(balance.into())
+	}
+}
@@ -0,0 +1,69 @@
+//! `contracts_*` RPC namespace backed by the `ContractMetadataApi` runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use global_network_runtime::contract_metadata::{ContractMetadata, ContractMetadataApi};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H160};
+use sp_runtime::{codec::Codec, generic::BlockId, traits::Block as BlockT};
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+
+/// Contract metadata RPC methods.
+#[rpc(server)]
+pub trait ContractsApi<BlockHash, AccountId, Balance> {
+	/// Source CID or ABI and compiler settings attached to a contract, if any.
+	#[method(name = "contracts_metadata")]
+	fn metadata(
+		&self,
+		address: H160,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ContractMetadata<AccountId, Balance, Bytes>>>;
+}
+
+/// Implements the [`ContractsApiServer`] RPC trait.
+pub struct Contracts<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Contracts<C, B> {
+	/// Create new `Contracts` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, Balance> ContractsApiServer<<Block as BlockT>::Hash, AccountId, Balance>
+	for Contracts<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ContractMetadataApi<Block, AccountId, Balance>,
+	AccountId: Codec + Serialize + Send + Sync + 'static,
+	Balance: Codec + Serialize + Send + Sync + 'static,
+{
+	fn metadata(
+		&self,
+		address: H160,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<ContractMetadata<AccountId, Balance, Bytes>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let metadata = self.client.runtime_api().contract_metadata(&at, address).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the contract metadata.",
+				Some(e.to_string()),
+			))
+		})?;
+		Ok(metadata.map(|metadata| metadata.map_bytes(Bytes::from)))
+	}
+}
@@ -0,0 +1,123 @@
+//! Unlocked accounts for `eth_sendTransaction` and `eth_sign` on development chains.
+
+use ethereum::TransactionV2 as EthereumTransaction;
+use fc_rpc::{internal_err, EthSigner};
+use fc_rpc_core::types::TransactionMessage;
+use jsonrpsee::core::RpcResult;
+use sha3::{Digest, Keccak256};
+use sp_core::{H160, H256};
+
+/// Secret keys of the well-known ALITH, BALTATHAR, CHARLETH and DOROTHY development accounts.
+///
+/// These keys are public knowledge. Never fund the accounts on a live chain.
+const DEV_KEYS: [[u8; 32]; 4] = [
+	hex_literal::hex!("5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133"),
+	hex_literal::hex!("8075991ce870b93a8870eca0c0f91913d12f47948ca0fd25b49c6fa7cdbeee8b"),
+	hex_literal::hex!("0b6e18cafb6ed99687ec547bd28139cafdd2bffe70e6b688025de6b445aa5c5b"),
+	hex_literal::hex!("39539ab1876910bbf3a223d84a29e28f1cb4e2e456503e7e91ed39b2e7223d68"),
+];
+
+/// Signs Ethereum transactions with the development keys.
+pub struct EthDevSigner {
+	keys: Vec<libsecp256k1::SecretKey>,
+}
+
+impl EthDevSigner {
+	/// Signer holding all development keys.
+	pub fn new() -> Self {
+		Self {
+			keys: DEV_KEYS
+				.iter()
+				.map(|key| libsecp256k1::SecretKey::parse(key).expect("valid secret key; qed"))
+				.collect(),
+		}
+	}
+
+	fn key(&self, address: &H160) -> Option<&libsecp256k1::SecretKey> {
+		self.keys.iter().find(|secret| secret_key_address(secret) == *address)
+	}
+}
+
+impl Default for EthDevSigner {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+fn secret_key_address(secret: &libsecp256k1::SecretKey) -> H160 {
+	let public = libsecp256k1::PublicKey::from_secret_key(secret);
+	H160::from(H256::from_slice(Keccak256::digest(&public.serialize()[1..65]).as_slice()))
+}
+
+/// Sign `hash`, returning the recovery id and the `r` and `s` values.
+fn sign_hash(secret: &libsecp256k1::SecretKey, hash: H256) -> RpcResult<(u8, H256, H256)> {
+	let message = libsecp256k1::Message::parse_slice(hash.as_bytes())
+		.map_err(|_| internal_err("invalid signing message"))?;
+	let (signature, recovery_id) = libsecp256k1::sign(&message, secret);
+	let rs = signature.serialize();
+	Ok((recovery_id.serialize(), H256::from_slice(&rs[0..32]), H256::from_slice(&rs[32..64])))
+}
+
+impl EthSigner for EthDevSigner {
+	fn accounts(&self) -> Vec<H160> {
+		self.keys.iter().map(secret_key_address).collect()
+	}
+
+	fn sign(&self, message: TransactionMessage, address: &H160) -> RpcResult<EthereumTransaction> {
+		let secret = self.key(address).ok_or_else(|| internal_err("signer not available"))?;
+
+		let transaction = match message {
+			TransactionMessage::Legacy(m) => {
+				let (recovery_id, r, s) = sign_hash(secret, m.hash())?;
+				let v = match m.chain_id {
+					None => 27 + recovery_id as u64,
+					Some(chain_id) => 2 * chain_id + 35 + recovery_id as u64,
+				};
+				EthereumTransaction::Legacy(ethereum::LegacyTransaction {
+					nonce: m.nonce,
+					gas_price: m.gas_price,
+					gas_limit: m.gas_limit,
+					action: m.action,
+					value: m.value,
+					input: m.input,
+					signature: ethereum::TransactionSignature::new(v, r, s)
+						.ok_or_else(|| internal_err("signer generated invalid signature"))?,
+				})
+			},
+			TransactionMessage::EIP2930(m) => {
+				let (recovery_id, r, s) = sign_hash(secret, m.hash())?;
+				EthereumTransaction::EIP2930(ethereum::EIP2930Transaction {
+					chain_id: m.chain_id,
+					nonce: m.nonce,
+					gas_price: m.gas_price,
+					gas_limit: m.gas_limit,
+					action: m.action,
+					value: m.value,
+					input: m.input,
+					access_list: m.access_list,
+					odd_y_parity: recovery_id != 0,
+					r,
+					s,
+				})
+			},
+			TransactionMessage::EIP1559(m) => {
+				let (recovery_id, r, s) = sign_hash(secret, m.hash())?;
+				EthereumTransaction::EIP1559(ethereum::EIP1559Transaction {
+					chain_id: m.chain_id,
+					nonce: m.nonce,
+					max_priority_fee_per_gas: m.max_priority_fee_per_gas,
+					max_fee_per_gas: m.max_fee_per_gas,
+					gas_limit: m.gas_limit,
+					action: m.action,
+					value: m.value,
+					input: m.input,
+					access_list: m.access_list,
+					odd_y_parity: recovery_id != 0,
+					r,
+					s,
+				})
+			},
+		};
+		Ok(transaction)
+	}
+}
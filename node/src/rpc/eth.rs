@@ -0,0 +1,200 @@
+//! Limits and the log index on top of the Frontier Ethereum RPC.
+
+use std::sync::Arc;
+
+use fc_rpc::internal_err;
+use fc_rpc_core::{
+	types::{BlockNumber, Filter, FilterChanges, Index, Log, VariadicValue},
+	EthFilterApiServer,
+};
+use jsonrpsee::core::{async_trait, RpcResult};
+use sp_blockchain::HeaderBackend;
+use sp_core::U256;
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+
+use crate::eth::log_index::{LogIndex, LogQuery};
+
+/// `eth_*Filter*` and `eth_getLogs` rejecting log queries that span too many blocks.
+pub struct LogRangeLimit<F, C, B> {
+	inner: F,
+	client: Arc<C>,
+	max_block_range: u32,
+	_marker: std::marker::PhantomData<fn() -> B>,
+}
+
+impl<F, C, B> LogRangeLimit<F, C, B> {
+	/// Wrap `inner`, allowing log queries over at most `max_block_range` blocks.
+	pub fn new(inner: F, client: Arc<C>, max_block_range: u32) -> Self {
+		Self { inner, client, max_block_range, _marker: Default::default() }
+	}
+}
+
+impl<F, C, B> LogRangeLimit<F, C, B>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	fn check_range(&self, filter: &Filter) -> RpcResult<()> {
+		if filter.block_hash.is_some() {
+			return Ok(())
+		}
+
+		let best: u64 = self.client.info().best_number.unique_saturated_into();
+		let resolve = |number: &Option<BlockNumber>| {
+			number.as_ref().and_then(BlockNumber::to_min_block_num).unwrap_or(best)
+		};
+		let from = resolve(&filter.from_block);
+		let to = resolve(&filter.to_block);
+
+		if to.saturating_sub(from) > self.max_block_range as u64 {
+			return Err(internal_err(format!(
+				"block range is too wide, at most {} blocks can be queried",
+				self.max_block_range
+			)))
+		}
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl<F, C, B> EthFilterApiServer for LogRangeLimit<F, C, B>
+where
+	F: EthFilterApiServer,
+	B: BlockT,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+{
+	fn new_filter(&self, filter: Filter) -> RpcResult<U256> {
+		self.check_range(&filter)?;
+		self.inner.new_filter(filter)
+	}
+
+	fn new_block_filter(&self) -> RpcResult<U256> {
+		self.inner.new_block_filter()
+	}
+
+	fn new_pending_transaction_filter(&self) -> RpcResult<U256> {
+		self.inner.new_pending_transaction_filter()
+	}
+
+	fn filter_changes(&self, index: Index) -> RpcResult<FilterChanges> {
+		self.inner.filter_changes(index)
+	}
+
+	async fn filter_logs(&self, index: Index) -> RpcResult<Vec<Log>> {
+		self.inner.filter_logs(index).await
+	}
+
+	fn uninstall_filter(&self, index: Index) -> RpcResult<bool> {
+		self.inner.uninstall_filter(index)
+	}
+
+	async fn logs(&self, filter: Filter) -> RpcResult<Vec<Log>> {
+		self.check_range(&filter)?;
+		self.inner.logs(filter).await
+	}
+}
+
+/// `eth_getLogs` answered from the SQLite log index when it covers the query, and by `inner`
+/// otherwise.
+pub struct IndexedLogs<F, C, B> {
+	inner: F,
+	client: Arc<C>,
+	index: Arc<LogIndex>,
+	max_past_logs: u32,
+	_marker: std::marker::PhantomData<fn() -> B>,
+}
+
+impl<F, C, B> IndexedLogs<F, C, B> {
+	/// Wrap `inner`, returning at most `max_past_logs` logs from `index`.
+	pub fn new(inner: F, client: Arc<C>, index: Arc<LogIndex>, max_past_logs: u32) -> Self {
+		Self { inner, client, index, max_past_logs, _marker: Default::default() }
+	}
+}
+
+impl<F, C, B> IndexedLogs<F, C, B>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	/// `filter` as an index query, if the index has every block it covers.
+	fn query(&self, filter: &Filter) -> RpcResult<Option<LogQuery>> {
+		if filter.block_hash.is_some() {
+			return Ok(None)
+		}
+		let indexed = match self.index.tip().map_err(internal_err)? {
+			Some((number, _)) => number,
+			None => return Ok(None),
+		};
+
+		let best: u64 = self.client.info().best_number.unique_saturated_into();
+		let resolve = |number: &Option<BlockNumber>| {
+			number.as_ref().and_then(BlockNumber::to_min_block_num).unwrap_or(best)
+		};
+		let (from_block, to_block) = (resolve(&filter.from_block), resolve(&filter.to_block));
+		if to_block > indexed {
+			return Ok(None)
+		}
+
+		let addresses = match &filter.address {
+			Some(VariadicValue::Single(address)) => vec![*address],
+			Some(VariadicValue::Multiple(addresses)) => addresses.clone(),
+			Some(VariadicValue::Null) | None => Vec::new(),
+		};
+		let positions = match &filter.topics {
+			Some(VariadicValue::Single(position)) => vec![position.clone()],
+			Some(VariadicValue::Multiple(positions)) => positions.clone(),
+			Some(VariadicValue::Null) | None => Vec::new(),
+		};
+		// A position matches anything if it is empty or lists a wildcard.
+		let topics = positions
+			.into_iter()
+			.map(|position| match position {
+				Some(VariadicValue::Single(Some(topic))) => vec![topic],
+				Some(VariadicValue::Multiple(topics)) if topics.iter().all(Option::is_some) =>
+					topics.into_iter().flatten().collect(),
+				_ => Vec::new(),
+			})
+			.collect();
+
+		Ok(Some(LogQuery { from_block, to_block, addresses, topics }))
+	}
+}
+
+#[async_trait]
+impl<F, C, B> EthFilterApiServer for IndexedLogs<F, C, B>
+where
+	F: EthFilterApiServer,
+	B: BlockT,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+{
+	fn new_filter(&self, filter: Filter) -> RpcResult<U256> {
+		self.inner.new_filter(filter)
+	}
+
+	fn new_block_filter(&self) -> RpcResult<U256> {
+		self.inner.new_block_filter()
+	}
+
+	fn new_pending_transaction_filter(&self) -> RpcResult<U256> {
+		self.inner.new_pending_transaction_filter()
+	}
+
+	fn filter_changes(&self, index: Index) -> RpcResult<FilterChanges> {
+		self.inner.filter_changes(index)
+	}
+
+	async fn filter_logs(&self, index: Index) -> RpcResult<Vec<Log>> {
+		self.inner.filter_logs(index).await
+	}
+
+	fn uninstall_filter(&self, index: Index) -> RpcResult<bool> {
+		self.inner.uninstall_filter(index)
+	}
+
+	async fn logs(&self, filter: Filter) -> RpcResult<Vec<Log>> {
+		match self.query(&filter)? {
+			Some(query) => self.index.logs(&query, self.max_past_logs).map_err(internal_err),
+			None => self.inner.logs(filter).await,
+		}
+	}
+}
@@ -0,0 +1,83 @@
+//! `callPause_*` RPC namespace backed by the `CallPauseApi` runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use global_network_runtime::call_pause::CallPauseApi;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{codec::Codec, generic::BlockId, traits::Block as BlockT};
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+
+/// A pause in place.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PausedCall<BlockNumber> {
+	/// Name of the paused pallet.
+	pub pallet: String,
+	/// Name of the paused call, or `None` when the whole pallet is paused.
+	pub call: Option<String>,
+	/// Block the pause ends at.
+	pub until: BlockNumber,
+}
+
+/// Call pause RPC methods.
+#[rpc(server)]
+pub trait CallPauseApi<BlockHash, BlockNumber> {
+	/// Pallets and calls paused by governance, with the block each pause ends at.
+	#[method(name = "callPause_paused")]
+	fn paused(&self, at: Option<BlockHash>) -> RpcResult<Vec<PausedCall<BlockNumber>>>;
+}
+
+/// Implements the [`CallPauseApiServer`] RPC trait.
+pub struct CallPause<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> CallPause<C, B> {
+	/// Create new `CallPause` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, BlockNumber> CallPauseApiServer<<Block as BlockT>::Hash, BlockNumber>
+	for CallPause<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: CallPauseApi<Block, BlockNumber>,
+	BlockNumber: Codec + Serialize + Send + Sync + 'static,
+{
+	fn paused(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<PausedCall<BlockNumber>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let paused = self.client.runtime_api().paused_calls(&at).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the paused calls.",
+				Some(e.to_string()),
+			))
+		})?;
+
+		let name = |name: Vec<u8>| String::from_utf8_lossy(&name).into_owned();
+		Ok(paused
+			.into_iter()
+			.map(|(pallet, call, until)| PausedCall {
+				pallet: name(pallet),
+				call: call.map(name),
+				until,
+			})
+			.collect())
+	}
+}
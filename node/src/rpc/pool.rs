@@ -0,0 +1,168 @@
+//! `pool_*` RPC namespace explaining why extrinsics are rejected or wait in the future queue.
+
+use std::{collections::HashSet, sync::Arc};
+
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use sc_transaction_pool::{ChainApi, Pool};
+use sc_transaction_pool_api::TransactionSource;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	codec::Decode,
+	generic::BlockId,
+	traits::Block as BlockT,
+	transaction_validity::{TransactionTag, TransactionValidityError, ValidTransaction},
+};
+
+use crate::rpc::DenyUnsafe;
+
+/// Error code returned when the extrinsic cannot be decoded.
+const DECODE_ERROR: i32 = 1;
+/// Error code returned when the runtime cannot validate the extrinsic.
+const VALIDATION_ERROR: i32 = 2;
+
+/// Validity of an extrinsic against the best block.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum ExtrinsicValidity {
+	/// Accepted by the runtime. The extrinsic waits in the future queue while `missing` is not
+	/// empty.
+	#[serde(rename_all = "camelCase")]
+	Valid {
+		/// Priority in the ready queue.
+		priority: u64,
+		/// Tags the extrinsic depends on.
+		requires: Vec<Bytes>,
+		/// Tags the extrinsic provides.
+		provides: Vec<Bytes>,
+		/// Required tags that no ready extrinsic provides yet.
+		missing: Vec<Bytes>,
+		/// Number of blocks the extrinsic stays valid for.
+		longevity: u64,
+	},
+	/// Rejected by the runtime.
+	Invalid {
+		/// The `TransactionValidityError` returned by the runtime.
+		error: TransactionValidityError,
+		/// Human readable description of `error`.
+		reason: String,
+	},
+}
+
+/// An extrinsic of the future queue.
+#[derive(Debug, Serialize)]
+pub struct FutureExtrinsic<Hash> {
+	/// Extrinsic hash.
+	pub hash: Hash,
+	/// Why the extrinsic is not ready.
+	pub validity: ExtrinsicValidity,
+}
+
+/// Transaction pool diagnostics RPC methods.
+#[rpc(server)]
+pub trait PoolApi<Hash> {
+	/// Validate an encoded extrinsic against the best block without submitting it. Unsafe: each
+	/// call runs the runtime's `validate_transaction`.
+	#[method(name = "pool_validate")]
+	async fn validate(&self, extrinsic: Bytes) -> RpcResult<ExtrinsicValidity>;
+
+	/// Extrinsics of the future queue and why they are not ready. Unsafe: revalidates the whole
+	/// future queue.
+	#[method(name = "pool_future")]
+	async fn future(&self) -> RpcResult<Vec<FutureExtrinsic<Hash>>>;
+}
+
+/// Implements the [`PoolApiServer`] RPC trait.
+pub struct PoolDiagnostics<C, A: ChainApi> {
+	client: Arc<C>,
+	graph: Arc<Pool<A>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C, A: ChainApi> PoolDiagnostics<C, A> {
+	/// Create new `PoolDiagnostics` over the given transaction pool.
+	pub fn new(client: Arc<C>, graph: Arc<Pool<A>>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, graph, deny_unsafe }
+	}
+}
+
+fn error(code: i32, message: &str, e: impl std::fmt::Display) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(code, message, Some(e.to_string()))).into()
+}
+
+impl<C, A> PoolDiagnostics<C, A>
+where
+	A: ChainApi,
+	C: HeaderBackend<A::Block>,
+{
+	/// Tags provided by the ready queue.
+	fn ready_tags(&self) -> HashSet<TransactionTag> {
+		self.graph
+			.validated_pool()
+			.ready()
+			.flat_map(|transaction| transaction.provides.clone())
+			.collect()
+	}
+
+	async fn explain(
+		&self,
+		extrinsic: <A::Block as BlockT>::Extrinsic,
+		ready_tags: &HashSet<TransactionTag>,
+	) -> RpcResult<ExtrinsicValidity> {
+		let at = BlockId::hash(self.client.info().best_hash);
+		let validity = self
+			.graph
+			.validated_pool()
+			.api()
+			.validate_transaction(&at, TransactionSource::External, extrinsic)
+			.await
+			.map_err(|e| error(VALIDATION_ERROR, "Unable to validate the extrinsic.", e))?;
+
+		Ok(match validity {
+			Ok(valid) => Self::valid(valid, ready_tags),
+			Err(e) => ExtrinsicValidity::Invalid { error: e, reason: e.to_string() },
+		})
+	}
+
+	fn valid(valid: ValidTransaction, ready_tags: &HashSet<TransactionTag>) -> ExtrinsicValidity {
+		let missing = valid.requires.iter().filter(|tag| !ready_tags.contains(*tag)).cloned();
+
+		ExtrinsicValidity::Valid {
+			priority: valid.priority,
+			missing: missing.map(Bytes).collect(),
+			requires: valid.requires.into_iter().map(Bytes).collect(),
+			provides: valid.provides.into_iter().map(Bytes).collect(),
+			longevity: valid.longevity,
+		}
+	}
+}
+
+#[async_trait]
+impl<C, A> PoolApiServer<<A::Block as BlockT>::Hash> for PoolDiagnostics<C, A>
+where
+	A: ChainApi + 'static,
+	C: HeaderBackend<A::Block> + Send + Sync + 'static,
+{
+	async fn validate(&self, extrinsic: Bytes) -> RpcResult<ExtrinsicValidity> {
+		self.deny_unsafe.check_if_safe()?;
+		let extrinsic = Decode::decode(&mut &extrinsic[..])
+			.map_err(|e| error(DECODE_ERROR, "Unable to decode the extrinsic.", e))?;
+		self.explain(extrinsic, &self.ready_tags()).await
+	}
+
+	async fn future(&self) -> RpcResult<Vec<FutureExtrinsic<<A::Block as BlockT>::Hash>>> {
+		self.deny_unsafe.check_if_safe()?;
+		let ready_tags = self.ready_tags();
+		let mut future = Vec::new();
+		for (hash, extrinsic) in self.graph.validated_pool().futures() {
+			let validity = self.explain(extrinsic, &ready_tags).await?;
+			future.push(FutureExtrinsic { hash, validity });
+		}
+		Ok(future)
+	}
+}
@@ -0,0 +1,300 @@
+//! Geth-compatible `eth_call` with state overrides, and `eth_createAccessList`.
+//!
+//! Takes over Frontier's `eth_call`, which has no state override parameter. Calls with
+//! overrides and access lists run through the `EvmSimulationApi` runtime API; calls without
+//! overrides are still served by Frontier.
+
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use ethereum::AccessListItem;
+use fc_rpc::{frontier_backend_client, internal_err, EthApiServer};
+use fc_rpc_core::types::{BlockNumber, Bytes, CallRequest};
+use fp_evm::{ExitError, ExitReason};
+use fp_rpc::EthereumRuntimeRPCApi;
+use global_network_runtime::evm_simulation::{
+	EvmSimulationApi, SimulationRequest, SimulationResult, StateOverride,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of a reverted execution, as used by geth.
+const EXECUTION_REVERTED: i32 = 3;
+
+/// Replacement state of an account for `eth_call`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct CallStateOverride {
+	/// Fake balance.
+	pub balance: Option<U256>,
+	/// Fake nonce.
+	pub nonce: Option<U256>,
+	/// Fake code.
+	pub code: Option<Bytes>,
+	/// Fake storage, replacing the whole storage of the account.
+	pub state: Option<BTreeMap<H256, H256>>,
+	/// Fake storage slots, leaving other slots untouched.
+	pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Result of `eth_createAccessList`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListWithGasUsed {
+	/// Accounts and storage slots the call accesses.
+	pub access_list: Vec<AccessListItem>,
+	/// Gas used by the call with `access_list` applied.
+	pub gas_used: U256,
+	/// Why the call failed, if it did.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// Ethereum call simulation RPC methods.
+#[rpc(server)]
+pub trait EthSimulationApi {
+	/// Execute a call without creating a transaction, optionally against overridden state.
+	#[method(name = "eth_call")]
+	fn call(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+		state_overrides: Option<BTreeMap<H160, CallStateOverride>>,
+	) -> RpcResult<Bytes>;
+
+	/// Generate the EIP-2930 access list of a call.
+	#[method(name = "eth_createAccessList")]
+	fn create_access_list(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> RpcResult<AccessListWithGasUsed>;
+}
+
+/// Implements the [`EthSimulationApiServer`] RPC trait.
+pub struct EthSimulation<B: BlockT, C, E> {
+	eth: E,
+	client: Arc<C>,
+	backend: Arc<fc_db::Backend<B>>,
+	execute_gas_limit_multiplier: u64,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C, E> EthSimulation<B, C, E> {
+	/// Create new `EthSimulation`, leaving calls without state overrides to `eth`. Calls without
+	/// a gas limit get the block gas limit times `execute_gas_limit_multiplier`.
+	pub fn new(
+		eth: E,
+		client: Arc<C>,
+		backend: Arc<fc_db::Backend<B>>,
+		execute_gas_limit_multiplier: u64,
+	) -> Self {
+		Self { eth, client, backend, execute_gas_limit_multiplier, _marker: Default::default() }
+	}
+}
+
+impl<B, C, E> EthSimulation<B, C, E>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B> + EvmSimulationApi<B>,
+{
+	fn block_id(&self, number: Option<BlockNumber>) -> RpcResult<BlockId<B>> {
+		frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			number,
+		)?
+		.ok_or_else(|| internal_err("header not found"))
+	}
+
+	/// Fail unless the runtime at `at` provides `EvmSimulationApi`, which blocks from before
+	/// the upgrade adding it do not.
+	fn ensure_simulation_api(&self, at: &BlockId<B>) -> RpcResult<()> {
+		let has_api = self
+			.client
+			.runtime_api()
+			.has_api::<dyn EvmSimulationApi<B>>(at)
+			.map_err(|e| internal_err(format!("runtime error: {:?}", e)))?;
+		if !has_api {
+			return Err(internal_err("the runtime of this block cannot simulate calls"))
+		}
+		Ok(())
+	}
+
+	fn simulation_request(
+		&self,
+		at: &BlockId<B>,
+		request: CallRequest,
+	) -> RpcResult<SimulationRequest> {
+		let gas_limit = match request.gas {
+			Some(gas) => gas,
+			None => {
+				let block_gas_limit = self
+					.client
+					.runtime_api()
+					.current_block(at)
+					.map_err(|e| internal_err(format!("runtime error: {:?}", e)))?
+					.map(|block| block.header.gas_limit)
+					.unwrap_or_else(|| U256::from(u32::MAX));
+				block_gas_limit.saturating_mul(self.execute_gas_limit_multiplier.into())
+			},
+		};
+		// A legacy gas price is both the fee cap and the tip.
+		let (max_fee_per_gas, max_priority_fee_per_gas) = match request.gas_price {
+			Some(gas_price) => (Some(gas_price), Some(gas_price)),
+			None => (request.max_fee_per_gas, request.max_priority_fee_per_gas),
+		};
+
+		Ok(SimulationRequest {
+			from: request.from.unwrap_or_default(),
+			to: request.to,
+			data: request.data.map(|data| data.into_vec()).unwrap_or_default(),
+			value: request.value.unwrap_or_default(),
+			gas_limit,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			nonce: request.nonce,
+			access_list: request
+				.access_list
+				.unwrap_or_default()
+				.into_iter()
+				.map(|item| (item.address, item.storage_keys))
+				.collect(),
+		})
+	}
+}
+
+/// Convert the RPC overrides into the runtime representation.
+fn state_overrides(overrides: BTreeMap<H160, CallStateOverride>) -> RpcResult<Vec<StateOverride>> {
+	overrides
+		.into_iter()
+		.map(|(address, account)| {
+			if account.state.is_some() && account.state_diff.is_some() {
+				return Err(internal_err(format!(
+					"account {:?} has both 'state' and 'stateDiff'",
+					address
+				)))
+			}
+			Ok(StateOverride {
+				address,
+				balance: account.balance,
+				nonce: account.nonce,
+				code: account.code.map(|code| code.into_vec()),
+				state: account.state.map(|state| state.into_iter().collect()),
+				state_diff: account.state_diff.map(|diff| diff.into_iter().collect()),
+			})
+		})
+		.collect()
+}
+
+/// Turn a failed execution into an error, the way geth reports it.
+fn execution_error(result: &SimulationResult) -> Option<jsonrpsee::core::Error> {
+	let error = match &result.exit_reason {
+		ExitReason::Succeed(_) => return None,
+		ExitReason::Revert(_) => CallError::Custom(ErrorObject::owned(
+			EXECUTION_REVERTED,
+			revert_message(&result.value),
+			Some(format!("0x{}", hex(&result.value))),
+		))
+		.into(),
+		ExitReason::Error(ExitError::OutOfGas) => internal_err("out of gas"),
+		ExitReason::Error(e) => internal_err(format!("evm error: {:?}", e)),
+		ExitReason::Fatal(e) => internal_err(format!("evm fatal: {:?}", e)),
+	};
+	Some(error)
+}
+
+/// `execution reverted`, with the reason of a Solidity `Error(string)` revert appended.
+pub fn revert_message(data: &[u8]) -> String {
+	// Selector of `Error(string)`, then the offset and length of the string.
+	const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+	let reason =
+		data.strip_prefix(&ERROR_SELECTOR[..])
+			.filter(|body| body.len() >= 64)
+			.and_then(|body| {
+				let length = U256::from_big_endian(&body[32..64]);
+				let end = 64usize.checked_add(usize::try_from(length).ok()?)?;
+				String::from_utf8(body.get(64..end)?.to_vec()).ok()
+			});
+
+	match reason {
+		Some(reason) => format!("execution reverted: {}", reason),
+		None => "execution reverted".into(),
+	}
+}
+
+fn hex(data: &[u8]) -> String {
+	data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl<B, C, E> EthSimulationApiServer for EthSimulation<B, C, E>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B> + EvmSimulationApi<B>,
+	E: EthApiServer + Send + Sync + 'static,
+{
+	fn call(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+		state_overrides: Option<BTreeMap<H160, CallStateOverride>>,
+	) -> RpcResult<Bytes> {
+		let state_overrides = state_overrides.unwrap_or_default();
+		if state_overrides.is_empty() {
+			return EthApiServer::call(&self.eth, request, number)
+		}
+
+		let at = self.block_id(number)?;
+		self.ensure_simulation_api(&at)?;
+		let request = self.simulation_request(&at, request)?;
+		let overrides = self::state_overrides(state_overrides)?;
+
+		let result = self
+			.client
+			.runtime_api()
+			.simulate(&at, request, overrides)
+			.map_err(|e| internal_err(format!("runtime error: {:?}", e)))?
+			.map_err(|e| internal_err(format!("execution fatal: {:?}", e)))?;
+
+		match execution_error(&result) {
+			Some(error) => Err(error),
+			None => Ok(Bytes(result.value)),
+		}
+	}
+
+	fn create_access_list(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> RpcResult<AccessListWithGasUsed> {
+		let at = self.block_id(number)?;
+		self.ensure_simulation_api(&at)?;
+		let request = self.simulation_request(&at, request)?;
+
+		let access_list = self
+			.client
+			.runtime_api()
+			.create_access_list(&at, request, Vec::new())
+			.map_err(|e| internal_err(format!("runtime error: {:?}", e)))?
+			.map_err(|e| internal_err(format!("execution fatal: {:?}", e)))?;
+
+		Ok(AccessListWithGasUsed {
+			error: execution_error(&access_list.result).map(|e| e.to_string()),
+			gas_used: access_list.result.used_gas,
+			access_list: access_list
+				.access_list
+				.into_iter()
+				.map(|(address, storage_keys)| AccessListItem { address, storage_keys })
+				.collect(),
+		})
+	}
+}
@@ -0,0 +1,224 @@
+//! Geth-compatible `txpool_*` RPC namespace for Ethereum transactions waiting in the pool.
+
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use ethereum::{TransactionAction, TransactionV2 as EthereumTransaction};
+use fc_rpc::{internal_err, public_key};
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use sc_transaction_pool::{ChainApi, Pool};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::keccak_256, Bytes, H160, H256, U256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Pool entries by sender and nonce.
+pub type Summary<T> = BTreeMap<H160, BTreeMap<u64, T>>;
+
+/// Ethereum transactions in the pool.
+#[derive(Debug, Serialize)]
+pub struct TxPoolResult<T> {
+	/// Transactions ready for inclusion.
+	pub pending: Summary<T>,
+	/// Transactions waiting for an earlier nonce.
+	pub queued: Summary<T>,
+}
+
+/// Number of Ethereum transactions in the pool.
+#[derive(Debug, Serialize)]
+pub struct TxPoolStatus {
+	/// Transactions ready for inclusion.
+	pub pending: U256,
+	/// Transactions waiting for an earlier nonce.
+	pub queued: U256,
+}
+
+/// A pooled Ethereum transaction, in the shape `txpool_content` returns it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxPoolTransaction {
+	/// Transaction hash.
+	pub hash: H256,
+	/// Sender nonce.
+	pub nonce: U256,
+	/// Always `null`, the transaction is not in a block.
+	pub block_hash: Option<H256>,
+	/// Always `null`, the transaction is not in a block.
+	pub block_number: Option<U256>,
+	/// Always `null`, the transaction is not in a block.
+	pub transaction_index: Option<U256>,
+	/// Sender.
+	pub from: H160,
+	/// Recipient, `null` for contract creation.
+	pub to: Option<H160>,
+	/// Transferred value.
+	pub value: U256,
+	/// Gas price, or the maximum fee per gas of EIP-1559 transactions.
+	pub gas_price: U256,
+	/// Maximum fee per gas of EIP-1559 transactions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_fee_per_gas: Option<U256>,
+	/// Maximum priority fee per gas of EIP-1559 transactions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_priority_fee_per_gas: Option<U256>,
+	/// Gas limit.
+	pub gas: U256,
+	/// Call data.
+	pub input: Bytes,
+}
+
+impl TxPoolTransaction {
+	fn new(transaction: &EthereumTransaction, from: H160) -> Self {
+		let (nonce, gas_price, max_fee_per_gas, max_priority_fee_per_gas, gas, action, value, input) =
+			match transaction {
+				EthereumTransaction::Legacy(t) =>
+					(t.nonce, t.gas_price, None, None, t.gas_limit, t.action, t.value, &t.input),
+				EthereumTransaction::EIP2930(t) =>
+					(t.nonce, t.gas_price, None, None, t.gas_limit, t.action, t.value, &t.input),
+				EthereumTransaction::EIP1559(t) => (
+					t.nonce,
+					t.max_fee_per_gas,
+					Some(t.max_fee_per_gas),
+					Some(t.max_priority_fee_per_gas),
+					t.gas_limit,
+					t.action,
+					t.value,
+					&t.input,
+				),
+			};
+
+		Self {
+			hash: transaction.hash(),
+			nonce,
+			block_hash: None,
+			block_number: None,
+			transaction_index: None,
+			from,
+			to: match action {
+				TransactionAction::Call(to) => Some(to),
+				TransactionAction::Create => None,
+			},
+			value,
+			gas_price,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			gas,
+			input: Bytes(input.clone()),
+		}
+	}
+
+	/// One-line summary as returned by `txpool_inspect`.
+	fn inspect(&self) -> String {
+		let to = match self.to {
+			Some(to) => format!("{:?}", to),
+			None => "contract creation".into(),
+		};
+		format!("{}: {} wei + {} gas × {} wei", to, self.value, self.gas, self.gas_price)
+	}
+}
+
+/// Ethereum transaction pool RPC methods.
+#[rpc(server)]
+pub trait TxPoolApi {
+	/// Pending and queued transactions by sender and nonce.
+	#[method(name = "txpool_content")]
+	fn content(&self) -> RpcResult<TxPoolResult<TxPoolTransaction>>;
+
+	/// Summaries of pending and queued transactions by sender and nonce.
+	#[method(name = "txpool_inspect")]
+	fn inspect(&self) -> RpcResult<TxPoolResult<String>>;
+
+	/// Number of pending and queued transactions.
+	#[method(name = "txpool_status")]
+	fn status(&self) -> RpcResult<TxPoolStatus>;
+}
+
+/// Implements the [`TxPoolApiServer`] RPC trait.
+pub struct TxPool<B, C, A: ChainApi> {
+	client: Arc<C>,
+	graph: Arc<Pool<A>>,
+	_marker: PhantomData<B>,
+}
+
+impl<B, C, A: ChainApi> TxPool<B, C, A> {
+	/// Create new `TxPool` over the given transaction pool.
+	pub fn new(client: Arc<C>, graph: Arc<Pool<A>>) -> Self {
+		Self { client, graph, _marker: Default::default() }
+	}
+}
+
+impl<B, C, A> TxPool<B, C, A>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	A: ChainApi<Block = B>,
+{
+	/// Ethereum transactions of the ready and the future queue.
+	fn transactions(&self) -> RpcResult<(Vec<EthereumTransaction>, Vec<EthereumTransaction>)> {
+		let pool = self.graph.validated_pool();
+		let ready = pool.ready().map(|transaction| transaction.data.clone()).collect();
+		let future = pool.futures().into_iter().map(|(_, extrinsic)| extrinsic).collect();
+
+		let at = BlockId::hash(self.client.info().best_hash);
+		let api = self.client.runtime_api();
+		let ready = api
+			.extrinsic_filter(&at, ready)
+			.map_err(|e| internal_err(format!("Unable to filter ready extrinsics: {:?}", e)))?;
+		let future = api
+			.extrinsic_filter(&at, future)
+			.map_err(|e| internal_err(format!("Unable to filter future extrinsics: {:?}", e)))?;
+		Ok((ready, future))
+	}
+
+	fn summary<T>(&self, entry: impl Fn(TxPoolTransaction) -> T) -> RpcResult<TxPoolResult<T>> {
+		let (ready, future) = self.transactions()?;
+		Ok(TxPoolResult { pending: by_sender(ready, &entry), queued: by_sender(future, &entry) })
+	}
+}
+
+fn sender(transaction: &EthereumTransaction) -> Option<H160> {
+	public_key(transaction)
+		.ok()
+		.map(|public| H160::from_slice(&keccak_256(&public)[12..]))
+}
+
+fn by_sender<T>(
+	transactions: Vec<EthereumTransaction>,
+	entry: &impl Fn(TxPoolTransaction) -> T,
+) -> Summary<T> {
+	let mut summary = Summary::new();
+	for transaction in transactions {
+		// The pool only admits transactions with a valid signature.
+		if let Some(from) = sender(&transaction) {
+			let transaction = TxPoolTransaction::new(&transaction, from);
+			summary
+				.entry(from)
+				.or_insert_with(BTreeMap::new)
+				.insert(transaction.nonce.low_u64(), entry(transaction));
+		}
+	}
+	summary
+}
+
+impl<B, C, A> TxPoolApiServer for TxPool<B, C, A>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	A: ChainApi<Block = B> + 'static,
+{
+	fn content(&self) -> RpcResult<TxPoolResult<TxPoolTransaction>> {
+		self.summary(|transaction| transaction)
+	}
+
+	fn inspect(&self) -> RpcResult<TxPoolResult<String>> {
+		self.summary(|transaction| transaction.inspect())
+	}
+
+	fn status(&self) -> RpcResult<TxPoolStatus> {
+		let (ready, future) = self.transactions()?;
+		Ok(TxPoolStatus { pending: ready.len().into(), queued: future.len().into() })
+	}
+}
@@ -0,0 +1,116 @@
+//! `validators_*` RPC namespace backed by the `ValidatorSetApi` runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use global_network_runtime::validators::{
+	RemovalRecord, SessionSchedule, ValidatorInfo, ValidatorSetApi,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{codec::Codec, generic::BlockId, traits::Block as BlockT};
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+
+/// Validator set RPC methods.
+#[rpc(server)]
+pub trait ValidatorsApi<BlockHash, AccountId, Keys, BlockNumber> {
+	/// Validators of the current session with their keys and `ImOnline` status.
+	#[method(name = "validators_current")]
+	fn current(&self, at: Option<BlockHash>) -> RpcResult<Vec<ValidatorInfo<AccountId, Keys>>>;
+
+	/// Validators that will take over at the next session rotation.
+	#[method(name = "validators_queued")]
+	fn queued(&self, at: Option<BlockHash>) -> RpcResult<Vec<ValidatorInfo<AccountId, Keys>>>;
+
+	/// Current session index and the number of blocks until the next rotation.
+	#[method(name = "validators_sessionSchedule")]
+	fn session_schedule(&self, at: Option<BlockHash>) -> RpcResult<SessionSchedule<BlockNumber>>;
+
+	/// Validators removed for missing heartbeats, with the session and reason of removal.
+	#[method(name = "validators_removed")]
+	fn removed(&self, at: Option<BlockHash>) -> RpcResult<Vec<(AccountId, RemovalRecord)>>;
+}
+
+/// Implements the [`ValidatorsApiServer`] RPC trait.
+pub struct Validators<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Validators<C, B> {
+	/// Create new `Validators` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+
+	fn at(&self, at: Option<<B as BlockT>::Hash>) -> BlockId<B>
+	where
+		B: BlockT,
+		C: HeaderBackend<B>,
+	{
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Display) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(RUNTIME_ERROR, message, Some(e.to_string()))).into()
+}
+
+impl<C, Block, AccountId, Keys, BlockNumber>
+	ValidatorsApiServer<<Block as BlockT>::Hash, AccountId, Keys, BlockNumber>
+	for Validators<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ValidatorSetApi<Block, AccountId, Keys, BlockNumber>,
+	AccountId: Codec + Serialize + Send + Sync + 'static,
+	Keys: Codec + Serialize + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + Send + Sync + 'static,
+{
+	fn current(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<ValidatorInfo<AccountId, Keys>>> {
+		self.client
+			.runtime_api()
+			.current_validators(&self.at(at))
+			.map_err(|e| runtime_error("Unable to query current validators.", e))
+	}
+
+	fn queued(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<ValidatorInfo<AccountId, Keys>>> {
+		self.client
+			.runtime_api()
+			.queued_validators(&self.at(at))
+			.map_err(|e| runtime_error("Unable to query queued validators.", e))
+	}
+
+	fn session_schedule(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<SessionSchedule<BlockNumber>> {
+		self.client
+			.runtime_api()
+			.session_schedule(&self.at(at))
+			.map_err(|e| runtime_error("Unable to query the session schedule.", e))
+	}
+
+	fn removed(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<(AccountId, RemovalRecord)>> {
+		self.client
+			.runtime_api()
+			.removed_validators(&self.at(at))
+			.map_err(|e| runtime_error("Unable to query removed validators.", e))
+	}
+}
@@ -1,20 +1,36 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use global_network_runtime::{self, opaque::Block, RuntimeApi};
+use global_network_runtime::{
+	self, opaque::Block, randomness_beacon::RandomnessBeaconApi, RuntimeApi,
+};
 use sc_client_api::{BlockBackend, BlockchainEvents};
 use sc_consensus_aura::{CompatibilityMode, ImportQueueParams, SlotProportion, StartAuraParams};
 pub use sc_executor::NativeElseWasmExecutor;
+use sc_chain_spec::Extension;
 use sc_finality_grandpa::SharedVoterState;
-use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager, BasePath};
+use sc_service::{
+	error::Error as ServiceError, BasePath, ChainType, Configuration, DatabaseSource, PruningMode,
+	TaskManager,
+};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sp_api::ProvideRuntimeApi;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_core::sr25519;
+use sp_keystore::CryptoStore;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 use std::{future, sync::{Arc, Mutex}, time::Duration, collections::BTreeMap};
 use fc_mapping_sync::{MappingSyncWorker, SyncStrategy};
-use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit};
+use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
 use fc_consensus::FrontierBlockImport;
 use futures::StreamExt;
 
+use crate::{
+	chain_spec::{Extensions, GrandpaParams, VotingRule},
+	cli::GrandpaCli,
+	eth::{log_index::LogIndex, EthConfiguration},
+	remote_keystore::{RemoteKeystore, RemoteKeystoreParams},
+};
+
 // Our native executor instance.
 pub struct ExecutorDispatch;
 
@@ -40,7 +56,10 @@ sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispat
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
-pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
+/// Number of blocks after which an unpolled `eth_newFilter` filter is removed.
+const FILTER_RETAIN_THRESHOLD: u64 = 100;
+
+pub fn frontier_database_dir(config: &Configuration, path: &str) -> std::path::PathBuf {
 	let config_dir = config
 		.base_path
 		.as_ref()
@@ -49,29 +68,39 @@ pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
 			BasePath::from_project("", "", config.chain_spec.id())
 				.config_dir(config.chain_spec.id())
 		});
-	config_dir.join("frontier").join("db")
+	config_dir.join("frontier").join(path)
 }
 
+/// Open the Frontier mapping database with the same kind of database as the node.
 pub fn open_frontier_backend<C>(
 	client: Arc<C>,
 	config: &Configuration,
+	eth_config: &EthConfiguration,
 ) -> Result<Arc<fc_db::Backend<Block>>, String>
 	where C: sp_blockchain::HeaderBackend<Block>,
 {
-	Ok(Arc::new(fc_db::Backend::<Block>::new(
-		client,
-		&fc_db::DatabaseSettings {
-			source: fc_db::DatabaseSource::RocksDb {
-				path: frontier_database_dir(&config),
-				cache_size: 0,
-			},
+	let source = match config.database {
+		DatabaseSource::RocksDb { .. } => DatabaseSource::RocksDb {
+			path: frontier_database_dir(config, "db"),
+			cache_size: eth_config.frontier_db_cache_size,
+		},
+		DatabaseSource::ParityDb { .. } =>
+			DatabaseSource::ParityDb { path: frontier_database_dir(config, "paritydb") },
+		DatabaseSource::Auto { .. } => DatabaseSource::Auto {
+			rocksdb_path: frontier_database_dir(config, "db"),
+			paritydb_path: frontier_database_dir(config, "paritydb"),
+			cache_size: eth_config.frontier_db_cache_size,
 		},
-	)?))
+		_ => return Err("Supported db sources: `rocksdb` | `paritydb` | `auto`".to_string()),
+	};
+
+	Ok(Arc::new(fc_db::Backend::<Block>::new(client, &fc_db::DatabaseSettings { source })?))
 }
 
 
 pub fn new_partial(
 	config: &Configuration,
+	eth_config: &EthConfiguration,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
@@ -98,10 +127,6 @@ pub fn new_partial(
 	>,
 	ServiceError,
 > {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other("Remote Keystores are not supported.".into()));
-	}
-
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -155,6 +180,7 @@ pub fn new_partial(
 	let frontier_backend = open_frontier_backend(
 		client.clone(),
 		config,
+		eth_config,
 	)?;
 
 	let frontier_block_import = FrontierBlockImport::new(
@@ -163,9 +189,8 @@ pub fn new_partial(
 		frontier_backend.clone(),
 	);
 
-	let fee_history_limit: u64 = 2048;
 	let fee_history_cache: FeeHistoryCache = Arc::new(Mutex::new(BTreeMap::new()));
-	let fee_history_cache_limit: FeeHistoryCacheLimit = fee_history_limit;
+	let fee_history_cache_limit: FeeHistoryCacheLimit = eth_config.fee_history_limit;
 	let fee_history = (fee_history_cache, fee_history_cache_limit);
 
 	let import_queue =
@@ -202,15 +227,88 @@ pub fn new_partial(
 	})
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//        must return a concrete type (NOT `LocalKeystore`) that
-	//        implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+/// Signs the randomness beacon VRF of `slot`, on top of `parent`, with the slot author's key.
+///
+/// Provides nothing when the keystore does not hold the author's key.
+async fn randomness_beacon(
+	client: &FullClient,
+	keystore: &dyn CryptoStore,
+	parent: <Block as BlockT>::Hash,
+	slot: u64,
+) -> pallet_randomness_beacon::inherent::InherentDataProvider {
+	let input = client.runtime_api().vrf_input(&BlockId::Hash(parent), slot).unwrap_or_else(|e| {
+		log::warn!("Unable to get the randomness beacon VRF input: {}", e);
+		None
+	});
+	match input {
+		Some((author, previous)) => pallet_randomness_beacon::inherent::InherentDataProvider::sign(
+			keystore,
+			sp_core::crypto::key_types::AURA,
+			&sr25519::Public::from_raw(author),
+			slot,
+			&previous,
+		)
+		.await,
+		None => pallet_randomness_beacon::inherent::InherentDataProvider(None),
+	}
+}
+
+fn remote_keystore(
+	url: &String,
+	params: &RemoteKeystoreParams,
+) -> Result<Arc<RemoteKeystore>, String> {
+	RemoteKeystore::open(url, params.tls_config()?).map(Arc::new)
+}
+
+/// GRANDPA settings of the chain spec with the command line overrides applied.
+pub fn grandpa_params(chain_spec: &dyn sc_service::ChainSpec, cli: &GrandpaCli) -> GrandpaParams {
+	let params = Extensions::try_get(chain_spec)
+		.map(|extensions| extensions.grandpa.clone())
+		.unwrap_or_default();
+	cli.apply(params)
+}
+
+/// Combine the configured voting rules, in order.
+pub fn grandpa_voting_rule<B, C>(
+	rules: &[VotingRule],
+) -> impl sc_finality_grandpa::VotingRule<B, C> + Clone
+where
+	B: BlockT,
+	C: sp_blockchain::HeaderBackend<B> + 'static,
+{
+	rules
+		.iter()
+		.fold(sc_finality_grandpa::VotingRulesBuilder::new(), |builder, rule| match rule {
+			VotingRule::BeforeBestBlockBy(blocks) =>
+				builder.add(sc_finality_grandpa::BeforeBestBlockBy(*blocks)),
+			VotingRule::ThreeQuartersOfTheUnfinalizedChain =>
+				builder.add(sc_finality_grandpa::ThreeQuartersOfTheUnfinalizedChain),
+		})
+		.build()
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	remote_keystore_params: RemoteKeystoreParams,
+	grandpa_cli: GrandpaCli,
+	eth_config: EthConfiguration,
+) -> Result<TaskManager, ServiceError> {
+	let grandpa_params = grandpa_params(&*config.chain_spec, &grandpa_cli);
+	if grandpa_params.justification_period == 0 {
+		return Err(ServiceError::Other("GRANDPA justification period must be non-zero".into()))
+	}
+	// The log index would record blocks whose state is pruned as having no Ethereum block.
+	let archive = matches!(
+		config.state_pruning,
+		Some(PruningMode::ArchiveAll) | Some(PruningMode::ArchiveCanonical)
+	);
+	if eth_config.frontier_log_index && !archive {
+		return Err(ServiceError::Other(
+			"--frontier-log-index needs --state-pruning archive or archive-canonical".into(),
+		))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -220,10 +318,10 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		select_chain,
 		transaction_pool,
 		other: (block_import, grandpa_link, frontier_backend, mut telemetry, fee_history),
-	} = new_partial(&config)?;
+	} = new_partial(&config, &eth_config)?;
 
 	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
+		match remote_keystore(url, &remote_keystore_params) {
 			Ok(k) => keystore_container.set_remote_keystore(k),
 			Err(e) =>
 				return Err(ServiceError::Other(format!(
@@ -275,19 +373,32 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let is_authority = config.role.is_authority();
 	let (fee_history_cache, fee_history_cache_limit) = fee_history;
+	let filter_pool: FilterPool = Arc::new(Mutex::new(BTreeMap::new()));
+	let enable_dev_signer = eth_config.enable_dev_signer ||
+		config.chain_spec.chain_type() == ChainType::Development;
+	let overrides = crate::rpc::overrides_handle(client.clone());
+	let log_index = if eth_config.frontier_log_index {
+		let path = frontier_database_dir(&config, "sql").join("logs.db3");
+		Some(Arc::new(LogIndex::open(&path).map_err(ServiceError::Other)?))
+	} else {
+		None
+	};
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 		let network = network.clone();
 		let frontier_backend = frontier_backend.clone();
-		let overrides = crate::rpc::overrides_handle(client.clone());
+		let overrides = overrides.clone();
+		let log_index = log_index.clone();
 		let fee_history_cache = fee_history_cache.clone();
+		let filter_pool = filter_pool.clone();
+		let eth_config = eth_config.clone();
 		let block_data_cache = Arc::new(fc_rpc::EthBlockDataCacheTask::new(
 			task_manager.spawn_handle(),
 			overrides.clone(),
-			50,
-			50,
+			eth_config.eth_log_block_cache,
+			eth_config.eth_statuses_cache,
 			prometheus_registry.clone(),
 		));
 
@@ -301,9 +412,16 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 					is_authority,
 					network: network.clone(),
 					backend: frontier_backend.clone(),
+					overrides: overrides.clone(),
 					block_data_cache: block_data_cache.clone(),
 					fee_history_cache: fee_history_cache.clone(),
 					fee_history_cache_limit,
+					filter_pool: Some(filter_pool.clone()),
+					max_past_logs: eth_config.max_past_logs,
+					max_log_block_range: eth_config.max_log_block_range,
+					log_index: log_index.clone(),
+					execute_gas_limit_multiplier: eth_config.execute_gas_limit_multiplier,
+					enable_dev_signer,
 				};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
@@ -338,6 +456,32 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			.for_each(|()| future::ready(())),
 	);
 
+	// Drop installed filters that have not been polled for a while.
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-filter-pool",
+		Some("frontier"),
+		fc_rpc::EthTask::filter_pool_task(client.clone(), filter_pool, FILTER_RETAIN_THRESHOLD),
+	);
+
+	if let Some(log_index) = log_index {
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"frontier-log-index",
+			Some("frontier"),
+			crate::eth::log_index::sync_task(client.clone(), overrides.clone(), log_index),
+		);
+	}
+
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-fee-history",
+		Some("frontier"),
+		fc_rpc::EthTask::fee_history_task(
+			client.clone(),
+			overrides,
+			fee_history_cache,
+			fee_history_cache_limit,
+		),
+	);
+
 	if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
@@ -348,6 +492,8 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 
 		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+		let beacon_client = client.clone();
+		let beacon_keystore = keystore_container.keystore();
 
 		let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
 			StartAuraParams {
@@ -356,16 +502,23 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				select_chain,
 				block_import,
 				proposer_factory,
-				create_inherent_data_providers: move |_, ()| async move {
-					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-					let slot =
-						sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
-							*timestamp,
-							slot_duration,
-						);
-
-					Ok((slot, timestamp))
+				create_inherent_data_providers: move |parent, ()| {
+					let client = beacon_client.clone();
+					let keystore = beacon_keystore.clone();
+					async move {
+						let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+						let slot =
+							sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+								*timestamp,
+								slot_duration,
+							);
+
+						let beacon =
+							randomness_beacon(&client, &*keystore, parent, (*slot).into()).await;
+
+						Ok((slot, timestamp, beacon))
+					}
 				},
 				force_authoring,
 				backoff_authoring_blocks,
@@ -393,11 +546,10 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			if role.is_authority() { Some(keystore_container.sync_keystore()) } else { None };
 
 		let grandpa_config = sc_finality_grandpa::Config {
-			// FIXME #1578 make this available through chainspec
-			gossip_duration: Duration::from_millis(333),
-			justification_period: 512,
+			gossip_duration: Duration::from_millis(grandpa_params.gossip_duration_ms),
+			justification_period: grandpa_params.justification_period,
 			name: Some(name),
-			observer_enabled: false,
+			observer_enabled: grandpa_params.observer_enabled,
 			keystore,
 			local_role: role,
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
@@ -414,7 +566,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			config: grandpa_config,
 			link: grandpa_link,
 			network,
-			voting_rule: sc_finality_grandpa::VotingRulesBuilder::default().build(),
+			voting_rule: grandpa_voting_rule(&grandpa_params.voting_rules),
 			prometheus_registry,
 			shared_voter_state: SharedVoterState::empty(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
@@ -0,0 +1,215 @@
+//! `session` sub-commands that help validators manage their session keys.
+//!
+//! Validator accounts are `AccountId20` and sign with `EthereumSignature`, which the default
+//! Polkadot-JS flow does not handle. `session rotate` does the whole rotation against a running
+//! node: it asks the node to generate fresh keys in its keystore, submits `Session::set_keys`
+//! signed with the validator's Ethereum key, and waits until the keys show up on chain.
+//!
+//! The Ethereum key is read from a file or from the environment, never from the command line,
+//! where it would show up in `ps` and in the shell history.
+
+use std::{path::PathBuf, time::Duration};
+
+use global_network_runtime::{
+	opaque::SessionKeys, AccountId, Index, Runtime, RuntimeCall, Signature, SignedExtra,
+	SignedPayload, UncheckedExtrinsic,
+};
+use jsonrpsee::{
+	core::client::ClientT,
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use sc_cli::{Result, RuntimeVersion};
+use sp_core::{
+	ecdsa,
+	hashing::{keccak_256, twox_128, twox_64},
+	Bytes, Decode, Encode, Pair, H256,
+};
+use sp_runtime::{
+	generic::Era,
+	traits::{IdentifyAccount, Verify},
+};
+
+/// How many times to look for the queued keys before giving up.
+const CONFIRMATION_ATTEMPTS: u32 = 20;
+
+/// Environment variable holding the signer key when `--signer-file` is not given.
+pub const SIGNER_ENV: &str = "SESSION_SIGNER";
+
+/// Sub-commands concerned with session keys.
+#[derive(Debug, clap::Subcommand)]
+pub enum SessionCmd {
+	/// Generate new session keys on a node and register them on chain.
+	Rotate(RotateCmd),
+}
+
+impl SessionCmd {
+	/// Run the sub-command.
+	pub fn run(&self) -> Result<()> {
+		match self {
+			SessionCmd::Rotate(cmd) => cmd.run(),
+		}
+	}
+}
+
+/// The `session rotate` command.
+#[derive(Debug, clap::Parser)]
+pub struct RotateCmd {
+	/// File holding the Ethereum private key of the validator account, as a hex seed or secret
+	/// URI. The key is read from the `SESSION_SIGNER` environment variable when not given.
+	#[arg(long, value_name = "PATH")]
+	pub signer_file: Option<PathBuf>,
+
+	/// WebSocket RPC endpoint of the node whose keystore receives the new keys.
+	#[arg(long, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// Seconds to wait between checks that the new keys are queued.
+	#[arg(long, default_value_t = 6)]
+	pub poll_interval: u64,
+}
+
+impl RotateCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let signer = self.signer()?;
+		let account = account_of(&signer);
+
+		sc_cli::build_runtime()?.block_on(self.rotate(signer, account))
+	}
+
+	/// The validator's Ethereum key, from `--signer-file` or `SESSION_SIGNER`.
+	pub fn signer(&self) -> Result<ecdsa::Pair> {
+		let (secret, source) = match &self.signer_file {
+			Some(path) => (
+				std::fs::read_to_string(path)
+					.map_err(|e| format!("failed to read {}: {}", path.display(), e))?,
+				path.display().to_string(),
+			),
+			None => (
+				std::env::var(SIGNER_ENV)
+					.map_err(|_| format!("pass --signer-file or set {}", SIGNER_ENV))?,
+				SIGNER_ENV.to_string(),
+			),
+		};
+		ecdsa::Pair::from_string(secret.trim(), None)
+			.map_err(|e| format!("invalid signer key in {}: {:?}", source, e).into())
+	}
+
+	async fn rotate(&self, signer: ecdsa::Pair, account: AccountId) -> Result<()> {
+		let client = WsClientBuilder::default()
+			.build(&self.url)
+			.await
+			.map_err(|e| format!("failed to connect to {}: {}", self.url, e))?;
+
+		let encoded_keys: Bytes = request(&client, "author_rotateKeys", rpc_params![]).await?;
+		let keys = SessionKeys::decode(&mut &encoded_keys[..])
+			.map_err(|e| format!("node returned keys the runtime cannot decode: {}", e))?;
+		println!("Generated session keys: {}", array_bytes::bytes2hex("0x", &encoded_keys));
+
+		let extrinsic = self.build_set_keys(&client, &signer, account, keys).await?;
+		let tx_hash: H256 = request(
+			&client,
+			"author_submitExtrinsic",
+			rpc_params![Bytes(extrinsic.encode())],
+		)
+		.await?;
+		println!("Submitted Session::set_keys for {:?} in {:?}", account, tx_hash);
+
+		let storage_key = next_keys_storage_key(&account);
+		for _ in 0..CONFIRMATION_ATTEMPTS {
+			let queued: Option<Bytes> =
+				request(&client, "state_getStorage", rpc_params![storage_key.clone()]).await?;
+			if queued.as_deref() == Some(&encoded_keys[..]) {
+				println!("New session keys are queued and take effect at the next session.");
+				return Ok(())
+			}
+			tokio::time::sleep(Duration::from_secs(self.poll_interval)).await;
+		}
+
+		Err(format!(
+			"keys were submitted in {:?} but are not queued yet; check the transaction status",
+			tx_hash
+		)
+		.into())
+	}
+
+	async fn build_set_keys(
+		&self,
+		client: &WsClient,
+		signer: &ecdsa::Pair,
+		account: AccountId,
+		keys: SessionKeys,
+	) -> Result<UncheckedExtrinsic> {
+		let nonce: Index =
+			request(client, "system_accountNextIndex", rpc_params![account]).await?;
+		let genesis_hash: H256 = request(client, "chain_getBlockHash", rpc_params![0]).await?;
+		let version: RuntimeVersion =
+			request(client, "state_getRuntimeVersion", rpc_params![]).await?;
+
+		Ok(set_keys_extrinsic(signer, nonce, genesis_hash, &version, keys))
+	}
+}
+
+/// The account an Ethereum key signs for.
+pub fn account_of(signer: &ecdsa::Pair) -> AccountId {
+	<<Signature as Verify>::Signer>::from(signer.public()).into_account()
+}
+
+/// An immortal `Session::set_keys` extrinsic signed by `signer`.
+pub fn set_keys_extrinsic(
+	signer: &ecdsa::Pair,
+	nonce: Index,
+	genesis_hash: H256,
+	version: &RuntimeVersion,
+	keys: SessionKeys,
+) -> UncheckedExtrinsic {
+	let call = RuntimeCall::Session(pallet_session::Call::set_keys { keys, proof: vec![] });
+	let extra: SignedExtra = (
+		frame_system::CheckNonZeroSender::<Runtime>::new(),
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_asset_tx_payment::ChargeAssetTxPayment::<Runtime>::from(0, None),
+	);
+
+	let raw_payload = SignedPayload::from_raw(
+		call.clone(),
+		extra.clone(),
+		(
+			(),
+			version.spec_version,
+			version.transaction_version,
+			genesis_hash,
+			genesis_hash,
+			(),
+			(),
+			(),
+		),
+	);
+	// `EthereumSignature` verifies against the keccak-256 hash of the payload.
+	let signature = raw_payload.using_encoded(|e| signer.sign_prehashed(&keccak_256(e)));
+
+	UncheckedExtrinsic::new_signed(call, account_of(signer), Signature::from(signature), extra)
+}
+
+async fn request<R: serde::de::DeserializeOwned>(
+	client: &WsClient,
+	method: &str,
+	params: jsonrpsee::core::params::ArrayParams,
+) -> Result<R> {
+	client.request(method, params).await.map_err(|e| format!("{} failed: {}", method, e).into())
+}
+
+/// Storage key of `Session::NextKeys` for `account`.
+fn next_keys_storage_key(account: &AccountId) -> Bytes {
+	let encoded = account.encode();
+	let mut key = twox_128(b"Session").to_vec();
+	key.extend_from_slice(&twox_128(b"NextKeys"));
+	key.extend_from_slice(&twox_64(&encoded));
+	key.extend_from_slice(&encoded);
+	Bytes(key)
+}
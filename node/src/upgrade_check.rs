@@ -0,0 +1,422 @@
+//! Offline runtime upgrade checks.
+//!
+//! `snapshot create` dumps the state of a block from the local database into a file.
+//! `upgrade-check` then puts a new runtime on top of that state, runs its migrations and
+//! reports the weight they take against `BlockWeights::max_block`, along with what changed in
+//! the metadata. Neither needs a running node, and the node itself does not have to be built
+//! with `try-runtime`:
+//!
+//! - a new runtime built with `try-runtime` runs the migrations through
+//!   `TryRuntime_on_runtime_upgrade`, so the pre and post upgrade checks and `try_state` run too;
+//! - any other runtime runs them the way a live chain does, by initializing the next block.
+//!
+//! Both `upgrade-check` and `metadata-diff` fail when the new runtime does not bump
+//! `spec_version` over the old one, or breaks signed transactions without bumping
+//! `transaction_version`.
+
+use std::{fs, path::PathBuf, sync::Arc};
+
+use global_network_runtime::{Block, BlockNumber, Header, Weight};
+use sc_cli::{
+	BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, Result, RuntimeVersion,
+	SharedParams,
+};
+use sc_client_api::UsageProvider;
+use sc_executor::{NativeExecutionDispatch, WasmExecutionMethod, WasmExecutor};
+use sc_executor_common::runtime_blob::RuntimeBlob;
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	hashing::{blake2_64, twox_128},
+	storage::{well_known_keys, ChildInfo, Storage, StorageChild},
+	Decode, Encode, H256,
+};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use sp_state_machine::BasicExternalities;
+
+use crate::{
+	metadata_diff::{self, Change, Metadata},
+	service::{ExecutorDispatch, FullClient},
+};
+
+/// Marks snapshot files, followed by the format version.
+const SNAPSHOT_MAGIC: &[u8; 8] = b"gnsnap\x00\x01";
+
+type HostFunctions = (
+	sp_io::SubstrateHostFunctions,
+	<ExecutorDispatch as NativeExecutionDispatch>::ExtendHostFunctions,
+);
+
+type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// State of a block, as written by `snapshot create`.
+pub struct Snapshot {
+	pub block_hash: H256,
+	pub block_number: BlockNumber,
+	pub storage: Storage,
+}
+
+impl Snapshot {
+	fn encode(&self) -> Vec<u8> {
+		let top: KeyValues = self.storage.top.clone().into_iter().collect();
+		let children: Vec<(Vec<u8>, KeyValues)> = self
+			.storage
+			.children_default
+			.iter()
+			.map(|(key, child)| (key.clone(), child.data.clone().into_iter().collect()))
+			.collect();
+
+		let mut encoded = SNAPSHOT_MAGIC.to_vec();
+		(self.block_hash, self.block_number, top, children).encode_to(&mut encoded);
+		encoded
+	}
+
+	fn decode(encoded: &[u8]) -> std::result::Result<Self, String> {
+		let data = encoded
+			.strip_prefix(&SNAPSHOT_MAGIC[..])
+			.ok_or("not a snapshot, or one written by an incompatible version")?;
+		let (block_hash, block_number, top, children) =
+			<(H256, BlockNumber, KeyValues, Vec<(Vec<u8>, KeyValues)>)>::decode(&mut &data[..])
+				.map_err(|e| format!("corrupt snapshot: {}", e))?;
+
+		let children_default = children
+			.into_iter()
+			.map(|(key, data)| {
+				let child = StorageChild {
+					data: data.into_iter().collect(),
+					child_info: ChildInfo::new_default(&key),
+				};
+				(key, child)
+			})
+			.collect();
+		Ok(Self {
+			block_hash,
+			block_number,
+			storage: Storage { top: top.into_iter().collect(), children_default },
+		})
+	}
+}
+
+/// Sub-commands concerned with state snapshots.
+#[derive(Debug, clap::Subcommand)]
+pub enum SnapshotCmd {
+	/// Write the state of a block to a file, for `upgrade-check`.
+	Create(SnapshotCreateCmd),
+}
+
+/// The `snapshot create` command.
+#[derive(Debug, clap::Parser)]
+pub struct SnapshotCreateCmd {
+	/// Block hash or number to snapshot. Defaults to the best block.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// File to write the snapshot to.
+	#[arg(long, short, default_value = "state.bin")]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl SnapshotCreateCmd {
+	/// Run the command against `client`.
+	pub fn run(&self, client: Arc<FullClient>) -> Result<()> {
+		let block_hash = match &self.at {
+			Some(at) => client.expect_block_hash_from_id(&at.parse::<Block>()?)?,
+			None => client.usage_info().chain.best_hash,
+		};
+		let block_number = client.expect_block_number_from_id(&BlockId::Hash(block_hash))?;
+		let storage = sc_service::chain_ops::export_raw_state(client, block_hash)?;
+
+		let snapshot = Snapshot { block_hash, block_number, storage };
+		fs::write(&self.output, snapshot.encode())?;
+		println!(
+			"Wrote the state of block #{} ({:?}) to {}",
+			block_number,
+			block_hash,
+			self.output.display()
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for SnapshotCreateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
+
+/// The `upgrade-check` command.
+#[derive(Debug, clap::Parser)]
+pub struct UpgradeCheckCmd {
+	/// Snapshot written by `snapshot create`.
+	#[arg(long)]
+	pub snapshot: PathBuf,
+
+	/// The runtime to upgrade to.
+	#[arg(long)]
+	pub wasm: PathBuf,
+
+	/// Heap pages to run the runtime with. Defaults to what the snapshot's chain uses.
+	#[arg(long)]
+	pub heap_pages: Option<u64>,
+}
+
+impl UpgradeCheckCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let snapshot = fs::read(&self.snapshot)?;
+		let mut snapshot = Snapshot::decode(&snapshot)?;
+		let new_code = fs::read(&self.wasm)?;
+		let old_code = snapshot
+			.storage
+			.top
+			.get(well_known_keys::CODE)
+			.cloned()
+			.ok_or("the snapshot holds no runtime code")?;
+
+		let heap_pages = self.heap_pages.or_else(|| {
+			let pages = snapshot.storage.top.get(well_known_keys::HEAP_PAGES)?;
+			u64::decode(&mut &pages[..]).ok()
+		});
+		let executor = executor(heap_pages);
+
+		let old_runtime = Runtime::load(&executor, &old_code)?;
+		let new_runtime = Runtime::load(&executor, &new_code)?;
+		println!(
+			"Upgrading block #{} ({:?}) from {} to {}",
+			snapshot.block_number, snapshot.block_hash, old_runtime, new_runtime,
+		);
+
+		snapshot.storage.top.insert(well_known_keys::CODE.to_vec(), new_code);
+		let mut ext = BasicExternalities::new(snapshot.storage);
+		let (consumed, max_block) = if new_runtime.has_api(b"TryRuntime") {
+			println!("Running migrations with pre and post upgrade checks and try_state");
+			let result =
+				new_runtime.call(&executor, &mut ext, "TryRuntime_on_runtime_upgrade", &[])?;
+			<(Weight, Weight)>::decode(&mut &result[..])
+				.map_err(|e| format!("invalid TryRuntime_on_runtime_upgrade result: {}", e))?
+		} else {
+			println!(
+				"Running migrations by initializing block #{}; build the runtime with \
+				`try-runtime` to also run the upgrade checks and try_state",
+				snapshot.block_number + 1
+			);
+			let header = Header::new(
+				snapshot.block_number + 1,
+				Default::default(),
+				Default::default(),
+				snapshot.block_hash,
+				Default::default(),
+			);
+			new_runtime.call(&executor, &mut ext, "Core_initialize_block", &header.encode())?;
+			(block_weight(&ext.into_storages())?, new_runtime.max_block()?)
+		};
+
+		let ref_time = percent(consumed.ref_time(), max_block.ref_time());
+		let proof_size = percent(consumed.proof_size(), max_block.proof_size());
+		println!(
+			"Weight: ref_time {} ({:.1}% of max_block), proof_size {} ({:.1}% of max_block)",
+			consumed.ref_time(),
+			ref_time,
+			consumed.proof_size(),
+			proof_size,
+		);
+
+		let changes = metadata_diff::diff(&old_runtime.metadata, &new_runtime.metadata);
+		report_changes(&changes);
+
+		if consumed.any_gt(max_block) {
+			return Err("the migrations do not fit in a block".into())
+		}
+		old_runtime.check_versions(&new_runtime, &changes)
+	}
+}
+
+/// The `metadata-diff` command.
+#[derive(Debug, clap::Parser)]
+pub struct MetadataDiffCmd {
+	/// The runtime currently on chain.
+	#[arg(long)]
+	pub old: PathBuf,
+
+	/// The runtime to upgrade to.
+	#[arg(long)]
+	pub new: PathBuf,
+}
+
+impl MetadataDiffCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let executor = executor(None);
+		let old_runtime = Runtime::load(&executor, &fs::read(&self.old)?)?;
+		let new_runtime = Runtime::load(&executor, &fs::read(&self.new)?)?;
+		println!("Comparing {} to {}", old_runtime, new_runtime);
+
+		let changes = metadata_diff::diff(&old_runtime.metadata, &new_runtime.metadata);
+		report_changes(&changes);
+		old_runtime.check_versions(&new_runtime, &changes)
+	}
+}
+
+fn executor(heap_pages: Option<u64>) -> WasmExecutor<HostFunctions> {
+	WasmExecutor::new(WasmExecutionMethod::default(), heap_pages, 1, None, 1)
+}
+
+/// Print `changes`, marking the ones that break transactions signed for the old runtime.
+fn report_changes(changes: &[Change]) {
+	if changes.is_empty() {
+		println!("Metadata: no changes");
+		return
+	}
+	println!("Metadata: {} changes, `!` breaks signed transactions", changes.len());
+	for change in changes {
+		let marker = if change.breaks_transactions() { '!' } else { '-' };
+		println!("  {} {}", marker, change);
+	}
+}
+
+/// Fail unless `new_version` bumps `spec_version` over `old_version` when the code changed, and
+/// `transaction_version` when one of `changes` breaks transactions signed for the old runtime.
+pub fn check_versions(
+	old_version: &RuntimeVersion,
+	new_version: &RuntimeVersion,
+	code_changed: bool,
+	changes: &[Change],
+) -> Result<()> {
+	let mut missing = Vec::new();
+
+	if code_changed && new_version.spec_version <= old_version.spec_version {
+		missing.push(format!(
+			"spec_version must be above {}, the runtime code changed",
+			old_version.spec_version
+		));
+	}
+	let breaking = changes.iter().filter(|change| change.breaks_transactions()).count();
+	if breaking > 0 && new_version.transaction_version <= old_version.transaction_version {
+		missing.push(format!(
+			"transaction_version must be above {}, {} changes break signed transactions",
+			old_version.transaction_version, breaking
+		));
+	}
+
+	if missing.is_empty() {
+		return Ok(())
+	}
+	for message in &missing {
+		eprintln!("Error: {}", message);
+	}
+	Err(missing.join("; ").into())
+}
+
+/// A runtime blob with the version and metadata read from it.
+struct Runtime {
+	code: Vec<u8>,
+	version: RuntimeVersion,
+	metadata: Metadata,
+}
+
+impl Runtime {
+	fn load(executor: &WasmExecutor<HostFunctions>, code: &[u8]) -> Result<Self> {
+		let blob = RuntimeBlob::uncompress_if_needed(code)
+			.map_err(|e| format!("invalid runtime code: {}", e))?;
+		let version = sc_executor::read_embedded_version(&blob)
+			.map_err(|e| format!("failed to read the runtime version: {}", e))?
+			.ok_or("the runtime code has no embedded version")?;
+
+		// Reading the metadata touches no storage.
+		let metadata = executor
+			.uncached_call(
+				blob,
+				&mut BasicExternalities::default(),
+				false,
+				"Metadata_metadata",
+				&[],
+			)
+			.map_err(|e| format!("Metadata_metadata failed: {}", e))?;
+		let metadata = Metadata::decode(&metadata)?;
+
+		Ok(Self { code: code.to_vec(), version, metadata })
+	}
+
+	/// [`check_versions`] of an upgrade from this runtime to `new`.
+	fn check_versions(&self, new: &Runtime, changes: &[Change]) -> Result<()> {
+		check_versions(&self.version, &new.version, self.code != new.code, changes)
+	}
+
+	fn has_api(&self, name: &[u8]) -> bool {
+		self.version.has_api_with(&blake2_64(name), |_| true)
+	}
+
+	fn call(
+		&self,
+		executor: &WasmExecutor<HostFunctions>,
+		ext: &mut BasicExternalities,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>> {
+		let blob = RuntimeBlob::uncompress_if_needed(&self.code)
+			.map_err(|e| format!("invalid runtime code: {}", e))?;
+		executor
+			.uncached_call(blob, ext, false, method, data)
+			.map_err(|e| format!("{} failed: {}", method, e).into())
+	}
+
+	/// `BlockWeights::max_block`, from the `System` constants in the metadata.
+	fn max_block(&self) -> Result<Weight> {
+		let encoded = self
+			.metadata
+			.constant("System", "BlockWeights")
+			.ok_or("the metadata has no System::BlockWeights constant")?;
+		let weights = frame_system::limits::BlockWeights::decode(&mut &encoded[..])
+			.map_err(|e| format!("invalid System::BlockWeights: {}", e))?;
+		Ok(weights.max_block)
+	}
+}
+
+impl std::fmt::Display for Runtime {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(
+			f,
+			"{} spec {} (transaction {})",
+			self.version.spec_name, self.version.spec_version, self.version.transaction_version
+		)
+	}
+}
+
+/// Weight consumed so far in the block, as recorded by `frame_system`.
+///
+/// This includes the `on_initialize` hooks of the block, not just the migrations.
+fn block_weight(storage: &Storage) -> Result<Weight> {
+	let key = [twox_128(b"System"), twox_128(b"BlockWeight")].concat();
+	let consumed = match storage.top.get(&key) {
+		Some(value) => frame_system::ConsumedWeight::decode(&mut &value[..])
+			.map_err(|e| format!("invalid System::BlockWeight: {}", e))?,
+		None => Default::default(),
+	};
+	Ok(consumed.total())
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+	if whole == 0 {
+		return 0.0
+	}
+	part as f64 * 100.0 / whole as f64
+}
@@ -0,0 +1,238 @@
+//! Assets of `pallet_assets` appear in the EVM at their own address.
+
+mod common;
+
+use fp_evm::{CallInfo, ExitReason, ExitRevert, ExitSucceed};
+use frame_support::traits::{Currency, Get};
+use global_network_runtime::{
+	asset_address, AccountId, AssetApprovalDeposit, Assets, Balances, Runtime, RuntimeOrigin,
+};
+use pallet_evm::Runner;
+use pallet_evm_precompile_assets_erc20::AssetsErc20Precompile;
+use sp_core::{H160, H256, U256};
+use sp_io::hashing::keccak_256;
+
+use common::development_state;
+
+const ASSET: u32 = 1;
+const NAME: &[u8] = b"Test Dollar";
+const ISSUER: H160 = H160::repeat_byte(0x1e);
+const SPENDER: H160 = H160::repeat_byte(0x5e);
+const BOB: H160 = H160::repeat_byte(0xb0);
+
+/// `transfer(address,uint256)`
+const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// `approve(address,uint256)`
+const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+/// `transferFrom(address,address,uint256)`
+const TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+/// `allowance(address,address)`
+const ALLOWANCE: [u8; 4] = [0xdd, 0x62, 0xed, 0x3e];
+/// `permit(address,address,uint256,uint256,uint8,bytes32,bytes32)`
+const PERMIT: [u8; 4] = [0xd5, 0x05, 0xac, 0xcf];
+/// `nonces(address)`
+const NONCES: [u8; 4] = [0x7e, 0xce, 0xbe, 0x00];
+
+/// The owner of the tokens, whose key signs permits.
+fn owner_key() -> libsecp256k1::SecretKey {
+	libsecp256k1::SecretKey::parse(&[0x42; 32]).unwrap()
+}
+
+fn owner() -> H160 {
+	let public = libsecp256k1::PublicKey::from_secret_key(&owner_key());
+	H160::from_slice(&keccak_256(&public.serialize()[1..])[12..])
+}
+
+/// Create [`ASSET`] and mint 1000 of it to the owner, who can pay approval deposits.
+fn create_asset() {
+	let issuer = AccountId::from(ISSUER);
+	Assets::force_create(RuntimeOrigin::root(), ASSET.into(), issuer, true, 1).unwrap();
+	Assets::force_set_metadata(
+		RuntimeOrigin::root(),
+		ASSET.into(),
+		NAME.to_vec(),
+		b"TUSD".to_vec(),
+		6,
+		false,
+	)
+	.unwrap();
+	Assets::mint(RuntimeOrigin::signed(issuer), ASSET.into(), owner().into(), 1_000).unwrap();
+	let _ = Balances::deposit_creating(&owner().into(), 10 * AssetApprovalDeposit::get());
+}
+
+fn word(value: u128) -> H256 {
+	let mut word = [0u8; 32];
+	U256::from(value).to_big_endian(&mut word);
+	H256(word)
+}
+
+fn call(source: H160, selector: [u8; 4], args: &[H256]) -> CallInfo {
+	let mut input = selector.to_vec();
+	args.iter().for_each(|arg| input.extend_from_slice(arg.as_bytes()));
+	<Runtime as pallet_evm::Config>::Runner::call(
+		source,
+		asset_address(ASSET.into()),
+		input,
+		U256::zero(),
+		1_000_000,
+		None,
+		None,
+		None,
+		Vec::new(),
+		false,
+		false,
+		<Runtime as pallet_evm::Config>::config(),
+	)
+	.unwrap_or_else(|_| panic!("the call to the asset executes"))
+}
+
+fn succeeds(info: CallInfo) -> U256 {
+	assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+	U256::from_big_endian(&info.value)
+}
+
+fn reverts(info: CallInfo) {
+	assert_eq!(info.exit_reason, ExitReason::Revert(ExitRevert::Reverted));
+}
+
+fn allowance(owner: H160, spender: H160) -> U256 {
+	succeeds(call(BOB, ALLOWANCE, &[owner.into(), spender.into()]))
+}
+
+fn balance(who: H160) -> u128 {
+	Assets::balance(ASSET, AccountId::from(who))
+}
+
+/// `permit` arguments for `value` to [`SPENDER`], signed by the owner with `nonce`.
+fn signed_permit(value: u128, nonce: u128) -> Vec<H256> {
+	let deadline = word(u64::MAX.into());
+	let mut domain = keccak_256(
+		b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+	)
+	.to_vec();
+	domain.extend(keccak_256(NAME));
+	domain.extend(keccak_256(b"1"));
+	domain.extend(word(<Runtime as pallet_evm::Config>::ChainId::get().into()).as_bytes());
+	domain.extend(H256::from(asset_address(ASSET.into())).as_bytes());
+	let mut permit = keccak_256(
+		b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+	)
+	.to_vec();
+	for field in [owner().into(), SPENDER.into(), word(value), word(nonce), deadline] {
+		permit.extend(H256::as_bytes(&field));
+	}
+	let digest =
+		keccak_256(&[&b"\x19\x01"[..], &keccak_256(&domain), &keccak_256(&permit)].concat());
+
+	let message = libsecp256k1::Message::parse(&digest);
+	let (signature, recovery_id) = libsecp256k1::sign(&message, &owner_key());
+	let rs = signature.serialize();
+	vec![
+		owner().into(),
+		SPENDER.into(),
+		word(value),
+		deadline,
+		word(27 + recovery_id.serialize() as u128),
+		H256::from_slice(&rs[..32]),
+		H256::from_slice(&rs[32..]),
+	]
+}
+
+#[test]
+fn asset_addresses_carry_the_asset_id() {
+	assert_eq!(
+		asset_address(1),
+		H160(hex_literal::hex!("ffffffff00000000000000000000000000000001")),
+	);
+}
+
+#[test]
+fn only_existing_assets_are_precompiles() {
+	development_state().execute_with(|| {
+		assert_eq!(AssetsErc20Precompile::<Runtime>::asset_at(asset_address(1)), None);
+
+		let owner = AccountId::from(H160::repeat_byte(0x01));
+		Assets::force_create(RuntimeOrigin::root(), 1u32.into(), owner, true, 1).unwrap();
+
+		assert_eq!(AssetsErc20Precompile::<Runtime>::asset_at(asset_address(1)), Some(1));
+		assert_eq!(AssetsErc20Precompile::<Runtime>::asset_at(asset_address(2)), None);
+		assert_eq!(AssetsErc20Precompile::<Runtime>::asset_at(H160::from_low_u64_be(1)), None);
+	});
+}
+
+#[test]
+fn transfer_moves_the_callers_tokens() {
+	development_state().execute_with(|| {
+		create_asset();
+
+		assert_eq!(succeeds(call(owner(), TRANSFER, &[BOB.into(), word(300)])), U256::one());
+		assert_eq!((balance(owner()), balance(BOB)), (700, 300));
+
+		reverts(call(BOB, TRANSFER, &[owner().into(), word(301)]));
+		assert_eq!((balance(owner()), balance(BOB)), (700, 300));
+	});
+}
+
+#[test]
+fn transfer_from_spends_the_allowance() {
+	development_state().execute_with(|| {
+		create_asset();
+
+		assert_eq!(succeeds(call(owner(), APPROVE, &[SPENDER.into(), word(500)])), U256::one());
+		assert_eq!(allowance(owner(), SPENDER), 500.into());
+
+		let transfer_from = [owner().into(), BOB.into(), word(300)];
+		assert_eq!(succeeds(call(SPENDER, TRANSFER_FROM, &transfer_from)), U256::one());
+		assert_eq!((balance(owner()), balance(BOB)), (700, 300));
+		assert_eq!(allowance(owner(), SPENDER), 200.into());
+		reverts(call(SPENDER, TRANSFER_FROM, &transfer_from));
+
+		// A new approval replaces the allowance left.
+		succeeds(call(owner(), APPROVE, &[SPENDER.into(), word(50)]));
+		assert_eq!(allowance(owner(), SPENDER), 50.into());
+		succeeds(call(owner(), APPROVE, &[SPENDER.into(), word(0)]));
+		assert_eq!(allowance(owner(), SPENDER), U256::zero());
+		reverts(call(SPENDER, TRANSFER_FROM, &[owner().into(), BOB.into(), word(1)]));
+	});
+}
+
+#[test]
+fn permit_approves_with_the_owners_signature() {
+	development_state().execute_with(|| {
+		create_asset();
+		let permit = signed_permit(400, 0);
+
+		succeeds(call(BOB, PERMIT, &permit));
+		assert_eq!(allowance(owner(), SPENDER), 400.into());
+		assert_eq!(succeeds(call(BOB, NONCES, &[owner().into()])), U256::one());
+
+		// The nonce was used: the same permit cannot be replayed.
+		succeeds(call(owner(), APPROVE, &[SPENDER.into(), word(0)]));
+		reverts(call(BOB, PERMIT, &permit));
+		assert_eq!(allowance(owner(), SPENDER), U256::zero());
+
+		succeeds(call(BOB, PERMIT, &signed_permit(100, 1)));
+		succeeds(call(SPENDER, TRANSFER_FROM, &[owner().into(), BOB.into(), word(100)]));
+		assert_eq!(balance(BOB), 100);
+	});
+}
+
+#[test]
+fn allowances_are_removed_with_the_asset() {
+	development_state().execute_with(|| {
+		create_asset();
+		succeeds(call(owner(), APPROVE, &[SPENDER.into(), word(500)]));
+		succeeds(call(BOB, PERMIT, &signed_permit(400, 0)));
+
+		let issuer = RuntimeOrigin::signed(AccountId::from(ISSUER));
+		Assets::start_destroy(RuntimeOrigin::root(), ASSET.into()).unwrap();
+		Assets::destroy_accounts(issuer.clone(), ASSET.into()).unwrap();
+		Assets::destroy_approvals(issuer.clone(), ASSET.into()).unwrap();
+		Assets::finish_destroy(issuer, ASSET.into()).unwrap();
+		create_asset();
+
+		assert_eq!(allowance(owner(), SPENDER), U256::zero());
+		// Permits signed for the destroyed asset stay used.
+		reverts(call(BOB, PERMIT, &signed_permit(400, 0)));
+	});
+}
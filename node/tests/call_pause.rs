@@ -0,0 +1,83 @@
+//! A paused `Ethereum::transact` is refused by transaction validation, so it never reaches the
+//! call filter, where it would fail without paying a fee.
+
+use ethereum::{LegacyTransactionMessage, TransactionAction};
+use fc_rpc::EthSigner;
+use fc_rpc_core::types::TransactionMessage;
+use fp_self_contained::SelfContainedCall;
+use frame_support::{assert_noop, assert_ok, dispatch::GetDispatchInfo, traits::Get};
+use global_network::{chain_spec::development_config, rpc::dev_signer::EthDevSigner};
+use global_network_runtime::{CallPause, Runtime, RuntimeCall, RuntimeOrigin, CALL_PAUSED};
+use sp_core::{Encode, H160, U256};
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionValidity},
+	BuildStorage,
+};
+
+/// A transfer from the first development account, signed for this chain.
+fn transfer() -> RuntimeCall {
+	let signer = EthDevSigner::new();
+	let message = TransactionMessage::Legacy(LegacyTransactionMessage {
+		nonce: U256::zero(),
+		gas_price: U256::from(10_000_000_000u64),
+		gas_limit: U256::from(21_000),
+		action: TransactionAction::Call(H160::repeat_byte(0xb0)),
+		value: U256::one(),
+		input: Vec::new(),
+		chain_id: Some(<Runtime as pallet_evm::Config>::ChainId::get()),
+	});
+	let transaction = signer.sign(message, &signer.accounts()[0]).unwrap();
+	RuntimeCall::Ethereum(pallet_ethereum::Call::transact { transaction })
+}
+
+fn validate(call: &RuntimeCall) -> TransactionValidity {
+	let sender = call.check_self_contained().unwrap().unwrap();
+	call.validate_self_contained(&sender, &call.get_dispatch_info(), call.encoded_size())
+		.unwrap()
+}
+
+fn pause(call: Option<&[u8]>) {
+	assert_ok!(CallPause::pause(
+		RuntimeOrigin::root(),
+		b"Ethereum".to_vec(),
+		call.map(<[u8]>::to_vec),
+		10
+	));
+}
+
+#[test]
+fn paused_ethereum_transactions_are_invalid() {
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		frame_system::Pallet::<Runtime>::set_block_number(1);
+		let call = transfer();
+		assert_ok!(validate(&call));
+
+		pause(Some(b"transact"));
+		assert_eq!(validate(&call), Err(InvalidTransaction::Custom(CALL_PAUSED).into()));
+
+		assert_ok!(CallPause::unpause(
+			RuntimeOrigin::root(),
+			b"Ethereum".to_vec(),
+			Some(b"transact".to_vec())
+		));
+		assert_ok!(validate(&call));
+
+		// Pausing the whole pallet pauses `transact` too.
+		pause(None);
+		assert_eq!(validate(&call), Err(InvalidTransaction::Custom(CALL_PAUSED).into()));
+	});
+}
+
+#[test]
+fn inherents_cannot_be_paused() {
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		for pallet in [&b"Timestamp"[..], b"RandomnessBeacon"] {
+			assert_noop!(
+				CallPause::pause(RuntimeOrigin::root(), pallet.to_vec(), None, 10),
+				pallet_call_pause::Error::<Runtime>::Whitelisted
+			);
+		}
+	});
+}
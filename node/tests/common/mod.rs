@@ -0,0 +1,49 @@
+//! Helpers shared by the node tests.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use global_network::{chain_spec::development_config, service::ExecutorDispatch};
+use global_network_runtime::{opaque::Block, RuntimeApi};
+use sc_executor::{NativeElseWasmExecutor, WasmExecutionMethod};
+use sp_core::storage::Storage;
+use sp_runtime::BuildStorage;
+use substrate_test_runtime_client::{GenesisInit, TestClientBuilder};
+
+/// A client of the development chain, running the native runtime.
+pub type DevClient =
+	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
+
+#[derive(Default)]
+struct Genesis(Storage);
+
+impl GenesisInit for Genesis {
+	fn genesis_storage(&self) -> Storage {
+		self.0.clone()
+	}
+}
+
+/// The development genesis state, to run the native runtime on.
+pub fn development_state() -> sp_io::TestExternalities {
+	sp_io::TestExternalities::new(development_config().unwrap().build_storage().unwrap())
+}
+
+/// A client whose only block is the development genesis.
+pub fn development_client() -> Arc<DevClient> {
+	client_with(development_config().unwrap().build_storage().unwrap())
+}
+
+/// A client whose only block is the genesis `storage`.
+pub fn client_with(storage: Storage) -> Arc<DevClient> {
+	let executor = NativeElseWasmExecutor::<ExecutorDispatch>::new(
+		WasmExecutionMethod::Interpreted,
+		None,
+		8,
+		2,
+	);
+	let mut builder = TestClientBuilder::<Block, _, _, Genesis>::with_default_backend();
+	*builder.genesis_init_mut() = Genesis(storage);
+	let (client, _) = builder.build_with_native_executor::<RuntimeApi, _>(executor);
+	Arc::new(client)
+}
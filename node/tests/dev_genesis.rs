@@ -0,0 +1,21 @@
+//! The development genesis builds, funds the `eth_sendTransaction` accounts and keeps its
+//! governance bodies to the well-known accounts.
+
+use fc_rpc::EthSigner;
+use global_network::{chain_spec::development_config, rpc::dev_signer::EthDevSigner};
+use global_network_runtime::{AccountId, Balances, TechnicalCommittee, EXISTENTIAL_DEPOSIT};
+use sp_runtime::BuildStorage;
+
+#[test]
+fn development_genesis_funds_the_dev_signer_accounts() {
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		for account in EthDevSigner::new().accounts() {
+			let balance = Balances::free_balance(AccountId::from(account));
+			assert!(balance >= EXISTENTIAL_DEPOSIT, "{:?} is not funded", account);
+		}
+
+		// ALITH and BALTATHAR, out of the four pre-funded accounts.
+		assert_eq!(TechnicalCommittee::members().len(), 2);
+	});
+}
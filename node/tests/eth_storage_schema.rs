@@ -0,0 +1,303 @@
+//! The Ethereum RPC reads blocks written under every `EthereumStorageSchema`, as a chain that
+//! went through `pallet_ethereum` upgrades has them.
+
+use std::{
+	net::TcpListener,
+	path::{Path, PathBuf},
+	process::{Child, Command},
+	sync::Arc,
+	thread,
+	time::Duration,
+};
+
+use ethereum::{
+	BlockV0, BlockV2, EIP658ReceiptData, LegacyTransaction, PartialHeader, ReceiptV0, ReceiptV3,
+	TransactionAction, TransactionSignature, TransactionV2,
+};
+use fp_consensus::{Hashes, PostLog, FRONTIER_ENGINE_ID};
+use fp_rpc::TransactionStatus;
+use fp_storage::{
+	EthereumStorageSchema, ETHEREUM_CURRENT_BLOCK, ETHEREUM_CURRENT_RECEIPTS,
+	ETHEREUM_CURRENT_TRANSACTION_STATUS, PALLET_ETHEREUM, PALLET_ETHEREUM_SCHEMA,
+};
+use futures::executor::block_on;
+use global_network::rpc::storage_overrides;
+use global_network_runtime::opaque::{Block, Header};
+use jsonrpsee::{
+	core::{client::ClientT, params::ArrayParams},
+	http_client::HttpClientBuilder,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::backend::{Backend as _, BlockImportOperation as _, NewBlockState};
+use sc_client_db::{BlocksPruning, DatabaseSettings, DatabaseSource};
+use serde_json::{json, Value};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{hashing::twox_128, H160, H256, U256};
+use sp_runtime::{
+	codec::Encode,
+	generic::{BlockId, Digest, DigestItem},
+	traits::{BlakeTwo256, Hash as _, Header as _},
+	StateVersion,
+};
+use sp_state_machine::Backend as _;
+use substrate_test_runtime_client::{
+	prelude::*, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+};
+
+fn storage_key(item: &[u8]) -> Vec<u8> {
+	[twox_128(PALLET_ETHEREUM), twox_128(item)].concat()
+}
+
+fn current_block_key() -> Vec<u8> {
+	storage_key(ETHEREUM_CURRENT_BLOCK)
+}
+
+fn partial_header(number: u64) -> PartialHeader {
+	PartialHeader {
+		parent_hash: H256::zero(),
+		beneficiary: H160::zero(),
+		state_root: H256::zero(),
+		receipts_root: H256::zero(),
+		logs_bloom: Default::default(),
+		difficulty: U256::zero(),
+		number: number.into(),
+		gas_limit: 15_000_000.into(),
+		gas_used: 21_000.into(),
+		timestamp: 1_000 * number,
+		extra_data: Vec::new(),
+		mix_hash: H256::zero(),
+		nonce: Default::default(),
+	}
+}
+
+fn transaction(nonce: u64) -> LegacyTransaction {
+	LegacyTransaction {
+		nonce: nonce.into(),
+		gas_price: 1_000_000_000.into(),
+		gas_limit: 21_000.into(),
+		action: TransactionAction::Call(H160::repeat_byte(0x11)),
+		value: 1.into(),
+		input: Vec::new(),
+		signature: TransactionSignature::new(
+			2_642,
+			H256::repeat_byte(0x01),
+			H256::repeat_byte(0x02),
+		)
+		.expect("valid signature values"),
+	}
+}
+
+/// Import a block storing `current_block` the way `pallet_ethereum` does under `schema`.
+fn import_block(
+	client: &mut Arc<TestClient>,
+	schema: EthereumStorageSchema,
+	current_block: Vec<u8>,
+) -> H256 {
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(PALLET_ETHEREUM_SCHEMA.to_vec(), Some(schema.encode())).unwrap();
+	builder.push_storage_change(current_block_key(), Some(current_block)).unwrap();
+	let block = builder.build().unwrap().block;
+	let hash = block.header.hash();
+	block_on(client.import(BlockOrigin::Own, block)).unwrap();
+	hash
+}
+
+#[test]
+fn reads_current_block_under_every_schema() {
+	let mut client = Arc::new(TestClientBuilder::new().build());
+	let overrides = storage_overrides(client.clone());
+
+	let v1 = BlockV0::new(partial_header(1), vec![transaction(0)], vec![]);
+	let v2 = BlockV2::new(partial_header(2), vec![TransactionV2::Legacy(transaction(1))], vec![]);
+	let v3 = BlockV2::new(partial_header(3), vec![TransactionV2::Legacy(transaction(2))], vec![]);
+
+	let written = [
+		(EthereumStorageSchema::V1, v1.encode(), v1.header.hash(), v1.transactions[0].hash()),
+		(EthereumStorageSchema::V2, v2.encode(), v2.header.hash(), v2.transactions[0].hash()),
+		(EthereumStorageSchema::V3, v3.encode(), v3.header.hash(), v3.transactions[0].hash()),
+	];
+
+	for (schema, encoded, header_hash, transaction_hash) in written {
+		let hash = import_block(&mut client, schema, encoded);
+
+		let current = overrides[&schema]
+			.current_block(&BlockId::Hash(hash))
+			.unwrap_or_else(|| panic!("block written under {:?} is readable", schema));
+		assert_eq!(current.header.hash(), header_hash);
+		assert_eq!(current.transactions.len(), 1);
+		assert_eq!(current.transactions[0].hash(), transaction_hash);
+	}
+}
+
+/// A `global-network --chain dev` process, not authoring blocks.
+struct Node {
+	process: Child,
+	rpc_port: u16,
+}
+
+impl Node {
+	fn start(base_path: &Path) -> Self {
+		let port = || TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+		let rpc_port = port();
+		let process = Command::new(env!("CARGO_BIN_EXE_global-network"))
+			.args(["--chain", "dev", "--database", "rocksdb", "--no-prometheus", "--no-mdns"])
+			.arg("--base-path")
+			.arg(base_path)
+			.args(["--rpc-port", &rpc_port.to_string()])
+			.args(["--ws-port", &port().to_string()])
+			.args(["--port", &port().to_string()])
+			.spawn()
+			.expect("the node starts");
+		Self { process, rpc_port }
+	}
+
+	/// The result of `method`, once the node answers it with something other than `null`.
+	fn call(&self, method: &str, params: Value) -> Value {
+		let runtime = tokio::runtime::Runtime::new().unwrap();
+		let _guard = runtime.enter();
+		let client = HttpClientBuilder::default()
+			.build(format!("http://127.0.0.1:{}", self.rpc_port))
+			.expect("valid RPC endpoint");
+		for _ in 0..300 {
+			let mut array = ArrayParams::new();
+			for param in params.as_array().into_iter().flatten() {
+				array.insert(param).unwrap();
+			}
+			let response = runtime.block_on(client.request::<Value, _>(method, array)).ok();
+			match response {
+				Some(Value::Null) | None => thread::sleep(Duration::from_millis(100)),
+				Some(result) => return result,
+			}
+		}
+		panic!("no answer to {} {}", method, params)
+	}
+}
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.process.kill();
+		let _ = self.process.wait();
+	}
+}
+
+/// Append a block on top of the best block of the database at `path`, with `changes` applied to
+/// the state of its parent, the way `pallet_ethereum` of an older runtime would have left it.
+fn append_block(path: &Path, changes: Vec<(Vec<u8>, Vec<u8>)>, digest: DigestItem) -> H256 {
+	let backend = sc_client_db::Backend::<Block>::new(
+		DatabaseSettings {
+			trie_cache_maximum_size: None,
+			state_pruning: None,
+			source: DatabaseSource::RocksDb { path: path.into(), cache_size: 16 },
+			blocks_pruning: BlocksPruning::KeepAll,
+		},
+		0,
+	)
+	.expect("the node database opens");
+	let info = backend.blockchain().info();
+
+	let state = backend.state_at(info.best_hash).unwrap();
+	let delta = changes.iter().map(|(key, value)| (&key[..], Some(&value[..])));
+	let (state_root, transaction) = state.storage_root(delta, StateVersion::V1);
+	let header = Header::new(
+		info.best_number + 1,
+		BlakeTwo256::ordered_trie_root(Vec::new(), StateVersion::V1),
+		state_root,
+		info.best_hash,
+		Digest { logs: vec![digest] },
+	);
+
+	let mut operation = backend.begin_operation().unwrap();
+	backend.begin_state_operation(&mut operation, info.best_hash).unwrap();
+	operation.update_db_storage(transaction).unwrap();
+	operation
+		.set_block_data(header.clone(), Some(Vec::new()), None, None, NewBlockState::Best)
+		.unwrap();
+	backend.commit_operation(operation).unwrap();
+	header.hash()
+}
+
+#[test]
+fn rpc_serves_blocks_and_receipts_of_every_schema() {
+	let base_path: PathBuf =
+		std::env::temp_dir().join(format!("eth-storage-schema-{}", std::process::id()));
+	let _ = std::fs::remove_dir_all(&base_path);
+
+	// Let the node write its genesis, then stop it to own the database.
+	let node = Node::start(&base_path);
+	node.call("chain_getBlockHash", json!([0]));
+	drop(node);
+
+	let receipt_v0 = ReceiptV0 {
+		state_root: H256::zero(),
+		used_gas: 21_000.into(),
+		logs_bloom: Default::default(),
+		logs: vec![],
+	};
+	let receipt_v3 = ReceiptV3::Legacy(EIP658ReceiptData {
+		status_code: 1,
+		used_gas: 21_000.into(),
+		logs_bloom: Default::default(),
+		logs: vec![],
+	});
+	let v1 = BlockV0::new(partial_header(1), vec![transaction(0)], vec![]);
+	let v2 = BlockV2::new(partial_header(2), vec![TransactionV2::Legacy(transaction(1))], vec![]);
+	let v3 = BlockV2::new(partial_header(3), vec![TransactionV2::Legacy(transaction(2))], vec![]);
+	let written = [
+		(EthereumStorageSchema::V1, v1.encode(), vec![receipt_v0.clone()].encode(), &v1.header),
+		(EthereumStorageSchema::V2, v2.encode(), vec![receipt_v0].encode(), &v2.header),
+		(EthereumStorageSchema::V3, v3.encode(), vec![receipt_v3].encode(), &v3.header),
+	];
+	let transaction_hashes =
+		[v1.transactions[0].hash(), v2.transactions[0].hash(), v3.transactions[0].hash()];
+
+	let db_path = base_path.join("chains").join("dev").join("db").join("full");
+	for ((schema, block, receipts, header), transaction_hash) in
+		written.iter().zip(transaction_hashes)
+	{
+		let status = TransactionStatus {
+			transaction_hash,
+			transaction_index: 0,
+			from: H160::repeat_byte(0x22),
+			to: Some(H160::repeat_byte(0x11)),
+			..Default::default()
+		};
+		let digest = PostLog::Hashes(Hashes {
+			block_hash: header.hash(),
+			transaction_hashes: vec![transaction_hash],
+		});
+		append_block(
+			&db_path,
+			vec![
+				(PALLET_ETHEREUM_SCHEMA.to_vec(), schema.encode()),
+				(current_block_key(), block.clone()),
+				(storage_key(ETHEREUM_CURRENT_RECEIPTS), receipts.clone()),
+				(storage_key(ETHEREUM_CURRENT_TRANSACTION_STATUS), vec![status].encode()),
+			],
+			DigestItem::Consensus(FRONTIER_ENGINE_ID, digest.encode()),
+		);
+	}
+
+	let node = Node::start(&base_path);
+	for (number, ((schema, _, _, header), transaction_hash)) in
+		written.iter().zip(transaction_hashes).enumerate()
+	{
+		let number = format!("0x{:x}", number + 1);
+
+		let block = node.call("eth_getBlockByNumber", json!([number, false]));
+		assert_eq!(block["hash"], json!(header.hash()), "block hash under {:?}", schema);
+		assert_eq!(block["number"], json!(number));
+		assert_eq!(block["transactions"], json!([transaction_hash]));
+
+		// Receipts are found through the mapping database, which the node fills in the
+		// background.
+		let receipt = node.call("eth_getTransactionReceipt", json!([transaction_hash]));
+		assert_eq!(receipt["blockHash"], json!(header.hash()), "receipt under {:?}", schema);
+		assert_eq!(receipt["blockNumber"], json!(number));
+		assert_eq!(receipt["transactionHash"], json!(transaction_hash));
+		assert_eq!(receipt["gasUsed"], json!("0x5208"));
+	}
+
+	drop(node);
+	let _ = std::fs::remove_dir_all(&base_path);
+}
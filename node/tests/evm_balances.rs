@@ -0,0 +1,54 @@
+//! `balances_evmAccount` reports the `pallet_balances` view of an account next to the EVM one.
+
+mod common;
+
+use frame_support::traits::{Currency, LockableCurrency, ReservableCurrency, WithdrawReasons};
+use global_network_runtime::{
+	evm_balances::{runtime_decl_for_EvmBalancesApi::EvmBalancesApi, EvmAccountBalance},
+	AccountId, Balance, Balances, Block, Index, Runtime, System, EXISTENTIAL_DEPOSIT,
+};
+use sp_core::{H160, U256};
+
+use common::development_state;
+
+const ED: Balance = EXISTENTIAL_DEPOSIT;
+
+fn evm_account(address: H160) -> EvmAccountBalance<Balance, Index> {
+	<Runtime as EvmBalancesApi<Block, Balance, Index>>::evm_account_balance(address)
+}
+
+#[test]
+fn reports_free_reserved_and_evm_visible_balances() {
+	development_state().execute_with(|| {
+		let address = H160::repeat_byte(0xe1);
+		let who = AccountId::from(address);
+		let _ = Balances::deposit_creating(&who, 100 * ED);
+		Balances::reserve(&who, 30 * ED).unwrap();
+		Balances::set_lock(*b"testlock", &who, 20 * ED, WithdrawReasons::TRANSFER);
+		System::inc_account_nonce(&who);
+
+		assert_eq!(
+			evm_account(address),
+			EvmAccountBalance {
+				free: 70 * ED,
+				reserved: 30 * ED,
+				misc_frozen: 20 * ED,
+				fee_frozen: 0,
+				// The EVM sees the free balance, but cannot move the locked part of it.
+				evm_balance: U256::from(70 * ED),
+				evm_spendable: 50 * ED,
+				nonce: 1,
+			}
+		);
+	});
+}
+
+#[test]
+fn reports_zero_for_unknown_accounts() {
+	development_state().execute_with(|| {
+		let balance = evm_account(H160::repeat_byte(0xe2));
+		assert_eq!((balance.free, balance.reserved, balance.evm_spendable), (0, 0, 0));
+		assert_eq!(balance.evm_balance, U256::zero());
+		assert_eq!(balance.nonce, 0);
+	});
+}
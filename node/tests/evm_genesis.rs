@@ -0,0 +1,104 @@
+//! EVM accounts loaded into genesis are served by `eth_getCode` and `eth_getStorageAt`, which
+//! answer from the runtime's `account_code_at` and `storage_at`.
+
+mod common;
+
+use std::sync::Arc;
+
+use fp_rpc::EthereumRuntimeRPCApi;
+use global_network::chain_spec::{
+	development_config_with, evm_accounts_from_json, evm_genesis_artifacts,
+};
+use global_network_runtime::{
+	system_contracts::{
+		DETERMINISTIC_DEPLOYMENT_PROXY, DETERMINISTIC_DEPLOYMENT_PROXY_CODE, PRECOMPILES_WITH_CODE,
+	},
+	PRECOMPILE_CODE, WGNF_ADDRESS,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{generic::BlockId, BuildStorage};
+
+use common::{client_with, development_client, DevClient};
+
+/// The code `eth_getCode` returns for `address` at genesis.
+fn code_at(client: &DevClient, address: H160) -> Vec<u8> {
+	client.runtime_api().account_code_at(&BlockId::number(0), address).unwrap()
+}
+
+/// The value `eth_getStorageAt` returns for `address` and `index` at genesis.
+fn storage_at(client: &DevClient, address: H160, index: U256) -> H256 {
+	client.runtime_api().storage_at(&BlockId::number(0), address, index).unwrap()
+}
+
+/// A client of the development chain with the EVM genesis artifacts `artifacts`.
+fn client_with_artifacts(artifacts: &str) -> Arc<DevClient> {
+	client_with(development_config_with(artifacts).unwrap().build_storage().unwrap())
+}
+
+#[test]
+fn development_genesis_serves_precompile_code() {
+	let client = development_client();
+
+	for address in PRECOMPILES_WITH_CODE {
+		assert_eq!(code_at(&client, address), PRECOMPILE_CODE.to_vec(), "code at {:?}", address);
+	}
+}
+
+#[test]
+fn development_genesis_serves_system_contracts() {
+	let client = development_client();
+
+	assert_eq!(
+		code_at(&client, DETERMINISTIC_DEPLOYMENT_PROXY),
+		DETERMINISTIC_DEPLOYMENT_PROXY_CODE.to_vec(),
+	);
+}
+
+#[test]
+fn artifact_accounts_are_served_from_genesis() {
+	let address = H160::repeat_byte(0xca);
+	let json = r#"{
+		"0xcacacacacacacacacacacacacacacacacacacaca": {
+			"code": "0x6080604052",
+			"storage": {
+				"0x0000000000000000000000000000000000000000000000000000000000000001":
+					"0x00000000000000000000000000000000000000000000000000000000000000ff"
+			},
+			"nonce": "0x1"
+		}
+	}"#;
+
+	let client = client_with_artifacts(json);
+
+	assert_eq!(code_at(&client, address), vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+	assert_eq!(storage_at(&client, address, U256::one()), H256::from_low_u64_be(0xff));
+	assert!(code_at(&client, H160::repeat_byte(0xcb)).is_empty());
+}
+
+#[test]
+fn artifacts_are_loaded_from_a_file() {
+	let path = std::env::temp_dir().join(format!("evm-genesis-{}.json", std::process::id()));
+	std::fs::write(
+		&path,
+		r#"{ "0xcacacacacacacacacacacacacacacacacacacaca": { "code": "0x00" } }"#,
+	)
+	.unwrap();
+	let artifacts = evm_genesis_artifacts(Some(&path)).unwrap();
+	std::fs::remove_file(&path).unwrap();
+
+	let client = client_with_artifacts(&artifacts);
+
+	assert_eq!(code_at(&client, H160::repeat_byte(0xca)), vec![0x00]);
+	assert_eq!(code_at(&client, WGNF_ADDRESS), PRECOMPILE_CODE.to_vec());
+	assert!(evm_genesis_artifacts(Some(&path)).is_err());
+}
+
+#[test]
+fn malformed_artifacts_are_rejected() {
+	assert!(evm_accounts_from_json(r#"{ "0xcaca": {} }"#).is_err());
+	assert!(evm_accounts_from_json(
+		r#"{ "0xcacacacacacacacacacacacacacacacacacacaca": { "bytecode": "0x00" } }"#
+	)
+	.is_err());
+}
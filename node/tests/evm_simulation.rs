@@ -0,0 +1,116 @@
+//! Call simulation behind `eth_call` state overrides and `eth_createAccessList`.
+
+use fp_evm::{ExitReason, ExitSucceed};
+use global_network::{chain_spec::development_config, rpc::simulation::revert_message};
+use global_network_runtime::evm_simulation::{
+	apply_overrides, create_access_list, create_address, SimulationRequest, StateOverride,
+};
+use hex_literal::hex;
+use sp_core::{H160, H256, U256};
+use sp_runtime::BuildStorage;
+
+/// ABI encoding of a Solidity `Error(string)` revert with `reason`.
+fn error_string(reason: &str) -> Vec<u8> {
+	let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+	data.extend_from_slice(H256::from_low_u64_be(0x20).as_bytes());
+	data.extend_from_slice(H256::from_low_u64_be(reason.len() as u64).as_bytes());
+	data.extend_from_slice(reason.as_bytes());
+	data.resize(data.len() + (32 - reason.len() % 32) % 32, 0);
+	data
+}
+
+#[test]
+fn revert_message_decodes_error_strings() {
+	assert_eq!(revert_message(&error_string("not the owner")), "execution reverted: not the owner");
+	assert_eq!(revert_message(&[]), "execution reverted");
+	// A custom error, and an `Error(string)` whose length runs past the data.
+	assert_eq!(revert_message(&[0xde, 0xad, 0xbe, 0xef]), "execution reverted");
+	let mut truncated = error_string("not the owner");
+	truncated.truncate(4 + 64 + 4);
+	assert_eq!(revert_message(&truncated), "execution reverted");
+}
+
+#[test]
+fn access_list_holds_touched_accounts_and_slots() {
+	let caller = H160::repeat_byte(0x01);
+	let contract = H160::repeat_byte(0x11);
+	let other = H160::repeat_byte(0x42);
+
+	// SLOAD(1), BALANCE(other), STOP.
+	let mut code = vec![0x60, 0x01, 0x54, 0x50, 0x73];
+	code.extend_from_slice(other.as_bytes());
+	code.extend_from_slice(&[0x31, 0x50, 0x00]);
+
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		apply_overrides(vec![StateOverride {
+			address: contract,
+			code: Some(code),
+			..Default::default()
+		}]);
+
+		let access_list = create_access_list(SimulationRequest {
+			from: caller,
+			to: Some(contract),
+			data: Vec::new(),
+			value: U256::zero(),
+			gas_limit: U256::from(1_000_000),
+			max_fee_per_gas: None,
+			max_priority_fee_per_gas: None,
+			nonce: None,
+			access_list: Vec::new(),
+		})
+		.unwrap();
+
+		assert_eq!(access_list.result.exit_reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		// The callee is kept for its slot, the caller is warm anyway and left out.
+		assert_eq!(
+			access_list.access_list,
+			vec![(contract, vec![H256::from_low_u64_be(1)]), (other, vec![])]
+		);
+	});
+}
+
+#[test]
+fn create_address_follows_the_create_scheme() {
+	let sender = H160(hex!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0"));
+	let created = [
+		hex!("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+		hex!("343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+		hex!("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+		hex!("fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+	];
+	for (nonce, created) in created.into_iter().enumerate() {
+		assert_eq!(create_address(sender, U256::from(nonce as u64)), H160(created));
+	}
+}
+
+#[test]
+fn access_list_of_a_creation_leaves_out_the_created_contract() {
+	let caller = H160::repeat_byte(0x01);
+	let other = H160::repeat_byte(0x42);
+
+	// BALANCE(ADDRESS), BALANCE(other), STOP.
+	let mut init_code = vec![0x30, 0x31, 0x50, 0x73];
+	init_code.extend_from_slice(other.as_bytes());
+	init_code.extend_from_slice(&[0x31, 0x50, 0x00]);
+
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		let access_list = create_access_list(SimulationRequest {
+			from: caller,
+			to: None,
+			data: init_code,
+			value: U256::zero(),
+			gas_limit: U256::from(1_000_000),
+			max_fee_per_gas: None,
+			max_priority_fee_per_gas: None,
+			nonce: None,
+			access_list: Vec::new(),
+		})
+		.unwrap();
+
+		assert_eq!(access_list.result.exit_reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert_eq!(access_list.access_list, vec![(other, vec![])]);
+	});
+}
@@ -0,0 +1,123 @@
+//! Who pays for extrinsics paid by a sponsor or in an asset, and how much.
+
+mod common;
+
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::{tokens::BalanceConversion, Currency},
+};
+use global_network_runtime::{
+	AccountId, AssetRates, Assets, Balances, EvmPaymaster, Runtime, RuntimeCall, RuntimeOrigin,
+	TransactionPayment, Treasury,
+};
+use pallet_asset_tx_payment::ChargeAssetTxPayment;
+use sp_core::H160;
+use sp_runtime::{traits::SignedExtension, FixedPointNumber, FixedU128};
+
+use common::development_state;
+
+const ASSET: u32 = 7;
+const LEN: usize = 100;
+const MINTED: u128 = 1 << 100;
+
+fn sponsored_call(source: H160, target: H160, gas_limit: u64) -> RuntimeCall {
+	RuntimeCall::EvmPaymaster(pallet_evm_paymaster::Call::sponsored_call {
+		source,
+		target,
+		input: Vec::new(),
+		gas_limit,
+	})
+}
+
+/// Charge `call` to `who` as the runtime's signed extension does, in `asset` if given.
+fn charge(who: &AccountId, call: &RuntimeCall, asset: Option<u32>) {
+	let info = call.get_dispatch_info();
+	let pre = ChargeAssetTxPayment::<Runtime>::from(0, asset)
+		.pre_dispatch(who, call, &info, LEN)
+		.unwrap();
+	ChargeAssetTxPayment::<Runtime>::post_dispatch(
+		Some(pre),
+		&info,
+		&PostDispatchInfo::default(),
+		LEN,
+		&Ok(()),
+	)
+	.unwrap();
+}
+
+#[test]
+fn sponsored_calls_are_paid_by_the_contract() {
+	development_state().execute_with(|| {
+		let user = H160::repeat_byte(0x01);
+		let contract = H160::repeat_byte(0xaa);
+		let call = sponsored_call(user, contract, 100_000);
+		assert_eq!(EvmPaymaster::sponsor_of(&user.into(), &call), None);
+
+		EvmPaymaster::set_sponsorship(RuntimeOrigin::root(), contract, 100_000).unwrap();
+
+		assert_eq!(EvmPaymaster::sponsor_of(&user.into(), &call), Some(contract.into()));
+		let over_the_cap = sponsored_call(user, contract, 100_001);
+		assert_eq!(EvmPaymaster::sponsor_of(&user.into(), &over_the_cap), None);
+		let other = H160::repeat_byte(0x02);
+		assert_eq!(EvmPaymaster::sponsor_of(&other.into(), &call), None);
+
+		let fee = TransactionPayment::compute_fee(LEN as u32, &call.get_dispatch_info(), 0);
+		let _ = Balances::deposit_creating(&contract.into(), 2 * fee);
+		charge(&user.into(), &call, None);
+		assert_eq!(Balances::free_balance(AccountId::from(contract)), fee);
+		assert_eq!(Balances::free_balance(AccountId::from(user)), 0);
+	});
+}
+
+#[test]
+fn asset_fees_follow_the_governance_rate() {
+	development_state().execute_with(|| {
+		assert!(AssetRates::to_asset_balance(1_000, ASSET).is_err());
+
+		let rate = FixedU128::saturating_from_rational(3, 2);
+		AssetRates::set_rate(RuntimeOrigin::root(), ASSET, rate).unwrap();
+
+		assert_eq!(AssetRates::to_asset_balance(1_000, ASSET).unwrap(), 1_500);
+	});
+}
+
+#[test]
+fn fees_are_charged_in_an_asset() {
+	development_state().execute_with(|| {
+		let issuer = AccountId::from(H160::repeat_byte(0x1e));
+		let payer = AccountId::from(H160::repeat_byte(0x01));
+		Assets::force_create(RuntimeOrigin::root(), ASSET.into(), issuer, true, 1).unwrap();
+		Assets::mint(RuntimeOrigin::signed(issuer), ASSET.into(), payer, MINTED).unwrap();
+		let rate = FixedU128::saturating_from_rational(3, 2);
+		AssetRates::set_rate(RuntimeOrigin::root(), ASSET, rate).unwrap();
+
+		let call = RuntimeCall::System(frame_system::Call::remark { remark: Vec::new() });
+		let fee = TransactionPayment::compute_fee(LEN as u32, &call.get_dispatch_info(), 0);
+		let asset_fee = rate.saturating_mul_int(fee);
+		assert!(asset_fee > 0);
+
+		charge(&payer, &call, Some(ASSET));
+
+		// Paid in the asset alone; without a block author, the treasury takes it all.
+		assert_eq!(Assets::balance(ASSET, payer), MINTED - asset_fee);
+		assert_eq!(Balances::free_balance(&payer), 0);
+		assert_eq!(Assets::balance(ASSET, Treasury::account_id()), asset_fee);
+	});
+}
+
+#[test]
+fn assets_without_a_rate_cannot_pay() {
+	development_state().execute_with(|| {
+		let issuer = AccountId::from(H160::repeat_byte(0x1e));
+		let payer = AccountId::from(H160::repeat_byte(0x01));
+		Assets::force_create(RuntimeOrigin::root(), ASSET.into(), issuer, true, 1).unwrap();
+		Assets::mint(RuntimeOrigin::signed(issuer), ASSET.into(), payer, MINTED).unwrap();
+
+		let call = RuntimeCall::System(frame_system::Call::remark { remark: Vec::new() });
+		let info = call.get_dispatch_info();
+		assert!(ChargeAssetTxPayment::<Runtime>::from(0, Some(ASSET))
+			.pre_dispatch(&payer, &call, &info, LEN)
+			.is_err());
+		assert_eq!(Assets::balance(ASSET, payer), MINTED);
+	});
+}
@@ -0,0 +1,139 @@
+//! GRANDPA settings come from the chain spec extension, fall back to the former hard-coded
+//! values, and are overridden by the command line.
+
+use std::sync::Arc;
+
+use clap::Parser;
+use futures::executor::block_on;
+use global_network::{
+	chain_spec::{development_config, ChainSpec, GrandpaParams, VotingRule},
+	cli::Cli,
+	service::{grandpa_params, grandpa_voting_rule},
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_finality_grandpa::VotingRule as _;
+use serde_json::json;
+use sp_consensus::BlockOrigin;
+use sp_runtime::traits::Header as _;
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block, Header},
+	DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+};
+
+/// The development chain spec with `grandpa` as its GRANDPA extension.
+fn spec_with(grandpa: serde_json::Value) -> ChainSpec {
+	let mut spec: serde_json::Value =
+		serde_json::from_str(&development_config().unwrap().as_json(false).unwrap()).unwrap();
+	spec["grandpa"] = grandpa;
+	ChainSpec::from_json_bytes(serde_json::to_vec(&spec).unwrap()).unwrap()
+}
+
+fn cli(args: &[&str]) -> Cli {
+	Cli::try_parse_from([&["global-network"][..], args].concat()).unwrap()
+}
+
+#[test]
+fn parses_voting_rules() {
+	assert_eq!("before-best-block-by=4".parse(), Ok(VotingRule::BeforeBestBlockBy(4)));
+	assert_eq!(
+		"three-quarters-of-unfinalized-chain".parse(),
+		Ok(VotingRule::ThreeQuartersOfTheUnfinalizedChain)
+	);
+	assert!("before-best-block-by=-1".parse::<VotingRule>().is_err());
+	assert!("before-best-block-by".parse::<VotingRule>().is_err());
+	assert!("three-quarters-of-unfinalized-chain=1".parse::<VotingRule>().is_err());
+	assert!("fastest".parse::<VotingRule>().is_err());
+}
+
+#[test]
+fn chain_spec_fields_default_to_the_former_constants() {
+	let defaults = GrandpaParams::default();
+	assert_eq!(defaults.gossip_duration_ms, 333);
+	assert_eq!(defaults.justification_period, 512);
+	assert!(!defaults.observer_enabled);
+
+	assert_eq!(grandpa_params(&development_config().unwrap(), &cli(&[]).grandpa), defaults);
+
+	let spec = spec_with(json!({ "justificationPeriod": 64 }));
+	let params = grandpa_params(&spec, &cli(&[]).grandpa);
+	assert_eq!(params, GrandpaParams { justification_period: 64, ..defaults });
+}
+
+#[test]
+fn command_line_overrides_the_chain_spec() {
+	let spec = spec_with(json!({
+		"gossipDurationMs": 500,
+		"justificationPeriod": 64,
+		"observerEnabled": false,
+		"votingRules": [{ "beforeBestBlockBy": 8 }],
+	}));
+
+	let only_period = cli(&["--grandpa-justification-period", "16"]);
+	let params = grandpa_params(&spec, &only_period.grandpa);
+	assert_eq!(
+		params,
+		GrandpaParams {
+			gossip_duration_ms: 500,
+			justification_period: 16,
+			observer_enabled: false,
+			voting_rules: vec![VotingRule::BeforeBestBlockBy(8)],
+		}
+	);
+
+	let everything = cli(&[
+		"--grandpa-gossip-duration",
+		"250",
+		"--grandpa-observer-enabled",
+		"true",
+		"--grandpa-voting-rule",
+		"three-quarters-of-unfinalized-chain",
+		"--grandpa-voting-rule",
+		"before-best-block-by=3",
+	]);
+	let params = grandpa_params(&spec, &everything.grandpa);
+	assert_eq!(
+		params,
+		GrandpaParams {
+			gossip_duration_ms: 250,
+			justification_period: 64,
+			observer_enabled: true,
+			voting_rules: vec![
+				VotingRule::ThreeQuartersOfTheUnfinalizedChain,
+				VotingRule::BeforeBestBlockBy(3),
+			],
+		}
+	);
+
+	assert!(Cli::try_parse_from(["global-network", "--grandpa-justification-period", "0"]).is_err());
+}
+
+/// A chain of `length` blocks, from block 1.
+fn chain(length: usize) -> (Arc<TestClient>, Vec<Header>) {
+	let mut client = Arc::new(TestClientBuilder::new().build());
+	let headers = (0..length)
+		.map(|_| {
+			let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+			let header = block.header.clone();
+			block_on(client.import(BlockOrigin::Own, block)).unwrap();
+			header
+		})
+		.collect();
+	(client, headers)
+}
+
+#[test]
+fn voting_rules_restrict_the_vote_in_order() {
+	let (client, headers) = chain(13);
+	let (base, best) = (&headers[0], &headers[12]);
+	let target = |rules: &[VotingRule]| {
+		let rule = grandpa_voting_rule::<Block, TestClient>(rules);
+		block_on(rule.restrict_vote(client.clone(), base, best, best)).map(|(_, number)| number)
+	};
+
+	assert_eq!(target(&[]), None);
+	assert_eq!(target(&[VotingRule::BeforeBestBlockBy(2)]), Some(11));
+	assert_eq!(target(&[VotingRule::ThreeQuartersOfTheUnfinalizedChain]), Some(10));
+	assert_eq!(target(&GrandpaParams::default().voting_rules), Some(10));
+	assert_eq!(*best.number(), 13);
+}
@@ -0,0 +1,160 @@
+//! The identity precompile resolves addresses to the display names registrars verified.
+
+mod common;
+
+use fp_evm::{ExitReason, ExitSucceed};
+use frame_support::{assert_ok, traits::Currency};
+use global_network_runtime::{
+	currency::GNF, AccountId, Balances, Identity, Runtime, RuntimeOrigin, IDENTITY_ADDRESS,
+};
+use pallet_evm::Runner;
+use pallet_identity::{Data, IdentityInfo, Judgement};
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::Hash;
+
+use common::development_state;
+
+const ALICE: H160 = H160::repeat_byte(0xa1);
+const BOB: H160 = H160::repeat_byte(0xb0);
+const REGISTRARS: [H160; 2] = [H160::repeat_byte(0xe1), H160::repeat_byte(0xe2)];
+
+/// `displayName(address)`
+const DISPLAY_NAME: [u8; 4] = [0x5a, 0xe1, 0x3b, 0x00];
+/// `isVerified(address)`
+const IS_VERIFIED: [u8; 4] = [0xb9, 0x20, 0x9e, 0x33];
+/// `superOf(address)`
+const SUPER_OF: [u8; 4] = [0xc1, 0x81, 0x10, 0xd6];
+
+fn raw(name: &[u8]) -> Data {
+	Data::Raw(name.to_vec().try_into().unwrap())
+}
+
+/// ALICE named `alice` with BOB as her sub-account `bob`, and two registrars that have not
+/// judged her yet.
+fn register_alice() {
+	let alice = AccountId::from(ALICE);
+	let _ = Balances::deposit_creating(&alice, 1_000 * GNF);
+	for registrar in REGISTRARS {
+		assert_ok!(Identity::add_registrar(RuntimeOrigin::root(), registrar.into()));
+	}
+	let info = IdentityInfo {
+		additional: Default::default(),
+		display: raw(b"alice"),
+		legal: Data::None,
+		web: Data::None,
+		riot: Data::None,
+		email: Data::None,
+		pgp_fingerprint: None,
+		image: Data::None,
+		twitter: Data::None,
+	};
+	assert_ok!(Identity::set_identity(RuntimeOrigin::signed(alice), Box::new(info)));
+	assert_ok!(Identity::set_subs(RuntimeOrigin::signed(alice), vec![(BOB.into(), raw(b"bob"))]));
+}
+
+/// Registrar `index` gives ALICE's identity `judgement`.
+fn judge_alice(index: u32, judgement: Judgement<u128>) {
+	let alice = AccountId::from(ALICE);
+	let info = Identity::identity(alice).unwrap().info;
+	assert_ok!(Identity::provide_judgement(
+		RuntimeOrigin::signed(REGISTRARS[index as usize].into()),
+		index,
+		alice,
+		judgement,
+		<Runtime as frame_system::Config>::Hashing::hash_of(&info),
+	));
+}
+
+fn call(selector: [u8; 4], who: H160) -> Vec<u8> {
+	let mut input = selector.to_vec();
+	input.extend_from_slice(H256::from(who).as_bytes());
+	let info = <Runtime as pallet_evm::Config>::Runner::call(
+		ALICE,
+		IDENTITY_ADDRESS,
+		input,
+		U256::zero(),
+		1_000_000,
+		None,
+		None,
+		None,
+		Vec::new(),
+		false,
+		false,
+		<Runtime as pallet_evm::Config>::config(),
+	)
+	.unwrap_or_else(|_| panic!("the call to the identity precompile executes"));
+	assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+	info.value
+}
+
+/// Decode the `string` whose offset is at byte `at` of `output`.
+fn string_at(output: &[u8], at: usize) -> Vec<u8> {
+	let word = |offset: usize| U256::from_big_endian(&output[offset..offset + 32]).as_usize();
+	let start = word(at);
+	output[start + 32..start + 32 + word(start)].to_vec()
+}
+
+fn display_name(who: H160) -> Vec<u8> {
+	string_at(&call(DISPLAY_NAME, who), 0)
+}
+
+fn is_verified(who: H160) -> bool {
+	U256::from_big_endian(&call(IS_VERIFIED, who)) == U256::one()
+}
+
+#[test]
+fn unjudged_identities_are_not_verified() {
+	development_state().execute_with(|| {
+		register_alice();
+
+		assert!(!is_verified(ALICE));
+		assert_eq!(display_name(ALICE), b"");
+		assert!(!is_verified(BOB));
+		assert_eq!(display_name(BOB), b"");
+	});
+}
+
+#[test]
+fn verified_identities_have_a_display_name() {
+	development_state().execute_with(|| {
+		register_alice();
+		judge_alice(0, Judgement::Reasonable);
+
+		assert!(is_verified(ALICE));
+		assert_eq!(display_name(ALICE), b"alice");
+	});
+}
+
+#[test]
+fn sub_accounts_are_named_after_their_parent() {
+	development_state().execute_with(|| {
+		register_alice();
+		judge_alice(0, Judgement::KnownGood);
+
+		assert!(is_verified(BOB));
+		assert_eq!(display_name(BOB), b"alice/bob");
+		let super_of = call(SUPER_OF, BOB);
+		assert_eq!(H160::from(H256::from_slice(&super_of[..32])), ALICE);
+		assert_eq!(string_at(&super_of, 32), b"bob");
+
+		let super_of = call(SUPER_OF, ALICE);
+		assert_eq!(H160::from(H256::from_slice(&super_of[..32])), H160::zero());
+		assert_eq!(string_at(&super_of, 32), b"");
+	});
+}
+
+#[test]
+fn bad_judgements_revoke_verification() {
+	for judgement in [Judgement::LowQuality, Judgement::Erroneous] {
+		development_state().execute_with(|| {
+			register_alice();
+			judge_alice(0, Judgement::Reasonable);
+			judge_alice(1, judgement);
+
+			assert!(!is_verified(ALICE));
+			assert_eq!(display_name(ALICE), b"");
+			assert!(!is_verified(BOB));
+			assert_eq!(display_name(BOB), b"");
+		});
+	}
+}
@@ -0,0 +1,122 @@
+//! The SQLite log index answers address and topic queries and follows re-orgs.
+
+use ethereum::Log as EthereumLog;
+use fp_rpc::TransactionStatus;
+use global_network::eth::log_index::{LogIndex, LogQuery};
+use sp_core::{H160, H256, U256};
+
+fn status(index: u32, logs: Vec<EthereumLog>) -> TransactionStatus {
+	TransactionStatus {
+		transaction_hash: H256::from_low_u64_be(0x1000 + index as u64),
+		transaction_index: index,
+		logs,
+		..Default::default()
+	}
+}
+
+fn log(address: u64, topics: &[u64]) -> EthereumLog {
+	EthereumLog {
+		address: H160::from_low_u64_be(address),
+		topics: topics.iter().map(|topic| H256::from_low_u64_be(*topic)).collect(),
+		data: vec![address as u8],
+	}
+}
+
+fn temp_index(name: &str) -> (LogIndex, std::path::PathBuf) {
+	let dir = std::env::temp_dir().join(format!("log-index-{}-{}", name, std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	(LogIndex::open(&dir.join("logs.db3")).unwrap(), dir)
+}
+
+fn query(from_block: u64, to_block: u64, addresses: &[u64], topics: &[&[u64]]) -> LogQuery {
+	LogQuery {
+		from_block,
+		to_block,
+		addresses: addresses.iter().map(|address| H160::from_low_u64_be(*address)).collect(),
+		topics: topics
+			.iter()
+			.map(|position| position.iter().map(|topic| H256::from_low_u64_be(*topic)).collect())
+			.collect(),
+	}
+}
+
+#[test]
+fn queries_logs_by_range_address_and_topics() {
+	let (index, dir) = temp_index("query");
+	index
+		.insert_blocks(&[
+			(0, H256::repeat_byte(0xa0), None),
+			(
+				1,
+				H256::repeat_byte(0xa1),
+				Some((
+					H256::repeat_byte(0xe1),
+					vec![
+						status(0, vec![log(1, &[10, 20]), log(2, &[10])]),
+						status(1, vec![log(1, &[11, 20])]),
+					],
+				)),
+			),
+			(
+				2,
+				H256::repeat_byte(0xa2),
+				Some((H256::repeat_byte(0xe2), vec![status(0, vec![log(1, &[10, 21])])])),
+			),
+		])
+		.unwrap();
+	assert_eq!(index.tip().unwrap(), Some((2, H256::repeat_byte(0xa2))));
+
+	let logs = index.logs(&query(0, 2, &[1], &[]), 100).unwrap();
+	assert_eq!(logs.len(), 3);
+	let second = &logs[1];
+	assert_eq!(second.block_hash, Some(H256::repeat_byte(0xe1)));
+	assert_eq!(second.block_number, Some(U256::from(1)));
+	assert_eq!(second.transaction_hash, Some(H256::from_low_u64_be(0x1001)));
+	assert_eq!(second.transaction_index, Some(U256::from(1)));
+	assert_eq!(second.log_index, Some(U256::from(2)));
+	assert_eq!(second.transaction_log_index, Some(U256::from(0)));
+	assert_eq!(second.topics, vec![H256::from_low_u64_be(11), H256::from_low_u64_be(20)]);
+	assert_eq!(second.data.0, vec![1]);
+
+	// Topic positions are ANDed, the values of a position ORed; an empty position is a wildcard.
+	let numbers = |logs: Vec<fc_rpc_core::types::Log>| {
+		logs.iter().map(|log| log.block_number.unwrap().as_u64()).collect::<Vec<_>>()
+	};
+	assert_eq!(numbers(index.logs(&query(0, 2, &[], &[&[10]]), 100).unwrap()), vec![1, 1, 2]);
+	assert_eq!(numbers(index.logs(&query(0, 2, &[], &[&[10], &[20]]), 100).unwrap()), vec![1]);
+	assert_eq!(
+		numbers(index.logs(&query(0, 2, &[], &[&[], &[21, 20]]), 100).unwrap()),
+		vec![1, 1, 2]
+	);
+	assert_eq!(numbers(index.logs(&query(2, 2, &[1, 2], &[]), 100).unwrap()), vec![2]);
+
+	// Queries returning more than the limit fail instead of being truncated.
+	assert!(index.logs(&query(0, 2, &[], &[]), 3).is_err());
+	assert_eq!(index.logs(&query(0, 2, &[], &[]), 4).unwrap().len(), 4);
+
+	let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn reverting_drops_retracted_blocks() {
+	let (index, dir) = temp_index("revert");
+	let block = |number: u64, fork: u8| {
+		(
+			number,
+			H256::repeat_byte(fork + number as u8),
+			Some((H256::repeat_byte(number as u8), vec![status(0, vec![log(fork as u64, &[])])])),
+		)
+	};
+	index.insert_blocks(&[block(0, 0x10), block(1, 0x10), block(2, 0x10)]).unwrap();
+
+	index.revert_to(0).unwrap();
+	assert_eq!(index.substrate_hash(1).unwrap(), None);
+	index.insert_blocks(&[block(1, 0x20)]).unwrap();
+
+	assert_eq!(index.tip().unwrap(), Some((1, H256::repeat_byte(0x21))));
+	let logs = index.logs(&query(0, 2, &[], &[]), 100).unwrap();
+	let addresses: Vec<_> = logs.iter().map(|log| log.address).collect();
+	assert_eq!(addresses, vec![H160::from_low_u64_be(0x10), H160::from_low_u64_be(0x20)]);
+
+	let _ = std::fs::remove_dir_all(dir);
+}
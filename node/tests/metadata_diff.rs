@@ -0,0 +1,103 @@
+//! The metadata comparison behind `metadata-diff` and `upgrade-check`.
+
+use frame_metadata::{v14::RuntimeMetadataV14, RuntimeMetadata, RuntimeMetadataPrefixed};
+use global_network::{
+	metadata_diff::{diff, Change, Metadata},
+	upgrade_check::check_versions,
+};
+use global_network_runtime::{Runtime, VERSION};
+use sp_core::{Decode, Encode};
+
+/// The native runtime's metadata, encoded the way `Metadata_metadata` returns it.
+fn native_metadata() -> Metadata {
+	edited_metadata(|_| {})
+}
+
+/// The native runtime's metadata, after `edit`.
+fn edited_metadata(edit: impl FnOnce(&mut RuntimeMetadataV14)) -> Metadata {
+	let opaque = Runtime::metadata().encode();
+	let mut prefixed = RuntimeMetadataPrefixed::decode(&mut &opaque[..]).unwrap();
+	let RuntimeMetadata::V14(metadata) = &mut prefixed.1 else { panic!("metadata is V14") };
+	edit(metadata);
+	Metadata::decode(&prefixed.encode().encode()).unwrap()
+}
+
+#[test]
+fn identical_runtimes_have_no_changes() {
+	assert!(diff(&native_metadata(), &native_metadata()).is_empty());
+}
+
+#[test]
+fn system_block_weights_are_readable() {
+	let metadata = native_metadata();
+	let encoded = metadata.constant("System", "BlockWeights").unwrap();
+
+	assert!(frame_system::limits::BlockWeights::decode(&mut &encoded[..]).is_ok());
+}
+
+#[test]
+fn breaking_changes_need_a_transaction_version_bump() {
+	let (old, mut new) = (VERSION, VERSION);
+	new.spec_version += 1;
+
+	let signed_extra = diff(
+		&native_metadata(),
+		&edited_metadata(|metadata| {
+			metadata.extrinsic.signed_extensions.pop();
+		}),
+	);
+	assert!(matches!(signed_extra[..], [Change::SignedExtensionsChanged { .. }]));
+	let pallet_index =
+		diff(&native_metadata(), &edited_metadata(|metadata| metadata.pallets[1].index += 100));
+	assert!(matches!(pallet_index[..], [Change::PalletIndexChanged { .. }]));
+	let call_index = vec![Change::CallIndexChanged {
+		pallet: "Balances".into(),
+		call: "transfer".into(),
+		old: 0,
+		new: 1,
+	}];
+
+	for changes in [&signed_extra, &pallet_index, &call_index] {
+		assert!(check_versions(&old, &new, true, changes).is_err());
+	}
+	new.transaction_version += 1;
+	for changes in [&signed_extra, &pallet_index, &call_index] {
+		assert!(check_versions(&old, &new, true, changes).is_ok());
+	}
+}
+
+#[test]
+fn code_changes_need_a_spec_version_bump() {
+	let (old, mut new) = (VERSION, VERSION);
+
+	assert!(check_versions(&old, &new, false, &[]).is_ok());
+	assert!(check_versions(&old, &new, true, &[]).is_err());
+	new.spec_version += 1;
+	assert!(check_versions(&old, &new, true, &[]).is_ok());
+}
+
+#[test]
+fn only_removed_pallets_with_calls_break_transactions() {
+	let (old, mut new) = (VERSION, VERSION);
+	new.spec_version += 1;
+
+	let without_calls = diff(
+		&native_metadata(),
+		&edited_metadata(|metadata| metadata.pallets.retain(|pallet| pallet.name != "Aura")),
+	);
+	assert!(matches!(
+		&without_calls[..],
+		[Change::PalletRemoved { pallet, had_calls: false }] if pallet == "Aura"
+	));
+	assert!(check_versions(&old, &new, true, &without_calls).is_ok());
+
+	let with_calls = diff(
+		&native_metadata(),
+		&edited_metadata(|metadata| metadata.pallets.retain(|pallet| pallet.name != "Balances")),
+	);
+	assert!(matches!(
+		&with_calls[..],
+		[Change::PalletRemoved { pallet, had_calls: true }] if pallet == "Balances"
+	));
+	assert!(check_versions(&old, &new, true, &with_calls).is_err());
+}
@@ -0,0 +1,143 @@
+//! Multicall3 is served at its canonical address by a precompile.
+
+mod common;
+
+use fp_evm::{CallInfo, ExitReason, ExitRevert, ExitSucceed};
+use frame_support::traits::Currency;
+use global_network_runtime::{
+	AccountId, Balances, Runtime, EXISTENTIAL_DEPOSIT, MULTICALL3_ADDRESS, PRECOMPILE_CODE,
+	WGNF_ADDRESS,
+};
+use pallet_evm::Runner;
+use sp_core::{H160, H256, U256};
+
+use common::development_state;
+
+const ALICE: H160 = H160::repeat_byte(0xa1);
+const BOB: H160 = H160::repeat_byte(0xb0);
+
+/// `aggregate3((address,bool,bytes)[])`
+const AGGREGATE3: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+/// `aggregate3Value((address,bool,uint256,bytes)[])`
+const AGGREGATE3_VALUE: [u8; 4] = [0x17, 0x4d, 0xea, 0x71];
+/// `getBlockNumber()`
+const GET_BLOCK_NUMBER: [u8; 4] = [0x42, 0xcb, 0xb1, 0x5c];
+
+fn word(value: u128) -> H256 {
+	let mut word = [0u8; 32];
+	U256::from(value).to_big_endian(&mut word);
+	H256(word)
+}
+
+fn call(source: H160, input: Vec<u8>, value: u128) -> CallInfo {
+	<Runtime as pallet_evm::Config>::Runner::call(
+		source,
+		MULTICALL3_ADDRESS,
+		input,
+		value.into(),
+		1_000_000,
+		None,
+		None,
+		None,
+		Vec::new(),
+		false,
+		false,
+		<Runtime as pallet_evm::Config>::config(),
+	)
+	.unwrap_or_else(|_| panic!("the call to Multicall3 executes"))
+}
+
+/// ABI encoding of `Call3Value[]` as the only argument, `value` left out when `None`.
+fn encode_calls(selector: [u8; 4], calls: &[(H160, bool, Option<u128>, Vec<u8>)]) -> Vec<u8> {
+	let mut tails = Vec::new();
+	for (target, allow_failure, value, data) in calls {
+		let mut tail = vec![(*target).into(), word(*allow_failure as u128)];
+		tail.extend(value.map(word));
+		tail.push(word(32 * (tail.len() as u128 + 1)));
+		tail.push(word(data.len() as u128));
+		let mut tail: Vec<u8> = tail.iter().flat_map(|w| w.0).collect();
+		tail.extend_from_slice(data);
+		tail.resize(tail.len() + (32 - data.len() % 32) % 32, 0);
+		tails.push(tail);
+	}
+
+	let mut input = selector.to_vec();
+	input.extend_from_slice(word(32).as_bytes());
+	input.extend_from_slice(word(calls.len() as u128).as_bytes());
+	let mut offset = 32 * calls.len();
+	for tail in &tails {
+		input.extend_from_slice(word(offset as u128).as_bytes());
+		offset += tail.len();
+	}
+	tails.into_iter().for_each(|tail| input.extend(tail));
+	input
+}
+
+/// Decode a `Result { bool success; bytes returnData; }[]` return value.
+fn decode_results(output: &[u8]) -> Vec<(bool, Vec<u8>)> {
+	let at = |offset: usize| U256::from_big_endian(&output[offset..offset + 32]).as_usize();
+	let array = at(0);
+	(0..at(array))
+		.map(|i| {
+			let result = array + 32 + at(array + 32 + 32 * i);
+			let data = result + at(result + 32);
+			(at(result) == 1, output[data + 32..data + 32 + at(data)].to_vec())
+		})
+		.collect()
+}
+
+#[test]
+fn development_genesis_serves_multicall3_code() {
+	development_state().execute_with(|| {
+		assert_eq!(
+			pallet_evm::Pallet::<Runtime>::account_codes(MULTICALL3_ADDRESS),
+			PRECOMPILE_CODE.to_vec()
+		);
+	});
+}
+
+#[test]
+fn aggregate3_batches_calls() {
+	development_state().execute_with(|| {
+		frame_system::Pallet::<Runtime>::set_block_number(7);
+		// balanceOf(ALICE) on WGNF, then a call WGNF reverts, allowed to fail.
+		let mut balance_of = vec![0x70, 0xa0, 0x82, 0x31];
+		balance_of.extend_from_slice(H256::from(ALICE).as_bytes());
+		let _ = Balances::deposit_creating(&AccountId::from(ALICE), 10 * EXISTENTIAL_DEPOSIT);
+		let calls = [
+			(MULTICALL3_ADDRESS, false, None, GET_BLOCK_NUMBER.to_vec()),
+			(WGNF_ADDRESS, false, None, balance_of),
+			(WGNF_ADDRESS, true, None, vec![0xde, 0xad, 0xbe, 0xef]),
+		];
+
+		let result = call(BOB, encode_calls(AGGREGATE3, &calls), 0);
+		assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		let results = decode_results(&result.value);
+		assert_eq!(results[0], (true, word(7).as_bytes().to_vec()));
+		assert_eq!(results[1], (true, word(10 * EXISTENTIAL_DEPOSIT).as_bytes().to_vec()));
+		assert!(!results[2].0);
+
+		// The same failing call, not allowed to fail, reverts the batch.
+		let calls = [(WGNF_ADDRESS, false, None, vec![0xde, 0xad, 0xbe, 0xef])];
+		let result = call(BOB, encode_calls(AGGREGATE3, &calls), 0);
+		assert_eq!(result.exit_reason, ExitReason::Revert(ExitRevert::Reverted));
+	});
+}
+
+#[test]
+fn aggregate3_value_forwards_the_value() {
+	development_state().execute_with(|| {
+		let _ = Balances::deposit_creating(&AccountId::from(ALICE), 10 * EXISTENTIAL_DEPOSIT);
+		let calls = [(BOB, false, Some(2 * EXISTENTIAL_DEPOSIT), Vec::new())];
+
+		// The value sent has to match the value of the calls.
+		let result = call(ALICE, encode_calls(AGGREGATE3_VALUE, &calls), EXISTENTIAL_DEPOSIT);
+		assert_eq!(result.exit_reason, ExitReason::Revert(ExitRevert::Reverted));
+		assert_eq!(Balances::free_balance(AccountId::from(BOB)), 0);
+
+		let result = call(ALICE, encode_calls(AGGREGATE3_VALUE, &calls), 2 * EXISTENTIAL_DEPOSIT);
+		assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(decode_results(&result.value), vec![(true, Vec::new())]);
+		assert_eq!(Balances::free_balance(AccountId::from(BOB)), 2 * EXISTENTIAL_DEPOSIT);
+	});
+}
@@ -0,0 +1,111 @@
+//! The randomness beacon VRF signed by the node verifies in the runtime, and its randomness is
+//! delivered to the contracts that requested it.
+
+mod common;
+
+use fp_evm::{ExitReason, ExitRevert, ExitSucceed};
+use global_network_runtime::{
+	MaxRandomnessRequestsPerRequester, RandomnessBeacon, Runtime, RANDOMNESS_ADDRESS,
+};
+use pallet_evm::Runner;
+use pallet_randomness_beacon::{inherent::InherentDataProvider, vrf, Error, Outputs};
+use sp_core::{crypto::key_types::AURA, Get, H160, H256, U256};
+use sp_keystore::{testing::KeyStore, SyncCryptoStore};
+
+use common::development_state;
+
+/// `fulfill(uint256)`
+const FULFILL: [u8; 4] = [0x75, 0x3b, 0x88, 0x07];
+
+/// Call `fulfill(id)` on the precompile with `gas_limit` gas.
+fn fulfill(id: u64, gas_limit: u64) -> ExitReason {
+	let mut input = FULFILL.to_vec();
+	input.extend_from_slice(H256::from_low_u64_be(id).as_bytes());
+	<Runtime as pallet_evm::Config>::Runner::call(
+		H160::repeat_byte(0xf1),
+		RANDOMNESS_ADDRESS,
+		input,
+		U256::zero(),
+		gas_limit,
+		None,
+		None,
+		None,
+		Vec::new(),
+		false,
+		false,
+		<Runtime as pallet_evm::Config>::config(),
+	)
+	.unwrap_or_else(|_| panic!("the call to the randomness precompile executes"))
+	.exit_reason
+}
+
+#[test]
+fn signed_vrf_verifies_for_its_slot_and_input_only() {
+	let keystore = KeyStore::new();
+	let author = SyncCryptoStore::sr25519_generate_new(&keystore, AURA, None).unwrap();
+	let previous = H256::repeat_byte(0x07);
+
+	let vrf = futures::executor::block_on(InherentDataProvider::sign(
+		&keystore, AURA, &author, 42, &previous,
+	))
+	.0
+	.expect("the keystore holds the author's key");
+
+	assert!(vrf::verify(&author.0, 42, &previous, &vrf).is_some());
+	assert_eq!(vrf::verify(&author.0, 43, &previous, &vrf), None);
+	assert_eq!(vrf::verify(&author.0, 42, &H256::zero(), &vrf), None);
+
+	let other = SyncCryptoStore::sr25519_generate_new(&keystore, AURA, None).unwrap();
+	assert_eq!(vrf::verify(&other.0, 42, &previous, &vrf), None);
+}
+
+#[test]
+fn requests_wait_for_a_later_round() {
+	development_state().execute_with(|| {
+		let contract = H160::repeat_byte(0xaa);
+		assert!(RandomnessBeacon::request(contract, 0, 100_000).is_err());
+
+		let id = RandomnessBeacon::request(contract, 1, 100_000).unwrap();
+		assert_eq!(RandomnessBeacon::request(contract, 1, 100_000), Ok(id + 1));
+		assert!(matches!(RandomnessBeacon::fulfillment(id), Err(Error::<Runtime>::NotReady)));
+		assert!(matches!(
+			RandomnessBeacon::fulfillment(id + 2),
+			Err(Error::<Runtime>::UnknownRequest)
+		));
+	});
+}
+
+#[test]
+fn requesters_have_a_bounded_number_of_pending_requests() {
+	development_state().execute_with(|| {
+		let contract = H160::repeat_byte(0xaa);
+		let max = MaxRandomnessRequestsPerRequester::get() as u64;
+		for _ in 0..max {
+			RandomnessBeacon::request(contract, 1, 100_000).unwrap();
+		}
+		assert!(RandomnessBeacon::request(contract, 1, 100_000).is_err());
+		assert!(RandomnessBeacon::request(H160::repeat_byte(0xbb), 1, 100_000).is_ok());
+
+		// Delivering a request frees its place.
+		Outputs::<Runtime>::insert(1, H256::repeat_byte(0x07));
+		assert_eq!(fulfill(0, 1_000_000), ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(RandomnessBeacon::pending_requests(contract), max as u32 - 1);
+		assert!(RandomnessBeacon::request(contract, 1, 100_000).is_ok());
+	});
+}
+
+#[test]
+fn fulfill_needs_the_gas_of_the_callback() {
+	development_state().execute_with(|| {
+		let contract = H160::repeat_byte(0xaa);
+		let id = RandomnessBeacon::request(contract, 1, 100_000).unwrap();
+		Outputs::<Runtime>::insert(1, H256::repeat_byte(0x07));
+
+		assert_eq!(fulfill(id, 100_000), ExitReason::Revert(ExitRevert::Reverted));
+		assert!(RandomnessBeacon::pending_request(id).is_some());
+
+		assert_eq!(fulfill(id, 1_000_000), ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(RandomnessBeacon::pending_request(id), None);
+		assert_eq!(RandomnessBeacon::pending_requests(contract), 0);
+	});
+}
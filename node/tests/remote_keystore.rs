@@ -0,0 +1,204 @@
+use std::{
+	net::TcpListener,
+	path::{Path, PathBuf},
+	process::{Child, Command},
+	thread,
+	time::Duration,
+};
+
+use global_network::remote_keystore::{RemoteKeystore, RemoteKeystoreParams};
+use pallet_randomness_beacon::{inherent::InherentDataProvider, vrf};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use sp_core::{
+	crypto::{ByteArray, KeyTypeId},
+	sr25519, Pair, H256,
+};
+use sp_keystore::{
+	vrf::{VRFTranscriptData, VRFTranscriptValue},
+	CryptoStore, SyncCryptoStore,
+};
+
+const AURA: KeyTypeId = KeyTypeId(*b"aura");
+
+/// A `keystore-signer` process serving from a temporary keystore.
+struct Signer {
+	process: Child,
+	dir: PathBuf,
+	port: u16,
+}
+
+impl Signer {
+	/// Start a signer in a fresh directory; `args` builds its transport options from that
+	/// directory.
+	fn start(args: impl FnOnce(&Path) -> Vec<String>) -> Self {
+		let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+		let dir =
+			std::env::temp_dir().join(format!("keystore-signer-{}-{}", std::process::id(), port));
+		std::fs::create_dir_all(&dir).unwrap();
+		let process = Command::new(env!("CARGO_BIN_EXE_keystore-signer"))
+			.arg("--listen")
+			.arg(format!("127.0.0.1:{}", port))
+			.arg("--keystore-path")
+			.arg(dir.join("keystore"))
+			.args(args(&dir))
+			.spawn()
+			.expect("keystore-signer starts");
+
+		Self { process, dir, port }
+	}
+}
+
+impl Drop for Signer {
+	fn drop(&mut self) {
+		let _ = self.process.kill();
+		let _ = std::fs::remove_dir_all(&self.dir);
+	}
+}
+
+/// Generate a key in `keystore`, waiting for the signer to start listening.
+fn generate_when_up(keystore: &RemoteKeystore) -> sr25519::Public {
+	(0..50)
+		.find_map(|_| {
+			SyncCryptoStore::sr25519_generate_new(keystore, AURA, None)
+				.map_err(|_| thread::sleep(Duration::from_millis(100)))
+				.ok()
+		})
+		.expect("keystore-signer did not come up")
+}
+
+/// A CA and a certificate it issued for `name`, written as PEM files under `dir`.
+struct Pki {
+	ca: PathBuf,
+	cert: PathBuf,
+	key: PathBuf,
+}
+
+impl Pki {
+	fn issue(dir: &Path, prefix: &str, name: &str) -> Self {
+		let mut ca_params = CertificateParams::new(vec![]);
+		ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+		let ca = Certificate::from_params(ca_params).unwrap();
+		let leaf = Certificate::from_params(CertificateParams::new(vec![name.into()])).unwrap();
+
+		let pki = Self {
+			ca: dir.join(format!("{}-ca.pem", prefix)),
+			cert: dir.join(format!("{}-cert.pem", prefix)),
+			key: dir.join(format!("{}-key.pem", prefix)),
+		};
+		std::fs::write(&pki.ca, ca.serialize_pem().unwrap()).unwrap();
+		std::fs::write(&pki.cert, leaf.serialize_pem_with_signer(&ca).unwrap()).unwrap();
+		std::fs::write(&pki.key, leaf.serialize_private_key_pem()).unwrap();
+		pki
+	}
+
+	/// Client options presenting this certificate and trusting `server_ca`.
+	fn client_params(&self, server_ca: &Path) -> RemoteKeystoreParams {
+		RemoteKeystoreParams {
+			keystore_remote_ca: Some(server_ca.into()),
+			keystore_remote_cert: Some(self.cert.clone()),
+			keystore_remote_key: Some(self.key.clone()),
+		}
+	}
+}
+
+#[test]
+fn signs_with_keys_held_by_the_signer() {
+	let signer = Signer::start(|_| vec!["--insecure-http".into()]);
+	let keystore =
+		RemoteKeystore::open(&format!("http://127.0.0.1:{}", signer.port), None).unwrap();
+
+	let public = generate_when_up(&keystore);
+
+	assert_eq!(SyncCryptoStore::sr25519_public_keys(&keystore, AURA), vec![public]);
+	assert!(SyncCryptoStore::has_keys(&keystore, &[(public.to_raw_vec(), AURA)]));
+
+	let signature = SyncCryptoStore::sign_with(&keystore, AURA, &public.into(), b"block")
+		.unwrap()
+		.expect("the signer holds the key");
+	let signature = sr25519::Signature::from_slice(&signature).unwrap();
+	assert!(sr25519::Pair::verify(&signature, b"block", &public));
+
+	let unknown = sr25519::Pair::from_seed(&[7; 32]).public();
+	assert_eq!(
+		SyncCryptoStore::sign_with(&keystore, AURA, &unknown.into(), b"block").unwrap(),
+		None
+	);
+}
+
+#[test]
+fn signs_the_randomness_beacon_vrf() {
+	let signer = Signer::start(|_| vec!["--insecure-http".into()]);
+	let keystore =
+		RemoteKeystore::open(&format!("http://127.0.0.1:{}", signer.port), None).unwrap();
+	let public = generate_when_up(&keystore);
+
+	let previous = H256::repeat_byte(0x01);
+	let vrf = InherentDataProvider::sign(&keystore, AURA, &public, 42, &previous)
+		.0
+		.expect("the signer holds the key");
+	assert!(vrf::verify(&public.0, 42, &previous, &vrf).is_some());
+	assert!(vrf::verify(&public.0, 43, &previous, &vrf).is_none());
+
+	// Any other transcript is refused.
+	let transcript = VRFTranscriptData {
+		label: b"something else",
+		items: vec![("slot", VRFTranscriptValue::U64(42))],
+	};
+	assert!(SyncCryptoStore::sr25519_vrf_sign(&keystore, AURA, &public, transcript).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mutual_tls_admits_only_certificates_of_the_client_ca() {
+	let mut pki = None;
+	let signer = Signer::start(|dir| {
+		let server = Pki::issue(dir, "server", "localhost");
+		let client = Pki::issue(dir, "client", "validator");
+		let args = vec![
+			format!("--tls-cert={}", server.cert.display()),
+			format!("--tls-key={}", server.key.display()),
+			format!("--client-ca={}", client.ca.display()),
+		];
+		pki = Some((server, client, Pki::issue(dir, "stranger", "validator")));
+		args
+	});
+	let (server, client, stranger) = pki.unwrap();
+	let uri = format!("https://localhost:{}", signer.port);
+
+	let open = |params: RemoteKeystoreParams| {
+		RemoteKeystore::open(&uri, params.tls_config().unwrap()).unwrap()
+	};
+	let keystore = open(client.client_params(&server.ca));
+
+	let public = {
+		let keystore = keystore.clone();
+		tokio::task::spawn_blocking(move || generate_when_up(&keystore)).await.unwrap()
+	};
+	assert_eq!(CryptoStore::sr25519_public_keys(&keystore, AURA).await, vec![public]);
+	let signature = CryptoStore::sign_with(&keystore, AURA, &public.into(), b"block")
+		.await
+		.unwrap()
+		.expect("the signer holds the key");
+	let signature = sr25519::Signature::from_slice(&signature).unwrap();
+	assert!(sr25519::Pair::verify(&signature, b"block", &public));
+
+	// A client certificate from another CA is refused by the signer.
+	let rejected = open(stranger.client_params(&server.ca));
+	assert!(CryptoStore::sign_with(&rejected, AURA, &public.into(), b"block").await.is_err());
+
+	// A signer whose certificate does not chain to the configured CA is refused by the client.
+	let untrusted = open(client.client_params(&stranger.ca));
+	assert!(CryptoStore::sign_with(&untrusted, AURA, &public.into(), b"block")
+		.await
+		.is_err());
+}
+
+#[test]
+fn tls_options_must_be_used_together() {
+	let params = RemoteKeystoreParams {
+		keystore_remote_ca: Some("ca.pem".into()),
+		keystore_remote_cert: None,
+		keystore_remote_key: None,
+	};
+	assert!(params.tls_config().is_err());
+	assert!(RemoteKeystore::open("https://localhost:9955", None).is_err());
+}
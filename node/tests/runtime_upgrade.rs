@@ -0,0 +1,82 @@
+//! Runs the runtime's migrations, with their try-runtime checks, on top of a chain state.
+//!
+//! Set `MIGRATION_SNAPSHOT` to a snapshot of the chain to check, exported with
+//! `global-network export-state --chain live <block> > snapshot.json`. Without it the
+//! development genesis is used, rolled back to the state of a chain none of the migrations have
+//! run on. Only built with `--features try-runtime`.
+
+#![cfg(feature = "try-runtime")]
+
+use frame_support::{
+	storage::migration::{have_storage_value, put_storage_value},
+	traits::{Get, GetStorageVersion, StorageVersion},
+};
+use global_network::chain_spec::{development_config, ChainSpec};
+use global_network_runtime::{
+	system_contracts::{PRECOMPILES_WITH_CODE, SYSTEM_CONTRACTS},
+	AccountId, Democracy, Executive, Preimage, Runtime, EVM,
+};
+use sp_core::H256;
+use sp_runtime::BuildStorage;
+
+fn chain_state() -> sp_io::TestExternalities {
+	match std::env::var("MIGRATION_SNAPSHOT") {
+		Ok(path) => {
+			let spec = ChainSpec::from_json_file(path.into())
+				.expect("MIGRATION_SNAPSHOT must be a state exported with `export-state`");
+			sp_io::TestExternalities::new(spec.build_storage().unwrap())
+		},
+		Err(_) => pre_upgrade_state(),
+	}
+}
+
+/// The development genesis, without what the migrations put in place.
+fn pre_upgrade_state() -> sp_io::TestExternalities {
+	let storage = development_config().unwrap().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		StorageVersion::new(0).put::<Preimage>();
+		StorageVersion::new(0).put::<Democracy>();
+		for (address, _) in SYSTEM_CONTRACTS {
+			pallet_evm::AccountCodes::<Runtime>::remove(address);
+			frame_system::Account::<Runtime>::mutate(AccountId::from(address), |account| {
+				account.nonce = 0;
+				account.sufficients = 0;
+			});
+		}
+		for address in PRECOMPILES_WITH_CODE {
+			pallet_evm::AccountCodes::<Runtime>::remove(address);
+			frame_system::Account::<Runtime>::mutate(AccountId::from(address), |account| {
+				account.sufficients = 0
+			});
+		}
+		// The block hashes `pallet_randomness_collective_flip` kept.
+		let material = vec![H256::repeat_byte(0x01); 81];
+		put_storage_value(b"RandomnessCollectiveFlip", b"RandomMaterial", b"", material);
+	});
+	ext
+}
+
+#[test]
+fn migrations_pass_try_runtime_checks() {
+	chain_state().execute_with(|| {
+		let weight = Executive::try_runtime_upgrade().unwrap();
+
+		let max_block = <Runtime as frame_system::Config>::BlockWeights::get().max_block;
+		assert!(weight.all_lte(max_block), "migrations use {:?}, more than a block", weight);
+
+		assert_eq!(Preimage::on_chain_storage_version(), 1);
+		assert_eq!(Democracy::on_chain_storage_version(), 1);
+		// Injected accounts look like the ones genesis creates.
+		let account = |address| frame_system::Account::<Runtime>::get(AccountId::from(address));
+		for (address, code) in SYSTEM_CONTRACTS {
+			assert_eq!(EVM::account_codes(address), code);
+			assert_eq!((account(address).nonce, account(address).sufficients), (1, 1));
+		}
+		for address in PRECOMPILES_WITH_CODE {
+			assert!(!EVM::account_codes(address).is_empty());
+			assert_eq!(account(address).sufficients, 1);
+		}
+		assert!(!have_storage_value(b"RandomnessCollectiveFlip", b"RandomMaterial", b""));
+	});
+}
@@ -0,0 +1,62 @@
+//! `session rotate` reads the validator key from a file or the environment and signs
+//! `Session::set_keys` for the validator's account.
+
+use clap::Parser;
+use global_network::{
+	chain_spec::development_config,
+	session::{account_of, set_keys_extrinsic, RotateCmd, SIGNER_ENV},
+};
+use global_network_runtime::{opaque::SessionKeys, AccountId, Runtime, System, VERSION};
+use sp_core::{Decode, Pair, H256};
+use sp_runtime::{traits::Checkable, BuildStorage};
+
+const SECRET: &str = "0x5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133";
+
+fn rotate(args: &[&str]) -> RotateCmd {
+	RotateCmd::try_parse_from([&["rotate"][..], args].concat()).unwrap()
+}
+
+#[test]
+fn the_signer_is_never_taken_from_the_command_line() {
+	assert!(RotateCmd::try_parse_from(["rotate", "--signer", SECRET]).is_err());
+}
+
+#[test]
+fn reads_the_signer_from_a_file_or_the_environment() {
+	let path = std::env::temp_dir().join(format!("session-signer-{}", std::process::id()));
+	std::fs::write(&path, format!("{}\n", SECRET)).unwrap();
+	let from_file = rotate(&["--signer-file", path.to_str().unwrap()]).signer().unwrap();
+	std::fs::remove_file(&path).unwrap();
+	assert!(rotate(&["--signer-file", path.to_str().unwrap()]).signer().is_err());
+
+	std::env::remove_var(SIGNER_ENV);
+	assert!(rotate(&[]).signer().is_err());
+	std::env::set_var(SIGNER_ENV, SECRET);
+	let from_env = rotate(&[]).signer().unwrap();
+	std::env::remove_var(SIGNER_ENV);
+
+	assert_eq!(from_file.public(), from_env.public());
+	// ALITH, the first development account.
+	assert_eq!(
+		account_of(&from_file),
+		AccountId::from(hex_literal::hex!("f24ff3a9cf04c71dbc94d0b566f7a27b94566cac"))
+	);
+}
+
+#[test]
+fn set_keys_is_signed_for_the_validator_account() {
+	let storage = development_config().unwrap().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		let signer = sp_core::ecdsa::Pair::from_string(SECRET, None).unwrap();
+		let keys = SessionKeys::decode(&mut &[1u8; 96][..]).unwrap();
+		let genesis_hash = System::block_hash(0);
+		let context = frame_system::ChainContext::<Runtime>::default();
+
+		let extrinsic = set_keys_extrinsic(&signer, 0, genesis_hash, &VERSION, keys.clone());
+		assert!(extrinsic.check(&context).is_ok());
+
+		// Signed for another chain, the signature does not verify.
+		let extrinsic = set_keys_extrinsic(&signer, 0, H256::repeat_byte(1), &VERSION, keys);
+		assert!(extrinsic.check(&context).is_err());
+	});
+}
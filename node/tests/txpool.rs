@@ -0,0 +1,155 @@
+//! `txpool_*` and `pool_*` report the ready and the future queue of the transaction pool.
+
+mod common;
+
+use std::sync::Arc;
+
+use ethereum::{LegacyTransactionMessage, TransactionAction, TransactionV2};
+use fc_rpc::EthSigner;
+use fc_rpc_core::types::TransactionMessage;
+use fp_rpc::EthereumRuntimeRPCApi;
+use futures::executor::block_on;
+use global_network::rpc::{
+	dev_signer::EthDevSigner,
+	pool::{ExtrinsicValidity, PoolApiServer, PoolDiagnostics},
+	txpool::{TxPool, TxPoolApiServer},
+	DenyUnsafe,
+};
+use global_network_runtime::{opaque::Block, Runtime, UncheckedExtrinsic};
+use sc_transaction_pool::{FullChainApi, Pool};
+use sc_transaction_pool_api::TransactionSource;
+use sp_api::ProvideRuntimeApi;
+use sp_core::{Bytes, Encode, H160, U256};
+use sp_runtime::{generic::BlockId, OpaqueExtrinsic};
+
+use common::{development_client, DevClient};
+
+type DevPool = Pool<FullChainApi<DevClient, Block>>;
+
+const RECIPIENT: H160 = H160::repeat_byte(0x11);
+
+/// A transfer of 1 wei from `from`, signed with its development key.
+fn transfer(client: &DevClient, from: H160, nonce: u64) -> TransactionV2 {
+	let at = BlockId::number(0);
+	let api = client.runtime_api();
+	let message = LegacyTransactionMessage {
+		nonce: nonce.into(),
+		gas_price: api.gas_price(&at).unwrap(),
+		gas_limit: 21_000.into(),
+		action: TransactionAction::Call(RECIPIENT),
+		value: 1.into(),
+		input: Vec::new(),
+		chain_id: Some(api.chain_id(&at).unwrap()),
+	};
+	EthDevSigner::new().sign(TransactionMessage::Legacy(message), &from).unwrap()
+}
+
+fn extrinsic(transaction: TransactionV2) -> OpaqueExtrinsic {
+	let call = pallet_ethereum::Call::<Runtime>::transact { transaction };
+	OpaqueExtrinsic::from_bytes(&UncheckedExtrinsic::new_unsigned(call.into()).encode()).unwrap()
+}
+
+/// A pool holding ALITH's nonces 0 and 1, ready, and BALTATHAR's nonce 3, in the future queue.
+fn pool() -> (Arc<DevClient>, Arc<DevPool>, [H160; 2], Vec<TransactionV2>) {
+	let client = development_client();
+	let spawner = sp_core::testing::TaskExecutor::new();
+	let api = FullChainApi::new(client.clone(), None, &spawner);
+	let pool = Arc::new(Pool::new(Default::default(), true.into(), Arc::new(api)));
+
+	let accounts = EthDevSigner::new().accounts();
+	let (alith, baltathar) = (accounts[0], accounts[1]);
+	let transactions = vec![
+		transfer(&client, alith, 0),
+		transfer(&client, alith, 1),
+		transfer(&client, baltathar, 3),
+	];
+	for transaction in &transactions {
+		let submitted = pool.submit_one(
+			&BlockId::number(0),
+			TransactionSource::External,
+			extrinsic(transaction.clone()),
+		);
+		block_on(submitted).unwrap();
+	}
+	assert_eq!(pool.validated_pool().status().ready, 2);
+	assert_eq!(pool.validated_pool().status().future, 1);
+	(client, pool, [alith, baltathar], transactions)
+}
+
+#[test]
+fn txpool_content_lists_pending_and_queued_transactions() {
+	let (client, pool, [alith, baltathar], transactions) = pool();
+	let txpool = TxPool::<Block, _, _>::new(client, pool);
+
+	let content = txpool.content().unwrap();
+	assert_eq!(content.pending.keys().collect::<Vec<_>>(), vec![&alith]);
+	assert_eq!(content.pending[&alith].keys().collect::<Vec<_>>(), vec![&0, &1]);
+	assert_eq!(content.queued.keys().collect::<Vec<_>>(), vec![&baltathar]);
+	assert_eq!(content.queued[&baltathar].keys().collect::<Vec<_>>(), vec![&3]);
+
+	let pending = &content.pending[&alith][&1];
+	assert_eq!(pending.hash, transactions[1].hash());
+	assert_eq!(pending.nonce, U256::from(1));
+	assert_eq!(pending.from, alith);
+	assert_eq!(pending.to, Some(RECIPIENT));
+	assert_eq!(pending.value, U256::from(1));
+	assert_eq!(pending.gas, U256::from(21_000));
+	assert_eq!(pending.block_hash, None);
+	assert_eq!(content.queued[&baltathar][&3].hash, transactions[2].hash());
+
+	let inspect = txpool.inspect().unwrap();
+	assert!(
+		inspect.queued[&baltathar][&3].starts_with(&format!("{:?}: 1 wei + 21000 gas", RECIPIENT))
+	);
+
+	let status = txpool.status().unwrap();
+	assert_eq!((status.pending, status.queued), (U256::from(2), U256::from(1)));
+}
+
+#[test]
+fn pool_future_explains_the_missing_nonce() {
+	let (client, pool, [alith, _], _) = pool();
+	let diagnostics = PoolDiagnostics::new(client.clone(), pool, DenyUnsafe::No);
+
+	let future = block_on(diagnostics.future()).unwrap();
+	assert_eq!(future.len(), 1);
+	match &future[0].validity {
+		ExtrinsicValidity::Valid { requires, missing, .. } => {
+			assert_eq!(requires.len(), 1);
+			assert_eq!(missing, requires);
+		},
+		invalid => panic!("expected a valid extrinsic, got {:?}", invalid),
+	}
+
+	// ALITH's nonce 2 requires nonce 1, which the ready queue provides.
+	let next = Bytes(extrinsic(transfer(&client, alith, 2)).encode());
+	match block_on(diagnostics.validate(next)).unwrap() {
+		ExtrinsicValidity::Valid { requires, missing, .. } => {
+			assert_eq!(requires.len(), 1);
+			assert!(missing.is_empty());
+		},
+		invalid => panic!("expected a valid extrinsic, got {:?}", invalid),
+	}
+
+	// Changed after signing, it recovers to an account without funds.
+	let mut tampered = transfer(&client, alith, 2);
+	if let TransactionV2::Legacy(transaction) = &mut tampered {
+		transaction.value = 2.into();
+	}
+	let tampered = Bytes(extrinsic(tampered).encode());
+	assert!(matches!(
+		block_on(diagnostics.validate(tampered)).unwrap(),
+		ExtrinsicValidity::Invalid { .. }
+	));
+
+	assert!(block_on(diagnostics.validate(Bytes(vec![0xff]))).is_err());
+}
+
+#[test]
+fn pool_methods_are_unsafe() {
+	let (client, pool, _, _) = pool();
+	let diagnostics = PoolDiagnostics::new(client, pool, DenyUnsafe::Yes);
+
+	assert!(block_on(diagnostics.future()).is_err());
+	assert!(block_on(diagnostics.validate(Bytes(Vec::new()))).is_err());
+}
@@ -0,0 +1,98 @@
+//! The wrapped GNF precompile is an ERC-20 view of native balances, callable directly only.
+
+mod common;
+
+use fp_evm::{CallInfo, ExitReason, ExitSucceed};
+use frame_support::traits::Currency;
+use global_network_runtime::{AccountId, Balances, Runtime, EXISTENTIAL_DEPOSIT, WGNF_ADDRESS};
+use pallet_evm::Runner;
+use sp_core::{H160, H256, U256};
+
+use common::development_state;
+
+const ALICE: H160 = H160::repeat_byte(0xa1);
+const BOB: H160 = H160::repeat_byte(0xb0);
+
+fn encode_call(selector: [u8; 4], args: &[H256]) -> Vec<u8> {
+	let mut input = selector.to_vec();
+	args.iter().for_each(|arg| input.extend_from_slice(arg.as_bytes()));
+	input
+}
+
+fn word(value: u128) -> H256 {
+	let mut word = [0u8; 32];
+	U256::from(value).to_big_endian(&mut word);
+	H256(word)
+}
+
+fn call(source: H160, target: H160, input: Vec<u8>) -> CallInfo {
+	<Runtime as pallet_evm::Config>::Runner::call(
+		source,
+		target,
+		input,
+		U256::zero(),
+		1_000_000,
+		None,
+		None,
+		None,
+		Vec::new(),
+		false,
+		false,
+		<Runtime as pallet_evm::Config>::config(),
+	)
+	.unwrap_or_else(|_| panic!("the call to {:?} executes", target))
+}
+
+fn free_balance(address: H160) -> u128 {
+	Balances::free_balance(AccountId::from(address))
+}
+
+/// `transfer(BOB, amount)`.
+fn transfer(amount: u128) -> Vec<u8> {
+	encode_call([0xa9, 0x05, 0x9c, 0xbb], &[BOB.into(), word(amount)])
+}
+
+#[test]
+fn balances_are_native_balances() {
+	development_state().execute_with(|| {
+		let _ = Balances::deposit_creating(&AccountId::from(ALICE), 1_000 * EXISTENTIAL_DEPOSIT);
+
+		// balanceOf(ALICE)
+		let balance =
+			call(BOB, WGNF_ADDRESS, encode_call([0x70, 0xa0, 0x82, 0x31], &[ALICE.into()]));
+		assert_eq!(balance.value, word(1_000 * EXISTENTIAL_DEPOSIT).as_bytes());
+
+		let transferred = call(ALICE, WGNF_ADDRESS, transfer(10 * EXISTENTIAL_DEPOSIT));
+		assert_eq!(transferred.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(free_balance(ALICE), 990 * EXISTENTIAL_DEPOSIT);
+		assert_eq!(free_balance(BOB), 10 * EXISTENTIAL_DEPOSIT);
+
+		// withdraw(amount) only logs: the amount is held natively already.
+		let withdrawn =
+			call(ALICE, WGNF_ADDRESS, encode_call([0x2e, 0x1a, 0x7d, 0x4d], &[word(5)]));
+		assert_eq!(withdrawn.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(withdrawn.logs.len(), 1);
+		assert_eq!(free_balance(ALICE), 990 * EXISTENTIAL_DEPOSIT);
+	});
+}
+
+#[test]
+fn delegate_calls_are_refused() {
+	development_state().execute_with(|| {
+		let _ = Balances::deposit_creating(&AccountId::from(ALICE), 1_000 * EXISTENTIAL_DEPOSIT);
+
+		// Forwards its call data to WGNF with DELEGATECALL and returns whether it succeeded.
+		let proxy = H160::repeat_byte(0xde);
+		let mut code = vec![0x36, 0x60, 0x00, 0x60, 0x00, 0x37];
+		code.extend_from_slice(&[0x60, 0x00, 0x60, 0x00, 0x36, 0x60, 0x00, 0x73]);
+		code.extend_from_slice(WGNF_ADDRESS.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf4, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+		pallet_evm::AccountCodes::<Runtime>::insert(proxy, code);
+
+		// DELEGATECALL keeps ALICE as the caller: any contract she calls could move her funds.
+		let result = call(ALICE, proxy, transfer(10 * EXISTENTIAL_DEPOSIT));
+		assert_eq!(result.value, word(0).as_bytes());
+		assert_eq!(free_balance(ALICE), 1_000 * EXISTENTIAL_DEPOSIT);
+		assert_eq!(free_balance(BOB), 0);
+	});
+}
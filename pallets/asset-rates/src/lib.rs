@@ -0,0 +1,112 @@
+//! # Asset Rates Pallet
+//!
+//! Conversion rates from the native currency to assets that can pay transaction fees.
+//!
+//! `UpdateOrigin` (root or the council in this runtime) sets, for each asset, how many units of
+//! the asset one unit of the native currency is worth. The pallet implements
+//! `BalanceConversion`, so `pallet_asset_tx_payment` can charge a fee computed in the native
+//! currency in the asset instead. Assets without a rate cannot pay fees.
+//!
+//! Rates are not updated automatically: governance is expected to refresh them when the market
+//! moves, and to remove the rate of an asset whose price it cannot follow.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use frame_support::traits::tokens::BalanceConversion;
+use sp_runtime::{FixedPointNumber, FixedPointOperand, FixedU128};
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Zero;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Identifier of an asset.
+		type AssetId: Member + Parameter + Copy + MaxEncodedLen;
+
+		/// Balance type of both the native currency and the assets.
+		type Balance: FixedPointOperand;
+
+		/// Origin allowed to set and remove rates.
+		type UpdateOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+	}
+
+	/// Units of each asset one unit of the native currency is worth.
+	#[pallet::storage]
+	#[pallet::getter(fn rate)]
+	pub type Rates<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, FixedU128, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The rate of an asset was set or replaced.
+		RateSet { asset_id: T::AssetId, rate: FixedU128 },
+		/// The rate of an asset was removed; it can no longer pay fees.
+		RateRemoved { asset_id: T::AssetId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A rate of zero would make fees free.
+		ZeroRate,
+		/// The asset has no rate.
+		NoRate,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the rate of `asset_id`: units of the asset one unit of the native currency is
+		/// worth.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_rate(
+			origin: OriginFor<T>,
+			asset_id: T::AssetId,
+			rate: FixedU128,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(!rate.is_zero(), Error::<T>::ZeroRate);
+
+			Rates::<T>::insert(asset_id, rate);
+			Self::deposit_event(Event::RateSet { asset_id, rate });
+			Ok(())
+		}
+
+		/// Remove the rate of `asset_id`, so that it can no longer pay fees.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_rate(origin: OriginFor<T>, asset_id: T::AssetId) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			Rates::<T>::take(asset_id).ok_or(Error::<T>::NoRate)?;
+
+			Self::deposit_event(Event::RateRemoved { asset_id });
+			Ok(())
+		}
+	}
+}
+
+/// Converts native fees to asset fees at the rate governance set, rounding down.
+impl<T: Config> BalanceConversion<T::Balance, T::AssetId, T::Balance> for Pallet<T> {
+	type Error = Error<T>;
+
+	fn to_asset_balance(balance: T::Balance, asset_id: T::AssetId) -> Result<T::Balance, Error<T>> {
+		let rate = Rates::<T>::get(asset_id).ok_or(Error::<T>::NoRate)?;
+		Ok(rate.saturating_mul_int(balance))
+	}
+}
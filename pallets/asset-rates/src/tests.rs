@@ -0,0 +1,48 @@
+use crate::{mock::*, Error, Event};
+use frame_support::{assert_noop, assert_ok, traits::tokens::BalanceConversion};
+use sp_runtime::{traits::One, DispatchError, FixedPointNumber, FixedU128};
+
+fn to_asset_balance(balance: u64, asset_id: u32) -> Result<u64, DispatchError> {
+	AssetRates::to_asset_balance(balance, asset_id).map_err(Into::into)
+}
+
+#[test]
+fn fees_convert_at_the_rate_rounding_down() {
+	new_test_ext().execute_with(|| {
+		let rate = FixedU128::saturating_from_rational(3, 2);
+		assert_ok!(AssetRates::set_rate(RuntimeOrigin::root(), 7, rate));
+		System::assert_last_event(Event::RateSet { asset_id: 7, rate }.into());
+
+		assert_eq!(to_asset_balance(1_000, 7).unwrap(), 1_500);
+		assert_eq!(to_asset_balance(3, 7).unwrap(), 4);
+	});
+}
+
+#[test]
+fn assets_without_a_rate_cannot_pay() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(to_asset_balance(1_000, 7), Err(Error::<Test>::NoRate.into()));
+
+		assert_ok!(AssetRates::set_rate(RuntimeOrigin::root(), 7, FixedU128::one()));
+		assert_ok!(AssetRates::remove_rate(RuntimeOrigin::root(), 7));
+		System::assert_last_event(Event::RateRemoved { asset_id: 7 }.into());
+
+		assert_eq!(to_asset_balance(1_000, 7), Err(Error::<Test>::NoRate.into()));
+		assert_noop!(AssetRates::remove_rate(RuntimeOrigin::root(), 7), Error::<Test>::NoRate);
+	});
+}
+
+#[test]
+fn rates_are_set_by_the_update_origin_and_never_zero() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetRates::set_rate(RuntimeOrigin::signed(1), 7, FixedU128::one()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			AssetRates::set_rate(RuntimeOrigin::root(), 7, FixedU128::from_inner(0)),
+			Error::<Test>::ZeroRate
+		);
+		assert_eq!(AssetRates::rate(7), None);
+	});
+}
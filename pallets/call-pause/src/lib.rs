@@ -0,0 +1,251 @@
+//! # Call Pause Pallet
+//!
+//! Lets governance stop calls during an incident without a runtime upgrade.
+//!
+//! `PauseOrigin` (root, the council or the technical committee in this runtime) pauses either a
+//! whole pallet or a single call of it, by name, for at most `MaxPauseDuration` blocks. The
+//! pallet is meant to be the runtime's `BaseCallFilter`: a paused call fails with
+//! `frame_system::Error::CallFiltered` for every origin but root. Pallets and calls in
+//! `WhitelistedCalls` cannot be paused, so governance, sudo and session management keep working.
+//!
+//! Pauses end on their own: expired entries are dropped at the start of the block they expire
+//! in. `UnpauseOrigin` can lift a pause earlier.
+//!
+//! The filter reads the pauses on every dispatch. Runtimes using it add
+//! [`Pallet::filter_weight`] to the base weight of their extrinsics.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use frame_support::{
+	traits::{CallMetadata, Contains, Get, GetCallMetadata},
+	weights::Weight,
+	BoundedVec,
+};
+use sp_std::vec::Vec;
+
+/// Name of a pallet, as in `construct_runtime`.
+pub type PalletNameOf<T> = BoundedVec<u8, <T as Config>::MaxNameLen>;
+
+/// Name of a call.
+pub type CallNameOf<T> = BoundedVec<u8, <T as Config>::MaxNameLen>;
+
+/// What a pause applies to: a whole pallet, or one of its calls.
+pub type PauseTargetOf<T> = (PalletNameOf<T>, Option<CallNameOf<T>>);
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Saturating, Zero};
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to pause calls.
+		type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Origin allowed to lift a pause before it expires.
+		type UnpauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Pallets and calls that can never be paused.
+		type WhitelistedCalls: Contains<PauseTargetOf<Self>>;
+
+		/// Longest a pause can last, in blocks.
+		#[pallet::constant]
+		type MaxPauseDuration: Get<Self::BlockNumber>;
+
+		/// Maximum number of pauses in place at once.
+		#[pallet::constant]
+		type MaxPaused: Get<u32>;
+
+		/// Maximum length of a pallet or call name.
+		#[pallet::constant]
+		type MaxNameLen: Get<u32>;
+	}
+
+	/// Pauses in place, with the block each one ends at.
+	#[pallet::storage]
+	#[pallet::getter(fn paused)]
+	pub type Paused<T: Config> = StorageValue<
+		_,
+		BoundedVec<(PauseTargetOf<T>, T::BlockNumber), T::MaxPaused>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A pallet or call was paused until block `until`.
+		Paused { target: PauseTargetOf<T>, until: T::BlockNumber },
+		/// A pause was lifted before it expired.
+		Unpaused { target: PauseTargetOf<T> },
+		/// A pause expired.
+		PauseExpired { target: PauseTargetOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The pallet or call is whitelisted and cannot be paused.
+		Whitelisted,
+		/// The duration is zero or longer than `MaxPauseDuration`.
+		InvalidDuration,
+		/// The pallet or call name is longer than `MaxNameLen`.
+		NameTooLong,
+		/// There are already `MaxPaused` pauses in place.
+		TooManyPaused,
+		/// The pallet or call is not paused.
+		NotPaused,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let (active, expired): (Vec<_>, Vec<_>) = Paused::<T>::get()
+				.into_inner()
+				.into_iter()
+				.partition(|(_, until)| *until > now);
+			if expired.is_empty() {
+				return T::DbWeight::get().reads(1)
+			}
+
+			// `active` is a subset of what was read, so it fits.
+			Paused::<T>::put(BoundedVec::truncate_from(active));
+			for (target, _) in expired {
+				Self::deposit_event(Event::PauseExpired { target });
+			}
+			T::DbWeight::get().reads_writes(1, 1)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Pause `pallet`, or only its call named `call`, for `duration` blocks.
+		///
+		/// Pausing something that is already paused replaces the end of its pause.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn pause(
+			origin: OriginFor<T>,
+			pallet: Vec<u8>,
+			call: Option<Vec<u8>>,
+			duration: T::BlockNumber,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(
+				!duration.is_zero() && duration <= T::MaxPauseDuration::get(),
+				Error::<T>::InvalidDuration
+			);
+			let target = Self::target(pallet, call)?;
+			ensure!(!Self::is_whitelisted(&target), Error::<T>::Whitelisted);
+
+			let until = frame_system::Pallet::<T>::block_number().saturating_add(duration);
+			Paused::<T>::try_mutate(|paused| {
+				match paused.iter_mut().find(|(paused, _)| *paused == target) {
+					Some((_, end)) => *end = until,
+					None => paused
+						.try_push((target.clone(), until))
+						.map_err(|_| Error::<T>::TooManyPaused)?,
+				}
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(Event::Paused { target, until });
+			Ok(())
+		}
+
+		/// Lift the pause of `pallet`, or of its call named `call`.
+		///
+		/// Only lifts a pause made for exactly this target: unpausing a call does not lift a
+		/// pause of its whole pallet.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn unpause(
+			origin: OriginFor<T>,
+			pallet: Vec<u8>,
+			call: Option<Vec<u8>>,
+		) -> DispatchResult {
+			T::UnpauseOrigin::ensure_origin(origin)?;
+			let target = Self::target(pallet, call)?;
+
+			Paused::<T>::try_mutate(|paused| {
+				let index = paused
+					.iter()
+					.position(|(paused, _)| *paused == target)
+					.ok_or(Error::<T>::NotPaused)?;
+				paused.remove(index);
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(Event::Unpaused { target });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn target(pallet: Vec<u8>, call: Option<Vec<u8>>) -> Result<PauseTargetOf<T>, Error<T>> {
+			let bound =
+				|name: Vec<u8>| BoundedVec::try_from(name).map_err(|_| Error::<T>::NameTooLong);
+			Ok((bound(pallet)?, call.map(bound).transpose()?))
+		}
+
+		pub(crate) fn is_whitelisted((pallet, call): &PauseTargetOf<T>) -> bool {
+			T::WhitelistedCalls::contains(&(pallet.clone(), None)) ||
+				(call.is_some() &&
+					T::WhitelistedCalls::contains(&(pallet.clone(), call.clone())))
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Weight of checking a call with [`Pallet::is_paused`].
+	pub fn filter_weight() -> Weight {
+		T::DbWeight::get().reads(1)
+	}
+
+	/// Whether the call named `function_name` of `pallet_name` is paused.
+	pub fn is_paused(CallMetadata { pallet_name, function_name }: CallMetadata) -> bool {
+		let now = frame_system::Pallet::<T>::block_number();
+		let paused = Paused::<T>::get().iter().any(|((pallet, call), until)| {
+			*until > now &&
+				&pallet[..] == pallet_name.as_bytes() &&
+				call.as_ref().map_or(true, |call| &call[..] == function_name.as_bytes())
+		});
+		if !paused {
+			return false
+		}
+
+		// Calls whitelisted after they were paused, by a runtime upgrade, are not paused.
+		let name = |name: &str| BoundedVec::truncate_from(name.as_bytes().to_vec());
+		!Self::is_whitelisted(&(name(pallet_name), Some(name(function_name))))
+	}
+
+	/// Pauses in place that have not expired, as `(pallet, call, until)`.
+	pub fn paused_calls() -> Vec<(Vec<u8>, Option<Vec<u8>>, T::BlockNumber)> {
+		let now = frame_system::Pallet::<T>::block_number();
+		Paused::<T>::get()
+			.into_iter()
+			.filter(|(_, until)| *until > now)
+			.map(|((pallet, call), until)| {
+				(pallet.into_inner(), call.map(BoundedVec::into_inner), until)
+			})
+			.collect()
+	}
+}
+
+/// Lets every call through except the paused ones, for `frame_system::Config::BaseCallFilter`.
+impl<T: Config, Call: GetCallMetadata> Contains<Call> for Pallet<T> {
+	fn contains(call: &Call) -> bool {
+		!Self::is_paused(call.get_call_metadata())
+	}
+}
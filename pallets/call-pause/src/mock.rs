@@ -0,0 +1,80 @@
+use crate as pallet_call_pause;
+use crate::PauseTargetOf;
+use frame_support::traits::{ConstU32, ConstU64, Contains};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		CallPause: pallet_call_pause,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = CallPause;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<u64>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+/// The pause pallet itself, and `System::set_code`.
+pub struct Whitelist;
+
+impl Contains<PauseTargetOf<Test>> for Whitelist {
+	fn contains((pallet, call): &PauseTargetOf<Test>) -> bool {
+		match (&pallet[..], call.as_deref()) {
+			(b"CallPause", _) => true,
+			(b"System", Some(call)) => call == b"set_code",
+			_ => false,
+		}
+	}
+}
+
+impl pallet_call_pause::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type PauseOrigin = EnsureRoot<u64>;
+	type UnpauseOrigin = EnsureRoot<u64>;
+	type WhitelistedCalls = Whitelist;
+	type MaxPauseDuration = ConstU64<10>;
+	type MaxPaused = ConstU32<2>;
+	type MaxNameLen = ConstU32<20>;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
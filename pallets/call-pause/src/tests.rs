@@ -0,0 +1,147 @@
+use crate::{mock::*, Error, Event, PauseTargetOf, Paused};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchResult, DispatchResultWithPostInfo},
+	traits::{CallMetadata, Hooks},
+	BoundedVec,
+};
+use sp_runtime::{traits::Dispatchable, DispatchError};
+
+fn target(pallet: &str, call: Option<&str>) -> PauseTargetOf<Test> {
+	let name = |name: &str| BoundedVec::truncate_from(name.as_bytes().to_vec());
+	(name(pallet), call.map(name))
+}
+
+fn pause(pallet: &str, call: Option<&str>, duration: u64) -> DispatchResult {
+	CallPause::pause(
+		RuntimeOrigin::root(),
+		pallet.as_bytes().to_vec(),
+		call.map(|call| call.as_bytes().to_vec()),
+		duration,
+	)
+}
+
+fn unpause(pallet: &str, call: Option<&str>) -> DispatchResult {
+	CallPause::unpause(
+		RuntimeOrigin::root(),
+		pallet.as_bytes().to_vec(),
+		call.map(|call| call.as_bytes().to_vec()),
+	)
+}
+
+/// Dispatch `System::remark`, or `System::remark_with_event` with `with_event`, as account 1.
+fn remark(with_event: bool) -> DispatchResultWithPostInfo {
+	let call = match with_event {
+		false => frame_system::Call::remark { remark: Vec::new() },
+		true => frame_system::Call::remark_with_event { remark: Vec::new() },
+	};
+	RuntimeCall::System(call).dispatch(RuntimeOrigin::signed(1))
+}
+
+fn filtered() -> DispatchError {
+	frame_system::Error::<Test>::CallFiltered.into()
+}
+
+#[test]
+fn paused_calls_are_filtered() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(pause("System", Some("remark"), 5));
+		System::assert_last_event(
+			Event::Paused { target: target("System", Some("remark")), until: 6 }.into(),
+		);
+
+		assert_eq!(remark(false).unwrap_err().error, filtered());
+		assert_ok!(remark(true));
+		// Root is not filtered.
+		let call = RuntimeCall::System(frame_system::Call::remark { remark: Vec::new() });
+		assert_ok!(call.dispatch(RuntimeOrigin::root()));
+
+		assert_ok!(pause("System", None, 5));
+		assert_eq!(remark(true).unwrap_err().error, filtered());
+		assert_eq!(CallPause::paused_calls().len(), 2);
+	});
+}
+
+#[test]
+fn pauses_expire() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(pause("System", Some("remark"), 3));
+		assert_ok!(pause("System", None, 5));
+
+		System::set_block_number(4);
+		CallPause::on_initialize(4);
+		System::assert_last_event(
+			Event::PauseExpired { target: target("System", Some("remark")) }.into(),
+		);
+		assert_eq!(Paused::<Test>::get().len(), 1);
+		assert_eq!(remark(false).unwrap_err().error, filtered());
+
+		// A pause ends with the block it expires at, before `on_initialize` drops it.
+		System::set_block_number(6);
+		assert_ok!(remark(false));
+		assert!(CallPause::paused_calls().is_empty());
+	});
+}
+
+#[test]
+fn whitelisted_calls_cannot_be_paused() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(pause("CallPause", None, 5), Error::<Test>::Whitelisted);
+		assert_noop!(pause("CallPause", Some("unpause"), 5), Error::<Test>::Whitelisted);
+		assert_noop!(pause("System", Some("set_code"), 5), Error::<Test>::Whitelisted);
+
+		// A pause of its whole pallet leaves a whitelisted call alone.
+		assert_ok!(pause("System", None, 5));
+		assert!(CallPause::is_paused(CallMetadata {
+			pallet_name: "System",
+			function_name: "remark",
+		}));
+		assert!(!CallPause::is_paused(CallMetadata {
+			pallet_name: "System",
+			function_name: "set_code",
+		}));
+	});
+}
+
+#[test]
+fn pauses_are_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(pause("System", None, 0), Error::<Test>::InvalidDuration);
+		assert_noop!(pause("System", None, 11), Error::<Test>::InvalidDuration);
+		assert_noop!(pause("AVeryLongPalletNameIndeed", None, 5), Error::<Test>::NameTooLong);
+		assert_noop!(
+			CallPause::pause(RuntimeOrigin::signed(1), b"System".to_vec(), None, 5),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(pause("System", Some("remark"), 5));
+		assert_ok!(pause("System", Some("remark_with_event"), 5));
+		assert_noop!(pause("System", None, 5), Error::<Test>::TooManyPaused);
+
+		// Pausing again moves the end of the pause.
+		assert_ok!(pause("System", Some("remark"), 10));
+		assert_eq!(Paused::<Test>::get()[0], (target("System", Some("remark")), 11));
+	});
+}
+
+#[test]
+fn unpause_lifts_only_its_target() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(unpause("System", None), Error::<Test>::NotPaused);
+
+		assert_ok!(pause("System", None, 5));
+		assert_ok!(pause("System", Some("remark"), 5));
+		assert_ok!(unpause("System", Some("remark")));
+		System::assert_last_event(
+			Event::Unpaused { target: target("System", Some("remark")) }.into(),
+		);
+		assert_eq!(remark(false).unwrap_err().error, filtered());
+
+		assert_ok!(unpause("System", None));
+		assert_ok!(remark(false));
+		assert_noop!(
+			CallPause::unpause(RuntimeOrigin::signed(1), b"System".to_vec(), None),
+			DispatchError::BadOrigin
+		);
+	});
+}
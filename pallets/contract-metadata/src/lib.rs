@@ -0,0 +1,307 @@
+//! # Contract Metadata Pallet
+//!
+//! Stores verified-source metadata for EVM contracts, so explorers have more to show than
+//! `EVM::account_codes`. A contract can carry either an IPFS CID pointing at the source bundle
+//! or a compressed ABI, plus the compiler settings used to build it.
+//!
+//! Metadata can be set by:
+//! - the contract's deployer, as reported by `T::Deployers`;
+//! - a verifier appointed by `ManageOrigin` (root or the council). Metadata set by a verifier is
+//!   flagged as verified, and from then on only verifiers can replace or clear it.
+//!
+//! The submitter reserves a deposit of `DepositPerItem + DepositPerByte * bytes`, returned when
+//! the metadata is replaced or cleared.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::traits::{Currency, ReservableCurrency};
+use scale_info::TypeInfo;
+use sp_core::H160;
+use sp_runtime::RuntimeDebug;
+
+type BalanceOf<T> =
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// Where the contract's source can be found.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum MetadataSource<Bytes> {
+	/// IPFS CID of the source bundle (sources, ABI and metadata JSON).
+	Ipfs(Bytes),
+	/// The contract ABI, compressed by the submitter.
+	CompressedAbi(Bytes),
+}
+
+impl<Bytes> MetadataSource<Bytes> {
+	/// The CID or the ABI.
+	pub fn bytes(&self) -> &Bytes {
+		match self {
+			Self::Ipfs(bytes) | Self::CompressedAbi(bytes) => bytes,
+		}
+	}
+}
+
+/// Metadata attached to a contract.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ContractMetadata<AccountId, Balance, Bytes> {
+	/// Source CID or ABI.
+	pub source: MetadataSource<Bytes>,
+	/// Compiler version and settings, as submitted (usually the solc settings JSON).
+	pub compiler_settings: Bytes,
+	/// Account that submitted the metadata and holds the deposit.
+	pub submitter: AccountId,
+	/// Deposit reserved from `submitter`.
+	pub deposit: Balance,
+	/// Whether the metadata was submitted by an appointed verifier.
+	pub verified: bool,
+}
+
+impl<AccountId, Balance, Bytes> ContractMetadata<AccountId, Balance, Bytes> {
+	/// Convert the byte fields, e.g. from bounded storage vectors to plain ones.
+	pub fn map_bytes<B>(self, f: impl Fn(Bytes) -> B) -> ContractMetadata<AccountId, Balance, B> {
+		ContractMetadata {
+			source: match self.source {
+				MetadataSource::Ipfs(cid) => MetadataSource::Ipfs(f(cid)),
+				MetadataSource::CompressedAbi(abi) => MetadataSource::CompressedAbi(f(abi)),
+			},
+			compiler_settings: f(self.compiler_settings),
+			submitter: self.submitter,
+			deposit: self.deposit,
+			verified: self.verified,
+		}
+	}
+}
+
+/// Tells who deployed a contract.
+pub trait DeployerLookup<AccountId> {
+	/// The account that deployed `contract`, if it is known.
+	fn deployer(contract: &H160) -> Option<AccountId>;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{pallet_prelude::*, weights::constants::WEIGHT_REF_TIME_PER_NANOS};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Saturating;
+	use sp_std::vec::Vec;
+
+	/// Upper bound of decoding, bounding and storing one byte of metadata.
+	const METADATA_BYTE_WEIGHT: Weight = Weight::from_ref_time(100 * WEIGHT_REF_TIME_PER_NANOS);
+
+	/// Metadata as kept in storage.
+	pub type StoredMetadata<T> = ContractMetadata<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		BoundedVec<u8, <T as Config>::MaxMetadataLen>,
+	>;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Currency the metadata deposit is reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Who deployed a given contract.
+		type Deployers: DeployerLookup<Self::AccountId>;
+
+		/// Origin allowed to appoint and dismiss verifiers.
+		type ManageOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Deposit reserved for every metadata entry.
+		#[pallet::constant]
+		type DepositPerItem: Get<BalanceOf<Self>>;
+
+		/// Deposit reserved for every byte of source and compiler settings.
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Maximum length of the source field and of the compiler settings, each.
+		#[pallet::constant]
+		type MaxMetadataLen: Get<u32>;
+	}
+
+	/// Metadata of each contract that has some.
+	#[pallet::storage]
+	#[pallet::getter(fn metadata)]
+	pub type Metadata<T: Config> =
+		StorageMap<_, Blake2_128Concat, H160, StoredMetadata<T>, OptionQuery>;
+
+	/// Accounts appointed to verify contracts.
+	#[pallet::storage]
+	pub type Verifiers<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Metadata was set or replaced.
+		MetadataSet { contract: H160, submitter: T::AccountId, verified: bool },
+		/// Metadata was removed and its deposit returned.
+		MetadataCleared { contract: H160 },
+		/// An account was appointed as verifier.
+		VerifierAdded { verifier: T::AccountId },
+		/// A verifier was dismissed.
+		VerifierRemoved { verifier: T::AccountId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Only the deployer or a verifier can set metadata for this contract.
+		NotDeployerOrVerifier,
+		/// Only the submitter, the deployer or a verifier can clear this metadata.
+		NotAllowedToClear,
+		/// The source or the compiler settings are longer than `MaxMetadataLen`.
+		TooLong,
+		/// The contract has no metadata.
+		NoMetadata,
+		/// The account is already a verifier.
+		AlreadyVerifier,
+		/// The account is not a verifier.
+		NotVerifier,
+		/// The metadata was verified; only a verifier can replace or clear it.
+		Verified,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Attach metadata to `contract`, replacing what was there.
+		///
+		/// The previous submitter gets its deposit back and the caller reserves a new one.
+		/// Verified metadata can only be replaced by a verifier.
+		#[pallet::call_index(0)]
+		#[pallet::weight(Pallet::<T>::set_metadata_weight(
+			source.bytes().len().saturating_add(compiler_settings.len())
+		))]
+		pub fn set_metadata(
+			origin: OriginFor<T>,
+			contract: H160,
+			source: MetadataSource<Vec<u8>>,
+			compiler_settings: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let verified = Verifiers::<T>::contains_key(&who);
+			ensure!(
+				verified || T::Deployers::deployer(&contract).as_ref() == Some(&who),
+				Error::<T>::NotDeployerOrVerifier,
+			);
+
+			let bound =
+				|bytes: Vec<u8>| BoundedVec::try_from(bytes).map_err(|_| Error::<T>::TooLong);
+			let source = match source {
+				MetadataSource::Ipfs(cid) => MetadataSource::Ipfs(bound(cid)?),
+				MetadataSource::CompressedAbi(abi) => MetadataSource::CompressedAbi(bound(abi)?),
+			};
+			let compiler_settings = bound(compiler_settings)?;
+
+			let deposit = Self::deposit_for(&source, &compiler_settings);
+			if let Some(old) = Metadata::<T>::take(contract) {
+				ensure!(verified || !old.verified, Error::<T>::Verified);
+				T::Currency::unreserve(&old.submitter, old.deposit);
+			}
+			T::Currency::reserve(&who, deposit)?;
+
+			Metadata::<T>::insert(
+				contract,
+				ContractMetadata {
+					source,
+					compiler_settings,
+					submitter: who.clone(),
+					deposit,
+					verified,
+				},
+			);
+			Self::deposit_event(Event::MetadataSet { contract, submitter: who, verified });
+			Ok(())
+		}
+
+		/// Remove the metadata of `contract` and return the deposit to its submitter.
+		///
+		/// Verified metadata can only be cleared by a verifier.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(4, 2))]
+		pub fn clear_metadata(origin: OriginFor<T>, contract: H160) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let metadata = Metadata::<T>::get(contract).ok_or(Error::<T>::NoMetadata)?;
+			if !Verifiers::<T>::contains_key(&who) {
+				ensure!(!metadata.verified, Error::<T>::Verified);
+				ensure!(
+					metadata.submitter == who ||
+						T::Deployers::deployer(&contract).as_ref() == Some(&who),
+					Error::<T>::NotAllowedToClear,
+				);
+			}
+
+			T::Currency::unreserve(&metadata.submitter, metadata.deposit);
+			Metadata::<T>::remove(contract);
+			Self::deposit_event(Event::MetadataCleared { contract });
+			Ok(())
+		}
+
+		/// Appoint `verifier`. Metadata it submits is flagged as verified.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn add_verifier(origin: OriginFor<T>, verifier: T::AccountId) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			ensure!(!Verifiers::<T>::contains_key(&verifier), Error::<T>::AlreadyVerifier);
+
+			Verifiers::<T>::insert(&verifier, ());
+			Self::deposit_event(Event::VerifierAdded { verifier });
+			Ok(())
+		}
+
+		/// Dismiss `verifier`. Metadata it already submitted stays verified.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_verifier(origin: OriginFor<T>, verifier: T::AccountId) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			ensure!(Verifiers::<T>::contains_key(&verifier), Error::<T>::NotVerifier);
+
+			Verifiers::<T>::remove(&verifier);
+			Self::deposit_event(Event::VerifierRemoved { verifier });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Weight of `set_metadata` with `bytes` of source and compiler settings.
+		fn set_metadata_weight(bytes: usize) -> Weight {
+			T::DbWeight::get()
+				.reads_writes(4, 3)
+				.saturating_add(METADATA_BYTE_WEIGHT.saturating_mul(bytes as u64))
+		}
+
+		fn deposit_for(
+			source: &MetadataSource<BoundedVec<u8, T::MaxMetadataLen>>,
+			compiler_settings: &BoundedVec<u8, T::MaxMetadataLen>,
+		) -> BalanceOf<T> {
+			let bytes = (source.bytes().len() + compiler_settings.len()) as u32;
+			T::DepositPerItem::get()
+				.saturating_add(T::DepositPerByte::get().saturating_mul(bytes.into()))
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Metadata of `contract` with plain byte vectors, for the runtime API.
+	pub fn contract_metadata(
+		contract: H160,
+	) -> Option<ContractMetadata<T::AccountId, BalanceOf<T>, sp_std::vec::Vec<u8>>> {
+		Metadata::<T>::get(contract).map(|metadata| metadata.map_bytes(|bytes| bytes.into_inner()))
+	}
+}
@@ -0,0 +1,113 @@
+use crate as pallet_contract_metadata;
+use crate::DeployerLookup;
+use frame_support::traits::{ConstU32, ConstU64};
+use frame_system::EnsureRoot;
+use sp_core::{H160, H256};
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		ContractMetadata: pallet_contract_metadata,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<u64>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	pub static DEPLOYERS: RefCell<BTreeMap<H160, u64>> = RefCell::new(BTreeMap::new());
+}
+
+/// Deployers registered with [`set_deployer`].
+pub struct MockDeployers;
+
+impl DeployerLookup<u64> for MockDeployers {
+	fn deployer(contract: &H160) -> Option<u64> {
+		DEPLOYERS.with(|d| d.borrow().get(contract).copied())
+	}
+}
+
+pub fn set_deployer(contract: H160, deployer: u64) {
+	DEPLOYERS.with(|d| d.borrow_mut().insert(contract, deployer));
+}
+
+impl pallet_contract_metadata::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type Deployers = MockDeployers;
+	type ManageOrigin = EnsureRoot<u64>;
+	type DepositPerItem = ConstU64<10>;
+	type DepositPerByte = ConstU64<1>;
+	type MaxMetadataLen = ConstU32<8>;
+}
+
+/// Deployed by account 1.
+pub const CONTRACT: H160 = H160::repeat_byte(0xc0);
+/// Account 4 is appointed verifier in genesis.
+pub const VERIFIER: u64 = 4;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 100), (2, 100), (3, 100), (VERIFIER, 100)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		set_deployer(CONTRACT, 1);
+		ContractMetadata::add_verifier(RuntimeOrigin::root(), VERIFIER).unwrap();
+	});
+	ext
+}
@@ -0,0 +1,153 @@
+use crate::{mock::*, Call, ContractMetadata as Record, Error, Event, MetadataSource};
+use frame_support::{assert_noop, assert_ok, dispatch::GetDispatchInfo};
+use sp_core::H160;
+use sp_runtime::DispatchError;
+
+fn set(who: u64, cid: &[u8], settings: &[u8]) -> frame_support::dispatch::DispatchResult {
+	ContractMetadata::set_metadata(
+		RuntimeOrigin::signed(who),
+		CONTRACT,
+		MetadataSource::Ipfs(cid.to_vec()),
+		settings.to_vec(),
+	)
+}
+
+#[test]
+fn deployer_sets_metadata_against_a_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set(1, b"cid", b"0.8"));
+
+		// 10 per item and 1 per byte.
+		assert_eq!(Balances::reserved_balance(1), 16);
+		assert_eq!(
+			ContractMetadata::contract_metadata(CONTRACT),
+			Some(Record {
+				source: MetadataSource::Ipfs(b"cid".to_vec()),
+				compiler_settings: b"0.8".to_vec(),
+				submitter: 1,
+				deposit: 16,
+				verified: false,
+			})
+		);
+		System::assert_last_event(
+			Event::MetadataSet { contract: CONTRACT, submitter: 1, verified: false }.into(),
+		);
+	});
+}
+
+#[test]
+fn verifier_replaces_metadata_and_the_old_deposit_is_returned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set(1, b"cid", b"0.8"));
+		assert_ok!(set(VERIFIER, b"cid-2", b""));
+
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(VERIFIER), 15);
+		let metadata = ContractMetadata::contract_metadata(CONTRACT).unwrap();
+		assert_eq!(metadata.submitter, VERIFIER);
+		assert!(metadata.verified);
+	});
+}
+
+#[test]
+fn deployer_cannot_replace_verified_metadata() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set(VERIFIER, b"cid", b""));
+		assert_noop!(set(1, b"cid-2", b""), Error::<Test>::Verified);
+
+		// Unverified metadata stays open to the deployer.
+		assert_ok!(ContractMetadata::clear_metadata(RuntimeOrigin::signed(VERIFIER), CONTRACT));
+		assert_ok!(set(1, b"cid", b""));
+		assert_ok!(set(1, b"cid-2", b""));
+		assert_ok!(ContractMetadata::clear_metadata(RuntimeOrigin::signed(1), CONTRACT));
+	});
+}
+
+#[test]
+fn set_metadata_weight_grows_with_the_metadata() {
+	let weight = |cid: &[u8], settings: &[u8]| {
+		Call::<Test>::set_metadata {
+			contract: CONTRACT,
+			source: MetadataSource::CompressedAbi(cid.to_vec()),
+			compiler_settings: settings.to_vec(),
+		}
+		.get_dispatch_info()
+		.weight
+	};
+
+	let empty = weight(b"", b"");
+	let byte = weight(b"a", b"").saturating_sub(empty);
+	assert!(byte.ref_time() > 0);
+	assert_eq!(weight(b"abc", b"de"), empty.saturating_add(byte.saturating_mul(5)));
+}
+
+#[test]
+fn only_deployer_or_verifier_sets_metadata() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(set(2, b"cid", b""), Error::<Test>::NotDeployerOrVerifier);
+		assert_noop!(
+			ContractMetadata::set_metadata(
+				RuntimeOrigin::signed(1),
+				H160::repeat_byte(0xc1),
+				MetadataSource::Ipfs(b"cid".to_vec()),
+				Vec::new(),
+			),
+			Error::<Test>::NotDeployerOrVerifier
+		);
+		assert_noop!(set(1, b"too long cid", b""), Error::<Test>::TooLong);
+		assert_noop!(set(1, b"cid", b"too long settings"), Error::<Test>::TooLong);
+	});
+}
+
+#[test]
+fn clearing_returns_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ContractMetadata::clear_metadata(RuntimeOrigin::signed(1), CONTRACT),
+			Error::<Test>::NoMetadata
+		);
+		assert_ok!(set(VERIFIER, b"cid", b""));
+
+		assert_noop!(
+			ContractMetadata::clear_metadata(RuntimeOrigin::signed(2), CONTRACT),
+			Error::<Test>::NotAllowedToClear
+		);
+		// Verified metadata can only be cleared by a verifier, not even by the deployer.
+		assert_noop!(
+			ContractMetadata::clear_metadata(RuntimeOrigin::signed(1), CONTRACT),
+			Error::<Test>::Verified
+		);
+		assert_ok!(ContractMetadata::add_verifier(RuntimeOrigin::root(), 2));
+		assert_ok!(ContractMetadata::clear_metadata(RuntimeOrigin::signed(2), CONTRACT));
+		assert_eq!(Balances::reserved_balance(VERIFIER), 0);
+		assert_eq!(ContractMetadata::metadata(CONTRACT), None);
+		System::assert_last_event(Event::MetadataCleared { contract: CONTRACT }.into());
+	});
+}
+
+#[test]
+fn verifiers_are_appointed_by_manage_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ContractMetadata::add_verifier(RuntimeOrigin::signed(1), 2),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			ContractMetadata::add_verifier(RuntimeOrigin::root(), VERIFIER),
+			Error::<Test>::AlreadyVerifier
+		);
+
+		assert_ok!(ContractMetadata::add_verifier(RuntimeOrigin::root(), 2));
+		assert_ok!(set(2, b"cid", b""));
+		assert!(ContractMetadata::contract_metadata(CONTRACT).unwrap().verified);
+
+		// Dismissing keeps what the verifier already submitted verified.
+		assert_ok!(ContractMetadata::remove_verifier(RuntimeOrigin::root(), 2));
+		System::assert_last_event(Event::VerifierRemoved { verifier: 2 }.into());
+		assert!(ContractMetadata::contract_metadata(CONTRACT).unwrap().verified);
+		assert_noop!(
+			ContractMetadata::remove_verifier(RuntimeOrigin::root(), 2),
+			Error::<Test>::NotVerifier
+		);
+	});
+}
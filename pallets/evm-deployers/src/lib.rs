@@ -0,0 +1,472 @@
+//! # EVM Deployers Pallet
+//!
+//! Controls who may deploy EVM contracts and keeps an audit trail of every deployment.
+//!
+//! In [`DeploymentMode::Open`] anyone can deploy. In [`DeploymentMode::AllowList`] only
+//! addresses in `AllowedDeployers` can; the runtime enforces this when it validates
+//! `pallet_ethereum::transact`, and [`Runner`] enforces it for every EVM execution, including
+//! `CREATE` and `CREATE2` run by contracts. The mode and the allow-list are set in genesis and
+//! changed afterwards by `ManageOrigin` (root or the council).
+//!
+//! Pre-EIP-155 transactions carry no chain id and can be replayed from any other chain. They are
+//! accepted like any other transaction until `ManageOrigin` sets `RejectUnprotected`; from then
+//! on the runtime only accepts them when `PresignedDeployments` is set, and only as contract
+//! creations from the canonical presigned deployers (see `system_contracts` in the runtime).
+//! `PresignedDeployments` also lets those creations through in `AllowList` mode.
+//!
+//! New contracts are noted by the `pallet_evm::OnCreate` hook, which only sees the contract a
+//! transaction creates, and by [`Runner`], which also sees those created by `CREATE` and
+//! `CREATE2`. The weight of recording a contract is added to the block when it is noted, and at
+//! the end of the block every contract that ended up with code is recorded in `Contracts` with
+//! its deployer and code hash.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use fp_evm::{CallInfo, Config as EvmConfig, CreateInfo};
+use frame_support::{dispatch::DispatchClass, weights::Weight};
+use pallet_evm::runner::RunnerError;
+use scale_info::TypeInfo;
+use sp_core::{Get, H160, H256, U256};
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// Who is allowed to deploy contracts.
+#[derive(
+	Clone, Copy, Default, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo,
+)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum DeploymentMode {
+	/// Anyone can deploy.
+	#[default]
+	Open,
+	/// Only addresses in `AllowedDeployers` can deploy.
+	AllowList,
+}
+
+/// A contract deployed while this pallet was in place.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub struct ContractRecord<BlockNumber> {
+	/// Account that sent the creating transaction, or the contract that ran `CREATE`.
+	pub deployer: H160,
+	/// Keccak-256 of the deployed (runtime) code.
+	pub code_hash: H256,
+	/// Block the contract was deployed in.
+	pub block: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_evm::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to switch the mode and to edit the allow-list.
+		type ManageOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+	}
+
+	/// Current deployment mode.
+	#[pallet::storage]
+	#[pallet::getter(fn mode)]
+	pub type Mode<T: Config> = StorageValue<_, DeploymentMode, ValueQuery>;
+
+	/// Addresses allowed to deploy in `AllowList` mode.
+	#[pallet::storage]
+	pub type AllowedDeployers<T: Config> = StorageMap<_, Blake2_128Concat, H160, (), OptionQuery>;
+
+	/// Every contract deployed since the pallet was added, keyed by contract address.
+	#[pallet::storage]
+	#[pallet::getter(fn contracts)]
+	pub type Contracts<T: Config> =
+		StorageMap<_, Blake2_128Concat, H160, ContractRecord<T::BlockNumber>, OptionQuery>;
+
+	/// Whether the canonical presigned deployment transactions are accepted.
+	#[pallet::storage]
+	#[pallet::getter(fn presigned_deployments)]
+	pub type PresignedDeployments<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Whether transactions without an EIP-155 chain id are rejected.
+	#[pallet::storage]
+	#[pallet::getter(fn reject_unprotected)]
+	pub type RejectUnprotected<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Contracts created in the current block, with their deployer. Drained in `on_finalize`.
+	#[pallet::storage]
+	pub type PendingContracts<T: Config> = StorageMap<_, Twox64Concat, H160, H160, OptionQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		pub mode: DeploymentMode,
+		pub deployers: Vec<H160>,
+		pub presigned_deployments: bool,
+		pub reject_unprotected: bool,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self {
+				mode: DeploymentMode::Open,
+				deployers: Vec::new(),
+				presigned_deployments: false,
+				reject_unprotected: false,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			Mode::<T>::put(self.mode);
+			PresignedDeployments::<T>::put(self.presigned_deployments);
+			RejectUnprotected::<T>::put(self.reject_unprotected);
+			for deployer in &self.deployers {
+				AllowedDeployers::<T>::insert(deployer, ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The deployment mode was changed.
+		ModeChanged { mode: DeploymentMode },
+		/// An address was added to the allow-list.
+		DeployerAdded { deployer: H160 },
+		/// An address was removed from the allow-list.
+		DeployerRemoved { deployer: H160 },
+		/// Presigned deployment transactions were allowed or disallowed.
+		PresignedDeploymentsSet { allowed: bool },
+		/// Transactions without a chain id were rejected or accepted again.
+		RejectUnprotectedSet { reject: bool },
+		/// A contract was deployed.
+		ContractDeployed { contract: H160, deployer: H160, code_hash: H256 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The address is already on the allow-list.
+		AlreadyAllowed,
+		/// The address is not on the allow-list.
+		NotAllowed,
+		/// The sender may not deploy contracts in the current mode.
+		DeploymentNotAllowed,
+		/// The transaction has no chain id, these are rejected, and it is not an allowed
+		/// presigned deployment.
+		UnprotectedTransaction,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			// Draining `PendingContracts` in `on_finalize`. Each record is charged when noted.
+			T::DbWeight::get().reads(1)
+		}
+
+		fn on_finalize(now: T::BlockNumber) {
+			for (contract, deployer) in PendingContracts::<T>::drain() {
+				let code = pallet_evm::AccountCodes::<T>::get(contract);
+				// Reverted or out-of-gas creations leave no code behind.
+				if code.is_empty() {
+					continue
+				}
+
+				let code_hash = H256::from(sp_io::hashing::keccak_256(&code));
+				Contracts::<T>::insert(
+					contract,
+					ContractRecord { deployer, code_hash, block: now },
+				);
+				Self::deposit_event(Event::ContractDeployed { contract, deployer, code_hash });
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Switch between open and allow-list deployment.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_mode(origin: OriginFor<T>, mode: DeploymentMode) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+
+			Mode::<T>::put(mode);
+			Self::deposit_event(Event::ModeChanged { mode });
+			Ok(())
+		}
+
+		/// Allow `deployer` to deploy contracts in allow-list mode.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn add_deployer(origin: OriginFor<T>, deployer: H160) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			ensure!(!AllowedDeployers::<T>::contains_key(deployer), Error::<T>::AlreadyAllowed);
+
+			AllowedDeployers::<T>::insert(deployer, ());
+			Self::deposit_event(Event::DeployerAdded { deployer });
+			Ok(())
+		}
+
+		/// Take `deployer` off the allow-list.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_deployer(origin: OriginFor<T>, deployer: H160) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			ensure!(AllowedDeployers::<T>::contains_key(deployer), Error::<T>::NotAllowed);
+
+			AllowedDeployers::<T>::remove(deployer);
+			Self::deposit_event(Event::DeployerRemoved { deployer });
+			Ok(())
+		}
+
+		/// Allow or disallow the canonical presigned deployment transactions.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_presigned_deployments(origin: OriginFor<T>, allowed: bool) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+
+			PresignedDeployments::<T>::put(allowed);
+			Self::deposit_event(Event::PresignedDeploymentsSet { allowed });
+			Ok(())
+		}
+
+		/// Reject or accept again transactions without an EIP-155 chain id, apart from the
+		/// allowed presigned deployments.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_reject_unprotected(origin: OriginFor<T>, reject: bool) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+
+			RejectUnprotected::<T>::put(reject);
+			Self::deposit_event(Event::RejectUnprotectedSet { reject });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether `who` may deploy a contract in the current mode.
+	pub fn can_deploy(who: &H160) -> bool {
+		match Mode::<T>::get() {
+			DeploymentMode::Open => true,
+			DeploymentMode::AllowList => AllowedDeployers::<T>::contains_key(who),
+		}
+	}
+
+	/// Queue `contract` for recording in `on_finalize` and add the cost of recording it to the
+	/// block.
+	fn note_created(contract: H160, deployer: H160) {
+		PendingContracts::<T>::insert(contract, deployer);
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			T::DbWeight::get().reads_writes(2, 3),
+			DispatchClass::Mandatory,
+		);
+	}
+}
+
+impl<T: Config> pallet_evm::OnCreate<T> for Pallet<T> {
+	fn on_create(owner: H160, contract: H160) {
+		Pallet::<T>::note_created(contract, owner);
+	}
+}
+
+/// `pallet_evm::Runner` enforcing the deployment mode around the runner `R`.
+///
+/// Contract creations by a sender that may not deploy are refused before execution. Calls from
+/// such a sender run with a contract size limit of zero, so every `CREATE` or `CREATE2` in them
+/// fails inside the EVM: the creating contract gets the zero address back, keeps the value it
+/// meant to send and the init code's changes are reverted.
+pub struct Runner<T, R>(PhantomData<(T, R)>);
+
+/// Collects the contracts an execution creates.
+#[derive(Default)]
+struct CreateListener {
+	created: Vec<(H160, H160)>,
+}
+
+impl evm::tracing::EventListener for CreateListener {
+	fn event(&mut self, event: evm::tracing::Event) {
+		if let evm::tracing::Event::Create { caller, address, .. } = event {
+			self.created.push((address, caller));
+		}
+	}
+}
+
+impl<T: Config, R: pallet_evm::Runner<T>> Runner<T, R> {
+	fn ensure_can_deploy(source: &H160) -> Result<(), RunnerError<DispatchError>> {
+		if Pallet::<T>::can_deploy(source) {
+			return Ok(())
+		}
+		Err(RunnerError {
+			error: Error::<T>::DeploymentNotAllowed.into(),
+			weight: T::DbWeight::get().reads(2),
+		})
+	}
+
+	/// Run `execute` on behalf of `source` and queue the contracts it created, or run it unable
+	/// to create contracts if `source` may not deploy.
+	fn execute<V>(
+		source: H160,
+		config: &EvmConfig,
+		execute: impl FnOnce(&EvmConfig) -> Result<V, RunnerError<R::Error>>,
+	) -> Result<V, RunnerError<DispatchError>> {
+		let result = if Pallet::<T>::can_deploy(&source) {
+			let mut listener = CreateListener::default();
+			let result = evm::tracing::using(&mut listener, || execute(config));
+			for (contract, creator) in listener.created {
+				Pallet::<T>::note_created(contract, creator);
+			}
+			result
+		} else {
+			execute(&EvmConfig { create_contract_limit: Some(0), ..config.clone() })
+		};
+		result.map_err(|e| RunnerError { error: e.error.into(), weight: e.weight })
+	}
+}
+
+impl<T: Config, R: pallet_evm::Runner<T>> pallet_evm::Runner<T> for Runner<T, R> {
+	type Error = DispatchError;
+
+	fn validate(
+		source: H160,
+		target: Option<H160>,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		evm_config: &EvmConfig,
+	) -> Result<(), RunnerError<Self::Error>> {
+		if target.is_none() {
+			Self::ensure_can_deploy(&source)?;
+		}
+		R::validate(
+			source,
+			target,
+			input,
+			value,
+			gas_limit,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			nonce,
+			access_list,
+			is_transactional,
+			evm_config,
+		)
+		.map_err(|e| RunnerError { error: e.error.into(), weight: e.weight })
+	}
+
+	fn call(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		validate: bool,
+		config: &EvmConfig,
+	) -> Result<CallInfo, RunnerError<Self::Error>> {
+		Self::execute(source, config, |config| {
+			R::call(
+				source,
+				target,
+				input,
+				value,
+				gas_limit,
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list,
+				is_transactional,
+				validate,
+				config,
+			)
+		})
+	}
+
+	fn create(
+		source: H160,
+		init: Vec<u8>,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		validate: bool,
+		config: &EvmConfig,
+	) -> Result<CreateInfo, RunnerError<Self::Error>> {
+		Self::ensure_can_deploy(&source)?;
+		Self::execute(source, config, |config| {
+			R::create(
+				source,
+				init,
+				value,
+				gas_limit,
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list,
+				is_transactional,
+				validate,
+				config,
+			)
+		})
+	}
+
+	fn create2(
+		source: H160,
+		init: Vec<u8>,
+		salt: H256,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		validate: bool,
+		config: &EvmConfig,
+	) -> Result<CreateInfo, RunnerError<Self::Error>> {
+		Self::ensure_can_deploy(&source)?;
+		Self::execute(source, config, |config| {
+			R::create2(
+				source,
+				init,
+				salt,
+				value,
+				gas_limit,
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list,
+				is_transactional,
+				validate,
+				config,
+			)
+		})
+	}
+}
@@ -0,0 +1,172 @@
+use crate::{mock::*, ContractRecord, DeploymentMode, Error, Event, PendingContracts};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchClass, DispatchResultWithPostInfo},
+	traits::{Get, Hooks},
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_core::{H160, H256, U256};
+use sp_runtime::DispatchError;
+
+/// Init code deploying the one-byte contract `STOP`.
+const INIT_CODE: [u8; 10] = [0x60, 0x00, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+
+/// Contract running `CREATE` with `INIT_CODE` on every call and storing the result in slot 0.
+fn factory_code() -> Vec<u8> {
+	let mut code = vec![0x69];
+	code.extend_from_slice(&INIT_CODE);
+	// MSTORE(0, init code); SSTORE(0, CREATE(0, 22, 10)); STOP
+	code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x0a, 0x60, 0x16, 0x60, 0x00, 0xf0]);
+	code.extend_from_slice(&[0x60, 0x00, 0x55, 0x00]);
+	code
+}
+
+/// What the last `CREATE` of `factory` returned.
+fn created_by(factory: H160) -> H256 {
+	pallet_evm::AccountStorages::<Test>::get(factory, H256::zero())
+}
+
+/// Weight added to the block for contract records.
+fn record_weight() -> Weight {
+	*System::block_weight().get(DispatchClass::Mandatory)
+}
+
+fn create(source: H160) -> DispatchResultWithPostInfo {
+	EVM::create(
+		RuntimeOrigin::root(),
+		source,
+		INIT_CODE.to_vec(),
+		U256::zero(),
+		1_000_000,
+		U256::zero(),
+		None,
+		None,
+		vec![],
+	)
+}
+
+fn call(source: H160, target: H160) -> DispatchResultWithPostInfo {
+	EVM::call(
+		RuntimeOrigin::root(),
+		source,
+		target,
+		Vec::new(),
+		U256::zero(),
+		1_000_000,
+		U256::zero(),
+		None,
+		None,
+		vec![],
+	)
+}
+
+/// Addresses holding code.
+fn contracts() -> Vec<H160> {
+	pallet_evm::AccountCodes::<Test>::iter_keys().collect()
+}
+
+#[test]
+fn only_allowed_senders_create_contracts() {
+	new_test_ext().execute_with(|| {
+		let refused = create(BOB).unwrap_err();
+		assert_eq!(refused.error, Error::<Test>::DeploymentNotAllowed.into());
+		assert!(contracts().is_empty());
+
+		assert_ok!(create(ALICE));
+		let contract = contracts()[0];
+		EvmDeployers::on_finalize(1);
+		let code_hash = H256(sp_io::hashing::keccak_256(&[0x00]));
+		let record = ContractRecord { deployer: ALICE, code_hash, block: 1 };
+		assert_eq!(EvmDeployers::contracts(contract), Some(record));
+		System::assert_last_event(
+			Event::ContractDeployed { contract, deployer: ALICE, code_hash }.into(),
+		);
+
+		assert_ok!(EvmDeployers::set_mode(RuntimeOrigin::root(), DeploymentMode::Open));
+		assert_ok!(create(BOB));
+		assert_eq!(contracts().len(), 2);
+	});
+}
+
+#[test]
+fn contracts_created_by_contracts_follow_the_sender() {
+	new_test_ext().execute_with(|| {
+		let factory = H160::repeat_byte(0xfa);
+		pallet_evm::AccountCodes::<Test>::insert(factory, factory_code());
+
+		// The call succeeds, but `CREATE` fails inside it.
+		assert_ok!(call(BOB, factory));
+		assert_eq!(contracts(), vec![factory]);
+		assert_eq!(created_by(factory), H256::zero());
+		assert_eq!(PendingContracts::<Test>::iter().count(), 0);
+
+		assert_ok!(call(ALICE, factory));
+		let created: Vec<_> = contracts().into_iter().filter(|c| *c != factory).collect();
+		assert_eq!(created.len(), 1);
+		assert_eq!(created_by(factory), H256::from(created[0]));
+		EvmDeployers::on_finalize(1);
+		assert_eq!(
+			EvmDeployers::contracts(created[0]).map(|record| record.deployer),
+			Some(factory)
+		);
+	});
+}
+
+#[test]
+fn contract_records_are_charged_per_creation() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(EvmDeployers::on_initialize(1), RocksDbWeight::get().reads(1));
+		assert_eq!(record_weight(), Weight::zero());
+
+		let record = RocksDbWeight::get().reads_writes(2, 3);
+		assert_ok!(create(ALICE));
+		assert_eq!(record_weight(), record);
+
+		let factory = H160::repeat_byte(0xfa);
+		pallet_evm::AccountCodes::<Test>::insert(factory, factory_code());
+		assert_ok!(call(BOB, factory));
+		assert_eq!(record_weight(), record);
+		assert_ok!(call(ALICE, factory));
+		assert_eq!(record_weight(), record.saturating_mul(2));
+	});
+}
+
+#[test]
+fn allow_list_is_managed_by_manage_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			EvmDeployers::add_deployer(RuntimeOrigin::signed(1), BOB),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			EvmDeployers::add_deployer(RuntimeOrigin::root(), ALICE),
+			Error::<Test>::AlreadyAllowed
+		);
+
+		assert_ok!(EvmDeployers::add_deployer(RuntimeOrigin::root(), BOB));
+		assert!(EvmDeployers::can_deploy(&BOB));
+		System::assert_last_event(Event::DeployerAdded { deployer: BOB }.into());
+
+		assert_ok!(EvmDeployers::remove_deployer(RuntimeOrigin::root(), BOB));
+		assert!(!EvmDeployers::can_deploy(&BOB));
+		assert_noop!(
+			EvmDeployers::remove_deployer(RuntimeOrigin::root(), BOB),
+			Error::<Test>::NotAllowed
+		);
+	});
+}
+
+#[test]
+fn unprotected_transactions_are_accepted_until_governance_rejects_them() {
+	new_test_ext().execute_with(|| {
+		assert!(!EvmDeployers::reject_unprotected());
+		assert_noop!(
+			EvmDeployers::set_reject_unprotected(RuntimeOrigin::signed(1), true),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(EvmDeployers::set_reject_unprotected(RuntimeOrigin::root(), true));
+		assert!(EvmDeployers::reject_unprotected());
+		System::assert_last_event(Event::RejectUnprotectedSet { reject: true }.into());
+	});
+}
@@ -0,0 +1,333 @@
+//! # EVM Paymaster Pallet
+//!
+//! Lets a contract pay for the calls made to it, so that users holding no GNF can use it.
+//!
+//! The contract's deployer, as reported by `T::Deployers`, or `ManageOrigin` (root or the
+//! council in this runtime) sponsors a contract with `set_sponsorship`, capping the gas of a
+//! single call. Users then call the contract through `sponsored_call`, an extrinsic signed with
+//! the same key as their Ethereum account. The contract's own balance pays for it:
+//! - the extrinsic fee, when the runtime charges fees through [`SponsoredFees`]. The fee leaves out
+//!   the weight of the gas, which the gas price pays for;
+//! - the gas of the EVM call, which runs with the user as `msg.sender`. The gas is moved from the
+//!   contract to the user before the call, and what the call did not use is moved back after it.
+//!
+//! A call from a `source` other than the signer's address, or over the contract's gas cap, is
+//! rejected and its fee paid by the signer. So is the fee of a call with a tip: the tip is the
+//! signer's to offer, not the contract's.
+//!
+//! Ethereum transactions are always paid by their sender: `pallet_ethereum` checks the sender's
+//! balance before the transaction reaches the runtime's fee handling.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use frame_support::{
+	dispatch::{DispatchInfo, PostDispatchInfo},
+	traits::{Currency, IsSubType},
+	weights::{Weight, WeightToFee},
+};
+use pallet_contract_metadata::DeployerLookup;
+use pallet_evm::{AddressMapping, GasWeightMapping};
+use pallet_transaction_payment::OnChargeTransaction;
+use sp_core::H160;
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SaturatedConversion, Zero},
+	transaction_validity::TransactionValidityError,
+	FixedPointNumber,
+};
+use sp_std::marker::PhantomData;
+
+type BalanceOf<T> = <<T as pallet_evm::Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use fp_evm::ExitReason;
+	use frame_support::{pallet_prelude::*, traits::ExistenceRequirement};
+	use frame_system::pallet_prelude::*;
+	use pallet_evm::{FeeCalculator, Runner};
+	use sp_core::U256;
+	use sp_runtime::traits::{UniqueSaturatedFrom, UniqueSaturatedInto};
+	use sp_std::vec::Vec;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_evm::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Who deployed a given contract.
+		type Deployers: DeployerLookup<Self::AccountId>;
+
+		/// Origin allowed to sponsor and stop sponsoring any contract.
+		type ManageOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+	}
+
+	/// Sponsored contracts, with the most gas a single sponsored call can use.
+	#[pallet::storage]
+	#[pallet::getter(fn sponsorship)]
+	pub type Sponsorships<T: Config> = StorageMap<_, Blake2_128Concat, H160, u64, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `contract` now pays for calls using at most `max_gas` each.
+		SponsorshipSet { contract: H160, max_gas: u64 },
+		/// `contract` no longer pays for calls.
+		SponsorshipRemoved { contract: H160 },
+		/// `contract` paid for a call made by `caller`.
+		SponsoredCall { contract: H160, caller: H160, used_gas: U256, succeeded: bool },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Only the deployer or `ManageOrigin` can change the sponsorship of this contract.
+		NotDeployer,
+		/// The contract is not sponsored.
+		NotSponsored,
+		/// The gas limit is above the contract's sponsorship.
+		GasLimitTooHigh,
+		/// `source` is not the signer's address.
+		WrongSource,
+		/// The gas price times the gas limit does not fit in a balance.
+		FeeOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Make `contract` pay for calls to it using at most `max_gas` each.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_sponsorship(
+			origin: OriginFor<T>,
+			contract: H160,
+			max_gas: u64,
+		) -> DispatchResult {
+			Self::ensure_deployer_or_manager(origin, &contract)?;
+
+			Sponsorships::<T>::insert(contract, max_gas);
+			Self::deposit_event(Event::SponsorshipSet { contract, max_gas });
+			Ok(())
+		}
+
+		/// Stop `contract` from paying for calls to it.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 1))]
+		pub fn remove_sponsorship(origin: OriginFor<T>, contract: H160) -> DispatchResult {
+			Self::ensure_deployer_or_manager(origin, &contract)?;
+			Sponsorships::<T>::take(contract).ok_or(Error::<T>::NotSponsored)?;
+
+			Self::deposit_event(Event::SponsorshipRemoved { contract });
+			Ok(())
+		}
+
+		/// Call `target` from `source`, the signer's address, with `target` paying the gas.
+		///
+		/// The call transfers no value and is paid at the base fee.
+		#[pallet::call_index(2)]
+		#[pallet::weight(
+			T::GasWeightMapping::gas_to_weight(*gas_limit, true)
+				.saturating_add(Pallet::<T>::sponsored_call_base_weight())
+		)]
+		pub fn sponsored_call(
+			origin: OriginFor<T>,
+			source: H160,
+			target: H160,
+			input: Vec<u8>,
+			gas_limit: u64,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(T::AddressMapping::into_account_id(source) == who, Error::<T>::WrongSource);
+			let max_gas = Sponsorships::<T>::get(target).ok_or(Error::<T>::NotSponsored)?;
+			ensure!(gas_limit <= max_gas, Error::<T>::GasLimitTooHigh);
+
+			let (gas_price, _) = T::FeeCalculator::min_gas_price();
+			let to_balance = |fee: U256| {
+				u128::try_from(fee)
+					.map(|fee| BalanceOf::<T>::unique_saturated_from(fee))
+					.map_err(|_| Error::<T>::FeeOverflow)
+			};
+			let sponsor = T::AddressMapping::into_account_id(target);
+			let fee = gas_price.checked_mul(gas_limit.into()).ok_or(Error::<T>::FeeOverflow)?;
+			// The minimum balance on top keeps the signer's account alive while the runner holds
+			// the fee, or the refund of the unused gas would be lost with it.
+			let lent = to_balance(fee)?.saturating_add(T::Currency::minimum_balance());
+			T::Currency::transfer(&sponsor, &who, lent, ExistenceRequirement::KeepAlive)?;
+
+			let info = T::Runner::call(
+				source,
+				target,
+				input,
+				U256::zero(),
+				gas_limit,
+				Some(gas_price),
+				None,
+				None,
+				Vec::new(),
+				true,
+				true,
+				T::config(),
+			)
+			.map_err(|err| -> DispatchError { err.error.into() })?;
+
+			// The runner refunded the unused gas to `source`.
+			let unused = fee.saturating_sub(info.used_gas.saturating_mul(gas_price));
+			T::Currency::transfer(
+				&who,
+				&sponsor,
+				to_balance(unused)?.saturating_add(T::Currency::minimum_balance()),
+				ExistenceRequirement::AllowDeath,
+			)?;
+
+			Self::deposit_event(Event::SponsoredCall {
+				contract: target,
+				caller: source,
+				used_gas: info.used_gas,
+				succeeded: matches!(info.exit_reason, ExitReason::Succeed(_)),
+			});
+			Ok(PostDispatchInfo {
+				actual_weight: Some(
+					T::GasWeightMapping::gas_to_weight(info.used_gas.unique_saturated_into(), true)
+						.saturating_add(Self::sponsored_call_base_weight()),
+				),
+				pays_fee: Pays::Yes,
+			})
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn ensure_deployer_or_manager(origin: OriginFor<T>, contract: &H160) -> DispatchResult {
+			if T::ManageOrigin::try_origin(origin.clone()).is_ok() {
+				return Ok(())
+			}
+			let who = ensure_signed(origin)?;
+			ensure!(
+				T::Deployers::deployer(contract).as_ref() == Some(&who),
+				Error::<T>::NotDeployer
+			);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Weight of `sponsored_call` besides the gas of its EVM call.
+	pub fn sponsored_call_base_weight() -> Weight {
+		T::DbWeight::get().reads_writes(3, 4)
+	}
+
+	/// The account paying for `call` signed by `who`: the contract it calls, if it is a sponsored
+	/// call from `who`'s address that the contract's sponsorship covers.
+	///
+	/// A call `sponsored_call` would reject, with another `source` or too much gas, is paid by
+	/// its signer, so that nobody can make a contract pay for failed calls. [`SponsoredFees`]
+	/// also leaves calls with a tip to their signer.
+	pub fn sponsor_of(
+		who: &T::AccountId,
+		call: &<T as frame_system::Config>::RuntimeCall,
+	) -> Option<T::AccountId>
+	where
+		<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+	{
+		match call.is_sub_type()? {
+			Call::sponsored_call { source, target, gas_limit, .. }
+				if T::AddressMapping::into_account_id(*source) == *who &&
+					Sponsorships::<T>::get(target)
+						.map_or(false, |max_gas| *gas_limit <= max_gas) =>
+				Some(T::AddressMapping::into_account_id(*target)),
+			_ => None,
+		}
+	}
+}
+
+/// Charges transaction fees through `Inner`, from the sponsor for sponsored calls without a tip
+/// and from the signer otherwise.
+///
+/// The fee of a `sponsored_call` is charged at [`Pallet::sponsored_call_base_weight`]: the gas
+/// of its EVM call is already paid at the gas price.
+pub struct SponsoredFees<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T, Inner> SponsoredFees<T, Inner>
+where
+	T: Config + pallet_transaction_payment::Config,
+	Inner: OnChargeTransaction<T>,
+{
+	/// The part of a `sponsored_call`'s fee at `weight` that pays for its gas.
+	fn gas_fee(weight: Weight) -> Inner::Balance {
+		let weight_fee = |weight: Weight| {
+			<T as pallet_transaction_payment::Config>::WeightToFee::weight_to_fee(&weight)
+				.saturated_into::<u128>()
+		};
+		let gas_fee = weight_fee(weight)
+			.saturating_sub(weight_fee(Pallet::<T>::sponsored_call_base_weight()));
+		pallet_transaction_payment::Pallet::<T>::next_fee_multiplier()
+			.saturating_mul_int(gas_fee)
+			.saturated_into()
+	}
+}
+
+impl<T, Inner> OnChargeTransaction<T> for SponsoredFees<T, Inner>
+where
+	T: Config + pallet_transaction_payment::Config,
+	<T as frame_system::Config>::RuntimeCall:
+		IsSubType<Call<T>> + Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	Inner: OnChargeTransaction<T>,
+{
+	type Balance = Inner::Balance;
+	/// The account that paid, whether the call was a `sponsored_call`, and what `Inner`
+	/// withdrew from the account.
+	type LiquidityInfo = (Option<T::AccountId>, bool, Inner::LiquidityInfo);
+
+	fn withdraw_fee(
+		who: &T::AccountId,
+		call: &<T as frame_system::Config>::RuntimeCall,
+		dispatch_info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+		fee: Self::Balance,
+		tip: Self::Balance,
+	) -> Result<Self::LiquidityInfo, TransactionValidityError> {
+		let payer = Pallet::<T>::sponsor_of(who, call)
+			.filter(|_| tip.is_zero())
+			.unwrap_or_else(|| who.clone());
+		let sponsored_call = matches!(call.is_sub_type(), Some(Call::sponsored_call { .. }));
+		let fee = if sponsored_call {
+			fee.saturating_sub(Self::gas_fee(dispatch_info.weight))
+		} else {
+			fee
+		};
+		let withdrawn = Inner::withdraw_fee(&payer, call, dispatch_info, fee, tip)?;
+		Ok((Some(payer), sponsored_call, withdrawn))
+	}
+
+	fn correct_and_deposit_fee(
+		who: &T::AccountId,
+		dispatch_info: &DispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+		post_info: &PostDispatchInfoOf<<T as frame_system::Config>::RuntimeCall>,
+		corrected_fee: Self::Balance,
+		tip: Self::Balance,
+		(payer, sponsored_call, withdrawn): Self::LiquidityInfo,
+	) -> Result<(), TransactionValidityError> {
+		let corrected_fee = if sponsored_call {
+			corrected_fee.saturating_sub(Self::gas_fee(post_info.calc_actual_weight(dispatch_info)))
+		} else {
+			corrected_fee
+		};
+		Inner::correct_and_deposit_fee(
+			payer.as_ref().unwrap_or(who),
+			dispatch_info,
+			post_info,
+			corrected_fee,
+			tip,
+			withdrawn,
+		)
+	}
+}
@@ -0,0 +1,171 @@
+use crate as pallet_evm_paymaster;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, ConstU64, ConstU8, GenesisBuild},
+	weights::{constants::RocksDbWeight, IdentityFee, Weight},
+};
+use frame_system::EnsureRoot;
+use pallet_contract_metadata::DeployerLookup;
+use pallet_evm::{AddressMapping, EnsureAddressNever, EnsureAddressRoot, FeeCalculator};
+use pallet_transaction_payment::CurrencyAdapter;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		Timestamp: pallet_timestamp,
+		TransactionPayment: pallet_transaction_payment,
+		EVM: pallet_evm,
+		EvmPaymaster: pallet_evm_paymaster,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<u64>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
+impl pallet_transaction_payment::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type OnChargeTransaction = crate::SponsoredFees<Self, CurrencyAdapter<Balances, ()>>;
+	type OperationalFeeMultiplier = ConstU8<5>;
+	type WeightToFee = IdentityFee<u64>;
+	type LengthToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+/// Maps an address to the account of its low 8 bytes.
+pub struct LowBytesAddressMapping;
+
+impl AddressMapping<u64> for LowBytesAddressMapping {
+	fn into_account_id(address: H160) -> u64 {
+		address.to_low_u64_be()
+	}
+}
+
+/// One unit per gas.
+pub struct FixedGasPrice;
+
+impl FeeCalculator for FixedGasPrice {
+	fn min_gas_price() -> (U256, Weight) {
+		(U256::one(), Weight::zero())
+	}
+}
+
+parameter_types! {
+	pub BlockGasLimit: U256 = U256::from(15_000_000);
+	pub WeightPerGas: Weight = Weight::from_ref_time(20_000);
+}
+
+impl pallet_evm::Config for Test {
+	type FeeCalculator = FixedGasPrice;
+	type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+	type WeightPerGas = WeightPerGas;
+	type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+	type CallOrigin = EnsureAddressRoot<u64>;
+	type WithdrawOrigin = EnsureAddressNever<u64>;
+	type AddressMapping = LowBytesAddressMapping;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type PrecompilesType = ();
+	type PrecompilesValue = ();
+	type ChainId = ();
+	type BlockGasLimit = BlockGasLimit;
+	type Runner = pallet_evm::runner::stack::Runner<Self>;
+	type OnChargeTransaction = ();
+	type OnCreate = ();
+	type FindAuthor = ();
+}
+
+/// [`DEPLOYER`] deployed [`CONTRACT`].
+pub struct MockDeployers;
+
+impl DeployerLookup<u64> for MockDeployers {
+	fn deployer(contract: &H160) -> Option<u64> {
+		(*contract == CONTRACT).then_some(DEPLOYER)
+	}
+}
+
+impl pallet_evm_paymaster::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Deployers = MockDeployers;
+	type ManageOrigin = EnsureRoot<u64>;
+}
+
+/// A contract that stops at once, the account of its low bytes holding `CONTRACT_BALANCE`.
+pub const CONTRACT: H160 = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xc0]);
+pub const CONTRACT_BALANCE: u64 = 1_000_000_000_000;
+pub const DEPLOYER: u64 = 10;
+pub const DEPLOYER_BALANCE: u64 = 1_000_000_000_000;
+/// The account of [`USER_ADDRESS`], holding nothing.
+pub const USER: u64 = 1;
+pub const USER_ADDRESS: H160 = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(CONTRACT.to_low_u64_be(), CONTRACT_BALANCE), (DEPLOYER, DEPLOYER_BALANCE)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		pallet_evm::AccountCodes::<Test>::insert(CONTRACT, vec![0x00]);
+	});
+	ext
+}
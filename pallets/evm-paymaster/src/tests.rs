@@ -0,0 +1,204 @@
+use crate::{mock::*, Error, Event, Sponsorships};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchInfo, DispatchResultWithPostInfo, GetDispatchInfo, Pays, PostDispatchInfo},
+	traits::Currency,
+};
+use pallet_evm::GasWeightMapping;
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_core::{H160, U256};
+use sp_runtime::{traits::SignedExtension, DispatchError};
+
+/// Gas used by a call to a contract that stops at once.
+const CALL_GAS: u64 = 21_000;
+
+fn sponsored_call(source: H160, gas_limit: u64) -> RuntimeCall {
+	RuntimeCall::EvmPaymaster(crate::Call::sponsored_call {
+		source,
+		target: CONTRACT,
+		input: Vec::new(),
+		gas_limit,
+	})
+}
+
+fn call(who: u64, source: H160, gas_limit: u64) -> DispatchResultWithPostInfo {
+	EvmPaymaster::sponsored_call(
+		RuntimeOrigin::signed(who),
+		source,
+		CONTRACT,
+		Vec::new(),
+		gas_limit,
+	)
+}
+
+fn contract_balance() -> u64 {
+	Balances::free_balance(CONTRACT.to_low_u64_be())
+}
+
+#[test]
+fn deployer_or_manager_sponsors_a_contract() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			EvmPaymaster::set_sponsorship(RuntimeOrigin::signed(USER), CONTRACT, 100_000),
+			Error::<Test>::NotDeployer
+		);
+
+		assert_ok!(EvmPaymaster::set_sponsorship(
+			RuntimeOrigin::signed(DEPLOYER),
+			CONTRACT,
+			100_000
+		));
+		System::assert_last_event(
+			Event::SponsorshipSet { contract: CONTRACT, max_gas: 100_000 }.into(),
+		);
+		assert_ok!(EvmPaymaster::set_sponsorship(RuntimeOrigin::root(), CONTRACT, 50_000));
+		assert_eq!(EvmPaymaster::sponsorship(CONTRACT), Some(50_000));
+
+		assert_noop!(
+			EvmPaymaster::remove_sponsorship(RuntimeOrigin::signed(USER), CONTRACT),
+			Error::<Test>::NotDeployer
+		);
+		assert_ok!(EvmPaymaster::remove_sponsorship(RuntimeOrigin::signed(DEPLOYER), CONTRACT));
+		System::assert_last_event(Event::SponsorshipRemoved { contract: CONTRACT }.into());
+		assert_noop!(
+			EvmPaymaster::remove_sponsorship(RuntimeOrigin::root(), CONTRACT),
+			Error::<Test>::NotSponsored
+		);
+	});
+}
+
+#[test]
+fn the_contract_pays_the_gas_of_sponsored_calls() {
+	new_test_ext().execute_with(|| {
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+
+		assert_ok!(call(USER, USER_ADDRESS, 50_000));
+
+		System::assert_last_event(
+			Event::SponsoredCall {
+				contract: CONTRACT,
+				caller: USER_ADDRESS,
+				used_gas: U256::from(CALL_GAS),
+				succeeded: true,
+			}
+			.into(),
+		);
+		assert_eq!(contract_balance(), CONTRACT_BALANCE - CALL_GAS);
+		assert_eq!(Balances::free_balance(USER), 0);
+	});
+}
+
+#[test]
+fn sponsored_calls_are_checked() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(call(USER, USER_ADDRESS, 50_000), Error::<Test>::NotSponsored);
+
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+		assert_noop!(call(USER, USER_ADDRESS, 100_001), Error::<Test>::GasLimitTooHigh);
+		assert_noop!(call(DEPLOYER, USER_ADDRESS, 50_000), Error::<Test>::WrongSource);
+		assert_noop!(
+			EvmPaymaster::sponsored_call(
+				RuntimeOrigin::root(),
+				USER_ADDRESS,
+				CONTRACT,
+				Vec::new(),
+				50_000
+			),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn the_contract_pays_the_fee_of_the_calls_it_covers() {
+	new_test_ext().execute_with(|| {
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+		let info = DispatchInfo::default();
+		let fee = TransactionPayment::compute_fee(10, &info, 0);
+		assert!(fee > 0);
+
+		let call = sponsored_call(USER_ADDRESS, 100_000);
+		assert_eq!(EvmPaymaster::sponsor_of(&USER, &call), Some(CONTRACT.to_low_u64_be()));
+		assert_ok!(ChargeTransactionPayment::<Test>::from(0).pre_dispatch(&USER, &call, &info, 10));
+		assert_eq!(contract_balance(), CONTRACT_BALANCE - fee);
+		assert_eq!(Balances::free_balance(USER), 0);
+	});
+}
+
+#[test]
+fn the_signer_pays_the_fee_of_calls_the_contract_does_not_cover() {
+	new_test_ext().execute_with(|| {
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+		let info = DispatchInfo::default();
+		let fee = TransactionPayment::compute_fee(10, &info, 0);
+
+		// From another address than the signer's, then over the gas cap.
+		let deployer_address = H160::from_low_u64_be(DEPLOYER);
+		for call in
+			[sponsored_call(USER_ADDRESS, 50_000), sponsored_call(deployer_address, 100_001)]
+		{
+			assert_eq!(EvmPaymaster::sponsor_of(&DEPLOYER, &call), None);
+			assert_ok!(
+				ChargeTransactionPayment::<Test>::from(0).pre_dispatch(&DEPLOYER, &call, &info, 10)
+			);
+		}
+		assert_eq!(Balances::free_balance(DEPLOYER), DEPLOYER_BALANCE - 2 * fee);
+		assert_eq!(contract_balance(), CONTRACT_BALANCE);
+	});
+}
+
+#[test]
+fn the_signer_pays_the_fee_of_sponsored_calls_with_a_tip() {
+	new_test_ext().execute_with(|| {
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+		let info = DispatchInfo::default();
+		let call = sponsored_call(USER_ADDRESS, 100_000);
+
+		// The signer holds nothing to pay with, and the contract does not pay instead.
+		assert!(ChargeTransactionPayment::<Test>::from(1)
+			.pre_dispatch(&USER, &call, &info, 10)
+			.is_err());
+		assert_eq!(contract_balance(), CONTRACT_BALANCE);
+
+		Balances::make_free_balance_be(&USER, 1_000_000);
+		assert_ok!(ChargeTransactionPayment::<Test>::from(1).pre_dispatch(&USER, &call, &info, 10));
+		let fee = TransactionPayment::compute_fee(10, &info, 1);
+		assert_eq!(Balances::free_balance(USER), 1_000_000 - fee);
+		assert_eq!(contract_balance(), CONTRACT_BALANCE);
+	});
+}
+
+#[test]
+fn the_fee_of_sponsored_calls_leaves_out_their_gas() {
+	new_test_ext().execute_with(|| {
+		Sponsorships::<Test>::insert(CONTRACT, 100_000);
+		let call = sponsored_call(USER_ADDRESS, 100_000);
+		let info = call.get_dispatch_info();
+		let base_weight = EvmPaymaster::sponsored_call_base_weight();
+		let fee =
+			TransactionPayment::compute_fee(10, &DispatchInfo { weight: base_weight, ..info }, 0);
+		assert!(fee < TransactionPayment::compute_fee(10, &info, 0));
+
+		let pre = ChargeTransactionPayment::<Test>::from(0)
+			.pre_dispatch(&USER, &call, &info, 10)
+			.unwrap();
+		assert_eq!(contract_balance(), CONTRACT_BALANCE - fee);
+
+		// Nor is the gas the call used charged once it ran.
+		let post_info = PostDispatchInfo {
+			actual_weight: Some(
+				<Test as pallet_evm::Config>::GasWeightMapping::gas_to_weight(CALL_GAS, true)
+					.saturating_add(base_weight),
+			),
+			pays_fee: Pays::Yes,
+		};
+		assert_ok!(ChargeTransactionPayment::<Test>::post_dispatch(
+			Some(pre),
+			&info,
+			&post_info,
+			10,
+			&Ok(())
+		));
+		assert_eq!(contract_balance(), CONTRACT_BALANCE - fee);
+	});
+}
@@ -0,0 +1,59 @@
+//! The inherent carrying the block author's VRF.
+
+use crate::VrfOutput;
+use sp_inherents::InherentIdentifier;
+
+/// Identifier of the beacon inherent data.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"rndbeacn";
+
+/// Provides the block author's VRF, when the node signed one.
+///
+/// Nodes that do not author the block, or whose keystore lacks the author's key, provide
+/// nothing, and the block is built without a beacon update.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider(pub Option<VrfOutput>);
+
+#[cfg(feature = "std")]
+impl InherentDataProvider {
+	/// Signs the VRF of `slot` over the beacon output `previous` with `author`, a key of type
+	/// `key_type` in `keystore`.
+	///
+	/// The keystore may be remote, so the signature is awaited rather than blocked on.
+	pub async fn sign(
+		keystore: &dyn sp_keystore::CryptoStore,
+		key_type: sp_core::crypto::KeyTypeId,
+		author: &sp_core::sr25519::Public,
+		slot: u64,
+		previous: &sp_core::H256,
+	) -> Self {
+		let transcript = crate::vrf::transcript_data(slot, previous);
+		let signature =
+			keystore.sr25519_vrf_sign(key_type, author, transcript).await.ok().flatten();
+		Self(signature.map(|signature| VrfOutput {
+			output: signature.output.to_bytes(),
+			proof: signature.proof.to_bytes(),
+		}))
+	}
+}
+
+#[cfg(feature = "std")]
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+	async fn provide_inherent_data(
+		&self,
+		inherent_data: &mut sp_inherents::InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		match &self.0 {
+			Some(vrf) => inherent_data.put_data(INHERENT_IDENTIFIER, vrf),
+			None => Ok(()),
+		}
+	}
+
+	async fn try_handle_error(
+		&self,
+		_identifier: &InherentIdentifier,
+		_error: &[u8],
+	) -> Option<Result<(), sp_inherents::Error>> {
+		None
+	}
+}
@@ -0,0 +1,323 @@
+//! # Randomness Beacon Pallet
+//!
+//! Randomness produced by the block authors, replacing `pallet_randomness_collective_flip`.
+//!
+//! Each block author signs a VRF with its Aura key over the slot and the latest beacon output,
+//! and puts it in the block as the `set_vrf_output` inherent. The runtime checks the VRF against
+//! the author `T::SlotAuthor` names for the slot, then hashes its output with the previous beacon
+//! output into the next one. Every update is a round; the outputs of the last `HistoryDepth`
+//! rounds are kept.
+//!
+//! Nobody can compute an output before its block is authored, but the author can: it may
+//! withhold its block, or build it without the inherent, when it does not like the outcome.
+//! Requests guard against the rest of the world: a contract asks for the randomness of a round
+//! at least one round ahead through `request`, and it is delivered after that round through
+//! `fulfillment`. A contract has at most `MaxRequestsPerRequester` requests pending, so that one
+//! contract cannot take every request of the coming rounds. The latest output, also exposed
+//! through `Randomness`, is only as good as the current author's honesty.
+//!
+//! The inherent is optional, so blocks built by nodes that do not sign a VRF are valid; they just
+//! do not start a round.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+pub mod inherent;
+pub mod vrf;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::traits::Randomness;
+use scale_info::TypeInfo;
+use sp_core::{H160, H256};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{traits::Hash, RuntimeDebug};
+
+/// The author of a slot, whose key signs the slot's VRF.
+pub trait SlotAuthor {
+	/// The slot of the block being executed.
+	fn current_slot() -> u64;
+	/// The sr25519 public key of the author of `slot`.
+	fn author(slot: u64) -> Option<[u8; 32]>;
+}
+
+/// A VRF output and its proof, as produced by `CryptoStore::sr25519_vrf_sign`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct VrfOutput {
+	/// The VRF pre-output.
+	pub output: [u8; 32],
+	/// The proof that the author's key produced `output`.
+	pub proof: [u8; 64],
+}
+
+/// A beacon output.
+#[derive(
+	Clone, Copy, Default, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo,
+)]
+pub struct BeaconOutput<BlockNumber> {
+	/// Number of updates up to this one. Round 0 is the all-zero seed.
+	pub round: u64,
+	/// The randomness.
+	pub randomness: H256,
+	/// Block that produced it.
+	pub block: BlockNumber,
+}
+
+/// A contract's request for the randomness of a future round.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub struct Request {
+	/// The contract that asked, and is called back with the randomness.
+	pub requester: H160,
+	/// The round whose output the randomness is derived from.
+	pub round: u64,
+	/// Gas the requester is called back with.
+	pub gas_limit: u64,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{
+		dispatch::DispatchClass,
+		pallet_prelude::*,
+		weights::constants::WEIGHT_REF_TIME_PER_MILLIS,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_inherents::{InherentData, InherentIdentifier, MakeFatalError};
+
+	/// Upper bound of verifying a VRF in the runtime.
+	const VRF_VERIFICATION_WEIGHT: Weight = Weight::from_ref_time(5 * WEIGHT_REF_TIME_PER_MILLIS);
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Who signs the VRF of each slot.
+		type SlotAuthor: SlotAuthor;
+
+		/// Rounds a beacon output is kept, and a request can wait to be fulfilled, for.
+		#[pallet::constant]
+		type HistoryDepth: Get<u32>;
+
+		/// Most rounds a request can wait for.
+		#[pallet::constant]
+		type MaxRequestDelay: Get<u32>;
+
+		/// Most requests for a single round.
+		#[pallet::constant]
+		type MaxRequestsPerRound: Get<u32>;
+
+		/// Most requests a single contract can have pending.
+		#[pallet::constant]
+		type MaxRequestsPerRequester: Get<u32>;
+	}
+
+	/// The latest beacon output.
+	#[pallet::storage]
+	#[pallet::getter(fn latest)]
+	pub type Latest<T: Config> = StorageValue<_, BeaconOutput<T::BlockNumber>, ValueQuery>;
+
+	/// Outputs of the last `HistoryDepth` rounds, by round.
+	#[pallet::storage]
+	#[pallet::getter(fn output)]
+	pub type Outputs<T: Config> = StorageMap<_, Twox64Concat, u64, H256, OptionQuery>;
+
+	/// Whether the current block updated the beacon.
+	#[pallet::storage]
+	pub(super) type Updated<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Identifier of the next request.
+	#[pallet::storage]
+	pub(super) type NextRequestId<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Requests waiting to be fulfilled, by identifier.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_request)]
+	pub type Requests<T: Config> = StorageMap<_, Twox64Concat, u64, Request, OptionQuery>;
+
+	/// Identifiers of the requests for each round, so they expire with its output.
+	#[pallet::storage]
+	pub(super) type RoundRequests<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		u64,
+		BoundedVec<u64, T::MaxRequestsPerRound>,
+		ValueQuery,
+	>;
+
+	/// Number of pending requests of each contract.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_requests)]
+	pub type PendingRequests<T: Config> = StorageMap<_, Blake2_128Concat, H160, u32, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `requester` asked for the randomness of `round`.
+		RandomnessRequested { id: u64, requester: H160, round: u64 },
+		/// The randomness of request `id` was delivered.
+		RandomnessFulfilled { id: u64 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The block already updated the beacon.
+		AlreadyUpdated,
+		/// The VRF was not signed by the slot's author over the latest output.
+		InvalidVrf,
+		/// The delay is zero or above `MaxRequestDelay`.
+		InvalidDelay,
+		/// The round already has `MaxRequestsPerRound` requests.
+		TooManyRequests,
+		/// The requester already has `MaxRequestsPerRequester` requests pending.
+		TooManyPendingRequests,
+		/// No such request, or it expired.
+		UnknownRequest,
+		/// The request's round has not happened yet.
+		NotReady,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			// Clearing `Updated` in `on_finalize`.
+			T::DbWeight::get().writes(1)
+		}
+
+		fn on_finalize(_n: T::BlockNumber) {
+			Updated::<T>::kill();
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Start a new round with the block author's VRF.
+		#[pallet::call_index(0)]
+		#[pallet::weight((
+			VRF_VERIFICATION_WEIGHT.saturating_add(T::DbWeight::get().reads_writes(
+				6 + 2 * T::MaxRequestsPerRound::get() as u64,
+				5 + 2 * T::MaxRequestsPerRound::get() as u64,
+			)),
+			DispatchClass::Mandatory
+		))]
+		pub fn set_vrf_output(origin: OriginFor<T>, vrf: VrfOutput) -> DispatchResult {
+			ensure_none(origin)?;
+			ensure!(!Updated::<T>::get(), Error::<T>::AlreadyUpdated);
+			let randomness = Self::verify(&vrf).ok_or(Error::<T>::InvalidVrf)?;
+
+			let round = Latest::<T>::get().round.saturating_add(1);
+			let block = frame_system::Pallet::<T>::block_number();
+			Latest::<T>::put(BeaconOutput { round, randomness, block });
+			Outputs::<T>::insert(round, randomness);
+			Updated::<T>::put(true);
+
+			if let Some(expired) = round.checked_sub(T::HistoryDepth::get().into()) {
+				Outputs::<T>::remove(expired);
+				for id in RoundRequests::<T>::take(expired) {
+					if let Some(request) = Requests::<T>::take(id) {
+						Self::release(request.requester);
+					}
+				}
+			}
+			Ok(())
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = MakeFatalError<()>;
+		const INHERENT_IDENTIFIER: InherentIdentifier = inherent::INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			let vrf: VrfOutput = data.get_data(&Self::INHERENT_IDENTIFIER).ok()??;
+			// A VRF the runtime would reject, e.g. one signed before the authority set changed,
+			// is left out rather than making the block invalid.
+			Self::verify(&vrf)?;
+			Some(Call::set_vrf_output { vrf })
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::set_vrf_output { .. })
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Ask for the randomness of the round `delay` rounds after the current one, to be
+		/// delivered with `gas_limit` gas.
+		///
+		/// Returns the identifier of the request.
+		pub fn request(requester: H160, delay: u32, gas_limit: u64) -> Result<u64, DispatchError> {
+			ensure!(delay > 0 && delay <= T::MaxRequestDelay::get(), Error::<T>::InvalidDelay);
+			let pending = PendingRequests::<T>::get(requester);
+			ensure!(
+				pending < T::MaxRequestsPerRequester::get(),
+				Error::<T>::TooManyPendingRequests
+			);
+
+			let id = NextRequestId::<T>::get();
+			let round = Latest::<T>::get().round.saturating_add(delay.into());
+			RoundRequests::<T>::try_mutate(round, |ids| ids.try_push(id))
+				.map_err(|_| Error::<T>::TooManyRequests)?;
+			PendingRequests::<T>::insert(requester, pending + 1);
+			Requests::<T>::insert(id, Request { requester, round, gas_limit });
+			NextRequestId::<T>::put(id.wrapping_add(1));
+
+			Self::deposit_event(Event::RandomnessRequested { id, requester, round });
+			Ok(id)
+		}
+
+		/// Request `id` and the randomness it gets, once its round is out.
+		///
+		/// Requests in the same round get different randomness.
+		pub fn fulfillment(id: u64) -> Result<(Request, H256), Error<T>> {
+			let request = Requests::<T>::get(id).ok_or(Error::<T>::UnknownRequest)?;
+			let output = Outputs::<T>::get(request.round).ok_or(Error::<T>::NotReady)?;
+			Ok((request, H256(blake2_256(&(output, id).encode()))))
+		}
+
+		/// Remove request `id` once its randomness was delivered.
+		pub fn complete(id: u64) {
+			if let Some(request) = Requests::<T>::take(id) {
+				Self::release(request.requester);
+				Self::deposit_event(Event::RandomnessFulfilled { id });
+			}
+		}
+
+		/// Count one request of `requester` as no longer pending.
+		fn release(requester: H160) {
+			PendingRequests::<T>::mutate_exists(requester, |pending| {
+				*pending =
+					pending.map(|pending| pending.saturating_sub(1)).filter(|pending| *pending > 0);
+			});
+		}
+
+		/// The key that signs the VRF of `slot` and the beacon output it extends.
+		pub fn vrf_input(slot: u64) -> Option<([u8; 32], H256)> {
+			Some((T::SlotAuthor::author(slot)?, Latest::<T>::get().randomness))
+		}
+
+		/// The next beacon output, if `vrf` is the current slot author's VRF over the latest one.
+		fn verify(vrf: &VrfOutput) -> Option<H256> {
+			let slot = T::SlotAuthor::current_slot();
+			let previous = Latest::<T>::get().randomness;
+			let proven = vrf::verify(&T::SlotAuthor::author(slot)?, slot, &previous, vrf)?;
+			Some(H256(blake2_256(&(previous, proven).encode())))
+		}
+	}
+}
+
+/// The latest beacon output mixed with `subject`, and the block that produced it.
+///
+/// The block's author could compute the output before anyone else; consumers that need more
+/// should go through [`Pallet::request`].
+impl<T: Config> Randomness<T::Hash, T::BlockNumber> for Pallet<T> {
+	fn random(subject: &[u8]) -> (T::Hash, T::BlockNumber) {
+		let latest = Latest::<T>::get();
+		(T::Hashing::hash(&(latest.randomness, subject).encode()), latest.block)
+	}
+}
@@ -0,0 +1,54 @@
+//! The VRF block authors sign, and its verification.
+//!
+//! The input of a slot's VRF is the slot and the latest beacon output, so an author can neither
+//! choose it nor compute it for slots it does not own. [`transcript`] is what the runtime
+//! verifies against; [`transcript_data`] is the same transcript in the form the keystore signs.
+
+use crate::VrfOutput;
+use schnorrkel::{
+	vrf::{VRFOutput, VRFProof},
+	PublicKey,
+};
+use sp_core::H256;
+
+/// Domain separation label of the transcript.
+pub const VRF_LABEL: &[u8] = b"GN randomness beacon";
+
+/// Context the VRF output bytes are derived under.
+pub const VRF_INOUT_CONTEXT: &[u8] = b"GN randomness beacon output";
+
+/// The transcript of the VRF of `slot`, extending the beacon output `previous`.
+pub fn transcript(slot: u64, previous: &H256) -> merlin::Transcript {
+	let mut transcript = merlin::Transcript::new(VRF_LABEL);
+	transcript.append_u64(b"slot", slot);
+	transcript.append_message(b"previous randomness", previous.as_bytes());
+	transcript
+}
+
+/// [`transcript`], for `CryptoStore::sr25519_vrf_sign`.
+#[cfg(feature = "std")]
+pub fn transcript_data(slot: u64, previous: &H256) -> sp_keystore::vrf::VRFTranscriptData {
+	use sp_keystore::vrf::{VRFTranscriptData, VRFTranscriptValue};
+
+	VRFTranscriptData {
+		label: VRF_LABEL,
+		items: vec![
+			("slot", VRFTranscriptValue::U64(slot)),
+			("previous randomness", VRFTranscriptValue::Bytes(previous.as_bytes().to_vec())),
+		],
+	}
+}
+
+/// The bytes `vrf` proves, if `public` signed it over the transcript of `slot` and `previous`.
+pub fn verify(
+	public: &[u8; 32],
+	slot: u64,
+	previous: &H256,
+	vrf: &VrfOutput,
+) -> Option<[u8; 32]> {
+	let public = PublicKey::from_bytes(public).ok()?;
+	let output = VRFOutput::from_bytes(&vrf.output).ok()?;
+	let proof = VRFProof::from_bytes(&vrf.proof).ok()?;
+	let (inout, _) = public.vrf_verify(transcript(slot, previous), &output, &proof).ok()?;
+	Some(inout.make_bytes(VRF_INOUT_CONTEXT))
+}
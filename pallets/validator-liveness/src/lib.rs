@@ -0,0 +1,275 @@
+//! # Validator Liveness Pallet
+//!
+//! Sits between `pallet_im_online` and `validator_set`. Unresponsiveness reports are counted
+//! per validator instead of being forwarded straight away, and a validator is only removed
+//! once it has missed `MissesBeforeRemoval` consecutive sessions. Every removal is recorded
+//! with its reason until the validator is reinstated.
+//!
+//! The session keys of a removed validator are purged along with it. A removed validator can
+//! come back in two ways:
+//! - `reinstate`, signed by the validator account itself once `SessionsBeforeReinstate` sessions
+//!   have passed and it has set new session keys;
+//! - `force_reinstate`, dispatched by `ReinstateOrigin` (root or the council).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::dispatch::DispatchResult;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_staking::{
+	offence::{Offence, OffenceError, ReportOffence},
+	SessionIndex,
+};
+use sp_std::vec::Vec;
+
+const LOG_TARGET: &str = "runtime::validator-liveness";
+
+/// Why a validator was taken out of the set.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum RemovalReason {
+	/// No `ImOnline` heartbeat for `misses` consecutive sessions.
+	Unresponsive { misses: u32 },
+}
+
+/// A removal that has not been undone yet.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RemovalRecord {
+	/// Session in which the last missed heartbeat was reported.
+	pub session: SessionIndex,
+	/// Why the validator was removed.
+	pub reason: RemovalReason,
+}
+
+/// Adds and removes validators on behalf of this pallet.
+pub trait ValidatorSetManager<AccountId> {
+	/// Put `who` back into the validator set.
+	fn add(who: AccountId) -> DispatchResult;
+	/// Take `who` out of the validator set.
+	fn remove(who: AccountId) -> DispatchResult;
+	/// Drop the session keys of `who`, so that it has to set new ones before it is reinstated.
+	fn purge_keys(who: AccountId) -> DispatchResult;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{pallet_prelude::*, traits::ValidatorRegistration};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Where validators are actually added and removed.
+		type ValidatorSet: ValidatorSetManager<Self::AccountId>;
+
+		/// Tells whether a validator has session keys registered.
+		type ValidatorRegistration: ValidatorRegistration<Self::AccountId>;
+
+		/// Origin allowed to reinstate any validator and to change the removal threshold.
+		type ReinstateOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The current session index.
+		type CurrentSession: Get<SessionIndex>;
+
+		/// Sessions a removed validator has to wait, counted from the session of its removal,
+		/// before it can reinstate itself.
+		#[pallet::constant]
+		type SessionsBeforeReinstate: Get<SessionIndex>;
+
+		/// Consecutive missed sessions before removal, until governance sets another value.
+		#[pallet::constant]
+		type DefaultMissesBeforeRemoval: Get<u32>;
+	}
+
+	#[pallet::type_value]
+	pub fn OnEmptyMissesBeforeRemoval<T: Config>() -> u32 {
+		T::DefaultMissesBeforeRemoval::get()
+	}
+
+	/// Consecutive missed sessions before a validator is removed. Zero disables removal.
+	#[pallet::storage]
+	#[pallet::getter(fn misses_before_removal)]
+	pub type MissesBeforeRemoval<T: Config> =
+		StorageValue<_, u32, ValueQuery, OnEmptyMissesBeforeRemoval<T>>;
+
+	/// Last session a validator missed and how many sessions in a row it has missed.
+	#[pallet::storage]
+	#[pallet::getter(fn misses)]
+	pub type Misses<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (SessionIndex, u32), OptionQuery>;
+
+	/// Validators removed by this pallet and not reinstated yet.
+	#[pallet::storage]
+	#[pallet::getter(fn removals)]
+	pub type Removals<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, RemovalRecord, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A validator missed its heartbeat in `session`.
+		HeartbeatMissed { validator: T::AccountId, session: SessionIndex, misses: u32 },
+		/// A validator was removed from the validator set.
+		ValidatorRemoved { validator: T::AccountId, session: SessionIndex, reason: RemovalReason },
+		/// A removed validator was added back. `forced` is set for governance reinstatements.
+		ValidatorReinstated { validator: T::AccountId, forced: bool },
+		/// The removal threshold was changed.
+		MissesBeforeRemovalSet { misses: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account was not removed by this pallet.
+		NotRemoved,
+		/// The validator has to set new session keys before asking to be reinstated.
+		SessionKeysNotSet,
+		/// `SessionsBeforeReinstate` sessions have not passed since the removal yet.
+		TooEarly,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Ask to be put back into the validator set after an unresponsiveness removal.
+		///
+		/// Session keys are purged on removal, so the validator must have set new ones since,
+		/// which its operator does with `author_rotateKeys` on a running node. The call also
+		/// has to wait until `SessionsBeforeReinstate` sessions have passed since the removal.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(4, 3))]
+		pub fn reinstate(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let record = Removals::<T>::get(&who).ok_or(Error::<T>::NotRemoved)?;
+			let earliest = record.session.saturating_add(T::SessionsBeforeReinstate::get());
+			ensure!(T::CurrentSession::get() >= earliest, Error::<T>::TooEarly);
+			ensure!(T::ValidatorRegistration::is_registered(&who), Error::<T>::SessionKeysNotSet);
+
+			Self::do_reinstate(who, false)
+		}
+
+		/// Put a removed validator back into the validator set.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 3))]
+		pub fn force_reinstate(origin: OriginFor<T>, validator: T::AccountId) -> DispatchResult {
+			T::ReinstateOrigin::ensure_origin(origin)?;
+			ensure!(Removals::<T>::contains_key(&validator), Error::<T>::NotRemoved);
+
+			Self::do_reinstate(validator, true)
+		}
+
+		/// Set how many consecutive sessions a validator may miss before it is removed.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_misses_before_removal(origin: OriginFor<T>, misses: u32) -> DispatchResult {
+			T::ReinstateOrigin::ensure_origin(origin)?;
+
+			MissesBeforeRemoval::<T>::put(misses);
+			Self::deposit_event(Event::MissesBeforeRemovalSet { misses });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// All validators that are currently removed, with the reason.
+	pub fn removed_validators() -> Vec<(T::AccountId, RemovalRecord)> {
+		Removals::<T>::iter().collect()
+	}
+
+	fn do_reinstate(validator: T::AccountId, forced: bool) -> DispatchResult {
+		T::ValidatorSet::add(validator.clone())?;
+		Removals::<T>::remove(&validator);
+		Misses::<T>::remove(&validator);
+
+		Self::deposit_event(Event::ValidatorReinstated { validator, forced });
+		Ok(())
+	}
+
+	fn note_missed_session(validator: T::AccountId, session: SessionIndex) {
+		if Removals::<T>::contains_key(&validator) {
+			return
+		}
+
+		let misses = match Misses::<T>::get(&validator) {
+			Some((last, count)) if last.saturating_add(1) == session => count.saturating_add(1),
+			_ => 1,
+		};
+		Self::deposit_event(Event::HeartbeatMissed {
+			validator: validator.clone(),
+			session,
+			misses,
+		});
+
+		let threshold = MissesBeforeRemoval::<T>::get();
+		if threshold == 0 || misses < threshold {
+			Misses::<T>::insert(&validator, (session, misses));
+			return
+		}
+
+		match T::ValidatorSet::remove(validator.clone()) {
+			Ok(()) => {
+				Misses::<T>::remove(&validator);
+				if let Err(e) = T::ValidatorSet::purge_keys(validator.clone()) {
+					log::warn!(
+						target: LOG_TARGET,
+						"could not purge the session keys of {:?}: {:?}",
+						validator,
+						e,
+					);
+				}
+				let record =
+					RemovalRecord { session, reason: RemovalReason::Unresponsive { misses } };
+				Removals::<T>::insert(&validator, record);
+				Self::deposit_event(Event::ValidatorRemoved {
+					validator,
+					session,
+					reason: record.reason,
+				});
+			},
+			Err(e) => {
+				// Keep counting, the next miss tries again.
+				Misses::<T>::insert(&validator, (session, misses));
+				log::warn!(
+					target: LOG_TARGET,
+					"could not remove unresponsive validator {:?}: {:?}",
+					validator,
+					e,
+				)
+			},
+		}
+	}
+}
+
+impl<T, I, O> ReportOffence<T::AccountId, (T::AccountId, I), O> for Pallet<T>
+where
+	T: Config,
+	O: Offence<(T::AccountId, I)>,
+{
+	fn report_offence(_reporters: Vec<T::AccountId>, offence: O) -> Result<(), OffenceError> {
+		let session = offence.session_index();
+		for (validator, _) in offence.offenders() {
+			Self::note_missed_session(validator, session);
+		}
+		Ok(())
+	}
+
+	fn is_known_offence(_offenders: &[(T::AccountId, I)], _time_slot: &O::TimeSlot) -> bool {
+		false
+	}
+}
@@ -0,0 +1,184 @@
+use crate as pallet_validator_liveness;
+use crate::ValidatorSetManager;
+use frame_support::{
+	dispatch::DispatchResult,
+	parameter_types,
+	traits::{ConstU32, ConstU64, Get, ValidatorRegistration},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+use sp_staking::{
+	offence::{Kind, Offence},
+	SessionIndex,
+};
+use std::{cell::RefCell, collections::BTreeSet};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		ValidatorLiveness: pallet_validator_liveness,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<u64>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+thread_local! {
+	pub static VALIDATORS: RefCell<BTreeSet<u64>> = RefCell::new([1, 2, 3].into());
+	pub static REGISTERED: RefCell<BTreeSet<u64>> = RefCell::new([1, 2, 3].into());
+	pub static SESSION: RefCell<SessionIndex> = RefCell::new(0);
+}
+
+/// The validator set, as seen by the pallet.
+pub struct MockValidatorSet;
+
+impl ValidatorSetManager<u64> for MockValidatorSet {
+	fn add(who: u64) -> DispatchResult {
+		VALIDATORS.with(|v| v.borrow_mut().insert(who));
+		Ok(())
+	}
+
+	fn remove(who: u64) -> DispatchResult {
+		VALIDATORS.with(|v| {
+			let mut validators = v.borrow_mut();
+			if validators.len() <= 1 {
+				return Err(DispatchError::Other("too few validators"))
+			}
+			validators.remove(&who);
+			Ok(())
+		})
+	}
+
+	fn purge_keys(who: u64) -> DispatchResult {
+		set_registered(who, false);
+		Ok(())
+	}
+}
+
+/// Validators with session keys, as seen by the pallet.
+pub struct MockRegistration;
+
+impl ValidatorRegistration<u64> for MockRegistration {
+	fn is_registered(who: &u64) -> bool {
+		REGISTERED.with(|r| r.borrow().contains(who))
+	}
+}
+
+/// The current session, as seen by the pallet.
+pub struct MockSession;
+
+impl Get<SessionIndex> for MockSession {
+	fn get() -> SessionIndex {
+		SESSION.with(|s| *s.borrow())
+	}
+}
+
+parameter_types! {
+	pub const DefaultMissesBeforeRemoval: u32 = 2;
+}
+
+impl pallet_validator_liveness::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type ValidatorSet = MockValidatorSet;
+	type ValidatorRegistration = MockRegistration;
+	type ReinstateOrigin = EnsureRoot<u64>;
+	type CurrentSession = MockSession;
+	type SessionsBeforeReinstate = ConstU32<2>;
+	type DefaultMissesBeforeRemoval = DefaultMissesBeforeRemoval;
+}
+
+/// An `ImOnline`-like unresponsiveness offence.
+pub struct Unresponsive {
+	pub session: SessionIndex,
+	pub offenders: Vec<(u64, ())>,
+}
+
+impl Offence<(u64, ())> for Unresponsive {
+	const ID: Kind = *b"test:unresponsiv";
+	type TimeSlot = SessionIndex;
+
+	fn offenders(&self) -> Vec<(u64, ())> {
+		self.offenders.clone()
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		validators().len() as u32
+	}
+
+	fn time_slot(&self) -> SessionIndex {
+		self.session
+	}
+
+	fn slash_fraction(&self, _offenders_count: u32) -> Perbill {
+		Perbill::zero()
+	}
+}
+
+pub fn validators() -> Vec<u64> {
+	VALIDATORS.with(|v| v.borrow().iter().copied().collect())
+}
+
+pub fn set_registered(who: u64, registered: bool) {
+	REGISTERED.with(|r| {
+		if registered {
+			r.borrow_mut().insert(who)
+		} else {
+			r.borrow_mut().remove(&who)
+		}
+	});
+}
+
+pub fn set_session(session: SessionIndex) {
+	SESSION.with(|s| *s.borrow_mut() = session);
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	VALIDATORS.with(|v| *v.borrow_mut() = [1, 2, 3].into());
+	REGISTERED.with(|r| *r.borrow_mut() = [1, 2, 3].into());
+	set_session(0);
+
+	let storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,138 @@
+use crate::{mock::*, Error, Event, RemovalReason, RemovalRecord};
+use frame_support::{assert_noop, assert_ok, traits::ValidatorRegistration};
+use sp_runtime::DispatchError;
+use sp_staking::{offence::ReportOffence, SessionIndex};
+
+fn miss(session: SessionIndex, offenders: &[u64]) {
+	let offence = Unresponsive { session, offenders: offenders.iter().map(|v| (*v, ())).collect() };
+	assert_ok!(ValidatorLiveness::report_offence(vec![], offence));
+}
+
+#[test]
+fn removes_after_consecutive_misses_only() {
+	new_test_ext().execute_with(|| {
+		miss(1, &[1]);
+		assert_eq!(ValidatorLiveness::misses(1), Some((1, 1)));
+
+		// Not consecutive: the count starts over.
+		miss(3, &[1]);
+		assert_eq!(ValidatorLiveness::misses(1), Some((3, 1)));
+		assert_eq!(validators(), vec![1, 2, 3]);
+
+		miss(4, &[1]);
+		assert_eq!(validators(), vec![2, 3]);
+		assert_eq!(ValidatorLiveness::misses(1), None);
+		let record =
+			RemovalRecord { session: 4, reason: RemovalReason::Unresponsive { misses: 2 } };
+		assert_eq!(ValidatorLiveness::removals(1), Some(record));
+		assert_eq!(ValidatorLiveness::removed_validators(), vec![(1, record)]);
+		System::assert_last_event(
+			Event::ValidatorRemoved { validator: 1, session: 4, reason: record.reason }.into(),
+		);
+
+		// Further reports about a removed validator are ignored.
+		miss(5, &[1]);
+		assert_eq!(ValidatorLiveness::misses(1), None);
+	});
+}
+
+#[test]
+fn zero_threshold_disables_removal() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(ValidatorLiveness::set_misses_before_removal(RuntimeOrigin::root(), 0));
+		System::assert_last_event(Event::MissesBeforeRemovalSet { misses: 0 }.into());
+
+		for session in 1..10 {
+			miss(session, &[2]);
+		}
+		assert_eq!(validators(), vec![1, 2, 3]);
+		assert_eq!(ValidatorLiveness::misses(2), Some((9, 9)));
+	});
+}
+
+#[test]
+fn failed_removal_is_not_recorded() {
+	new_test_ext().execute_with(|| {
+		miss(1, &[1, 2]);
+		miss(2, &[1, 2]);
+		assert_eq!(validators(), vec![3]);
+
+		miss(1, &[3]);
+		miss(2, &[3]);
+		assert_eq!(validators(), vec![3]);
+		assert_eq!(ValidatorLiveness::removals(3), None);
+		// The misses are kept, so the next one tries again.
+		assert_eq!(ValidatorLiveness::misses(3), Some((2, 2)));
+		miss(3, &[3]);
+		assert_eq!(ValidatorLiveness::misses(3), Some((3, 3)));
+	});
+}
+
+#[test]
+fn removal_purges_session_keys() {
+	new_test_ext().execute_with(|| {
+		miss(1, &[1]);
+		assert!(MockRegistration::is_registered(&1));
+		miss(2, &[1]);
+		assert!(!MockRegistration::is_registered(&1));
+		assert!(MockRegistration::is_registered(&2));
+	});
+}
+
+#[test]
+fn reinstate_requires_removal_cooldown_and_new_session_keys() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			ValidatorLiveness::reinstate(RuntimeOrigin::signed(1)),
+			Error::<Test>::NotRemoved
+		);
+
+		miss(1, &[1]);
+		miss(2, &[1]);
+
+		// The session keys in place before the removal were purged.
+		set_session(4);
+		assert_noop!(
+			ValidatorLiveness::reinstate(RuntimeOrigin::signed(1)),
+			Error::<Test>::SessionKeysNotSet
+		);
+
+		// Removed in session 2, so not before session 4 whatever the keys.
+		set_registered(1, true);
+		set_session(3);
+		assert_noop!(
+			ValidatorLiveness::reinstate(RuntimeOrigin::signed(1)),
+			Error::<Test>::TooEarly
+		);
+
+		set_session(4);
+		assert_ok!(ValidatorLiveness::reinstate(RuntimeOrigin::signed(1)));
+		assert_eq!(validators(), vec![1, 2, 3]);
+		assert_eq!(ValidatorLiveness::removals(1), None);
+		System::assert_last_event(
+			Event::ValidatorReinstated { validator: 1, forced: false }.into(),
+		);
+	});
+}
+
+#[test]
+fn force_reinstate_is_root_only() {
+	new_test_ext().execute_with(|| {
+		miss(1, &[2]);
+		miss(2, &[2]);
+		set_registered(2, false);
+
+		assert_noop!(
+			ValidatorLiveness::force_reinstate(RuntimeOrigin::signed(1), 2),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			ValidatorLiveness::force_reinstate(RuntimeOrigin::root(), 3),
+			Error::<Test>::NotRemoved
+		);
+
+		assert_ok!(ValidatorLiveness::force_reinstate(RuntimeOrigin::root(), 2));
+		assert_eq!(validators(), vec![1, 2, 3]);
+		System::assert_last_event(Event::ValidatorReinstated { validator: 2, forced: true }.into());
+	});
+}
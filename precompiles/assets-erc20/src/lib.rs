@@ -0,0 +1,378 @@
+//! ERC-20 interface over the assets of `pallet_assets`.
+//!
+//! Every asset has its own address: [`ASSET_ADDRESS_PREFIX`] followed by its id as a
+//! big-endian `u128`, so asset 1 is at `0xffffffff00000000000000000000000000000001`. Balances,
+//! supply and metadata are the asset's own. Allowances are approvals of `pallet_assets`: the first
+//! approval of a spender reserves the pallet's approval deposit from the owner, and allowances are
+//! removed with the asset when it is destroyed.
+//!
+//! On top of ERC-20 the precompile implements EIP-2612 `permit`, signed over the domain
+//! `(name, "1", chainId, assetAddress)`. Permit nonces are kept by the precompile, by asset
+//! address, and outlive the asset: a permit signed for a destroyed asset cannot be replayed on
+//! an asset created again at the same address.
+//!
+//! `Transfer` and `Approval` are only emitted for transfers and approvals made through the
+//! precompile, not for those made with `pallet_assets` extrinsics. Asset addresses hold no code:
+//! contracts built with solc 0.8.10 or later call them like any token, since calls that return
+//! data skip the `extcodesize` check. The precompile acts for its caller, so the runtime must not
+//! let it be reached through DELEGATECALL or CALLCODE.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_evm::{PrecompileFailure, PrecompileHandle, PrecompileResult};
+use frame_support::{
+	pallet_prelude::{Blake2_128Concat, ValueQuery},
+	sp_runtime::traits::{Bounded, StaticLookup, Zero},
+	traits::{
+		fungibles::{
+			approvals::{Inspect as InspectApprovals, Mutate as MutateApprovals},
+			metadata::Inspect as InspectMetadata,
+			Inspect, Transfer,
+		},
+		Get,
+	},
+};
+use frame_system::RawOrigin;
+use hex_literal::hex;
+use pallet_evm::AddressMapping;
+use precompile_utils::{
+	db_read_cost, db_write_cost, encode_address, encode_bool, encode_string, encode_u256,
+	ensure_mutable, ensure_not_payable, log, revert, succeed, Arguments,
+};
+use sp_core::{H160, H256, U256};
+use sp_io::hashing::keccak_256;
+use sp_std::{marker::PhantomData, vec, vec::Vec};
+
+/// First bytes of the address of every asset.
+pub const ASSET_ADDRESS_PREFIX: [u8; 4] = [0xff; 4];
+
+mod selector {
+	pub const NAME: [u8; 4] = [0x06, 0xfd, 0xde, 0x03];
+	pub const SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41];
+	pub const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+	pub const TOTAL_SUPPLY: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+	pub const BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+	pub const ALLOWANCE: [u8; 4] = [0xdd, 0x62, 0xed, 0x3e];
+	pub const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+	pub const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+	pub const TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+	pub const PERMIT: [u8; 4] = [0xd5, 0x05, 0xac, 0xcf];
+	pub const NONCES: [u8; 4] = [0x7e, 0xce, 0xbe, 0x00];
+	pub const DOMAIN_SEPARATOR: [u8; 4] = [0x36, 0x44, 0xe5, 0x15];
+}
+
+/// `Transfer(address,address,uint256)`
+const TRANSFER_TOPIC: H256 =
+	H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+/// `Approval(address,address,uint256)`
+const APPROVAL_TOPIC: H256 =
+	H256(hex!("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925"));
+/// `EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)`
+const DOMAIN_TYPEHASH: [u8; 32] =
+	hex!("8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f");
+/// `Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)`
+const PERMIT_TYPEHASH: [u8; 32] =
+	hex!("6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9");
+/// Hash of the domain version, "1".
+const VERSION_HASH: [u8; 32] =
+	hex!("c89efdaa54c0f20c7adf612882df0950f5a951637e0307cdcb4c672f298b8bc6");
+
+/// EIP-2612 nonces, by asset address then owner.
+#[frame_support::storage_alias]
+type Nonces =
+	StorageDoubleMap<AssetsErc20, Blake2_128Concat, H160, Blake2_128Concat, H160, U256, ValueQuery>;
+
+/// Asset id type of runtime `R`.
+pub type AssetIdOf<R> = <R as pallet_assets::Config>::AssetId;
+
+/// Asset balance type of runtime `R`.
+pub type AssetBalanceOf<R> = <R as pallet_assets::Config>::Balance;
+
+type Assets<R> = pallet_assets::Pallet<R>;
+
+/// The address of asset `id`.
+pub fn asset_address(id: u128) -> H160 {
+	let mut address = [0u8; 20];
+	address[..4].copy_from_slice(&ASSET_ADDRESS_PREFIX);
+	address[4..].copy_from_slice(&id.to_be_bytes());
+	H160(address)
+}
+
+/// ERC-20 precompile over the assets of runtime `R`.
+pub struct AssetsErc20Precompile<R>(PhantomData<R>);
+
+impl<R> AssetsErc20Precompile<R>
+where
+	R: pallet_evm::Config + pallet_assets::Config + pallet_timestamp::Config,
+	AssetIdOf<R>: TryFrom<u128>,
+	AssetBalanceOf<R>: TryFrom<U256> + Into<U256>,
+	R::Moment: Into<U256>,
+{
+	/// The asset at `address`, if the address has the asset prefix and the asset exists.
+	pub fn asset_at(address: H160) -> Option<AssetIdOf<R>> {
+		let (prefix, id) = address.as_bytes().split_at(4);
+		if prefix != ASSET_ADDRESS_PREFIX {
+			return None
+		}
+		let mut bytes = [0u8; 16];
+		bytes.copy_from_slice(id);
+		let asset = AssetIdOf::<R>::try_from(u128::from_be_bytes(bytes)).ok()?;
+
+		// Assets are created with a non-zero minimum balance, missing ones report zero.
+		let minimum_balance: U256 = Assets::<R>::minimum_balance(asset).into();
+		(!minimum_balance.is_zero()).then_some(asset)
+	}
+
+	/// Run the call in `handle` against `asset`, the asset at the code address.
+	pub fn execute(handle: &mut impl PrecompileHandle, asset: AssetIdOf<R>) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("tried to parse selector out of bounds"))
+		}
+		let (selector, args) = input.split_at(4);
+		let args = Arguments(args);
+
+		match [selector[0], selector[1], selector[2], selector[3]] {
+			selector::NAME => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_string(&Assets::<R>::name(asset)))
+			},
+			selector::SYMBOL => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_string(&Assets::<R>::symbol(asset)))
+			},
+			selector::DECIMALS => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(Assets::<R>::decimals(asset).into()))
+			},
+			selector::TOTAL_SUPPLY => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(Assets::<R>::total_issuance(asset).into()))
+			},
+			selector::BALANCE_OF => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let owner = R::AddressMapping::into_account_id(args.address(0)?);
+				succeed(encode_u256(Assets::<R>::balance(asset, &owner).into()))
+			},
+			selector::ALLOWANCE => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let (owner, spender) = (args.address(0)?, args.address(1)?);
+				succeed(encode_u256(Self::allowance(asset, owner, spender).into()))
+			},
+			selector::NONCES => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(Nonces::get(handle.context().address, args.address(0)?)))
+			},
+			selector::DOMAIN_SEPARATOR => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(Self::domain_separator(handle.context().address, asset).to_vec())
+			},
+			selector::TRANSFER => Self::transfer(handle, asset, args),
+			selector::APPROVE => Self::approve(handle, asset, args),
+			selector::TRANSFER_FROM => Self::transfer_from(handle, asset, args),
+			selector::PERMIT => Self::permit(handle, asset, args),
+			_ => Err(revert("unknown selector")),
+		}
+	}
+
+	fn transfer(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		args: Arguments,
+	) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let to = args.address(0)?;
+		let amount = args.u256(1)?;
+
+		let from = handle.context().caller;
+		Self::move_balance(handle, asset, from, to, amount)?;
+		succeed(encode_bool(true))
+	}
+
+	fn approve(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		args: Arguments,
+	) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let spender = args.address(0)?;
+		let amount = args.u256(1)?;
+
+		let owner = handle.context().caller;
+		Self::set_allowance(handle, asset, owner, spender, amount)?;
+		succeed(encode_bool(true))
+	}
+
+	fn transfer_from(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		args: Arguments,
+	) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let from = args.address(0)?;
+		let to = args.address(1)?;
+		let amount = args.u256(2)?;
+
+		let spender = handle.context().caller;
+		if spender == from {
+			Self::move_balance(handle, asset, from, to, amount)?;
+			return succeed(encode_bool(true))
+		}
+
+		handle.record_cost(db_read_cost::<R>())?;
+		let allowed: U256 = Self::allowance(asset, from, spender).into();
+		if allowed < amount {
+			return Err(revert("trying to spend more than allowed"))
+		}
+		// The transfer, then the approval spent down and its deposit returned once spent.
+		handle.record_cost(4 * db_read_cost::<R>() + 4 * db_write_cost::<R>())?;
+		let amount_balance = amount.try_into().map_err(|_| revert("amount is too large"))?;
+		<Assets<R> as MutateApprovals<R::AccountId>>::transfer_from(
+			asset,
+			&R::AddressMapping::into_account_id(from),
+			&R::AddressMapping::into_account_id(spender),
+			&R::AddressMapping::into_account_id(to),
+			amount_balance,
+		)
+		.map_err(|e| revert(e.into()))?;
+
+		log(handle, vec![TRANSFER_TOPIC, from.into(), to.into()], amount)?;
+		succeed(encode_bool(true))
+	}
+
+	/// EIP-2612: approve `spender` with `owner`'s signature instead of a call from `owner`.
+	fn permit(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		args: Arguments,
+	) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let owner = args.address(0)?;
+		let spender = args.address(1)?;
+		let amount = args.u256(2)?;
+		let deadline = args.u256(3)?;
+		let v = args.u256(4)?;
+		let (r, s) = (args.word(5)?, args.word(6)?);
+
+		handle.record_cost(db_read_cost::<R>())?;
+		// Timestamps are in milliseconds, deadlines in seconds.
+		let now: U256 = pallet_timestamp::Pallet::<R>::get().into();
+		if deadline < now / U256::from(1000) {
+			return Err(revert("permit expired"))
+		}
+
+		let this = handle.context().address;
+		handle.record_cost(2 * db_read_cost::<R>() + db_write_cost::<R>())?;
+		let nonce = Nonces::get(this, owner);
+		let mut permit = PERMIT_TYPEHASH.to_vec();
+		permit.extend(encode_address(owner));
+		permit.extend(encode_address(spender));
+		permit.extend(encode_u256(amount));
+		permit.extend(encode_u256(nonce));
+		permit.extend(encode_u256(deadline));
+		let domain_separator = Self::domain_separator(this, asset);
+		let digest =
+			keccak_256(&[&b"\x19\x01"[..], &domain_separator, &keccak_256(&permit)].concat());
+
+		if v != 27.into() && v != 28.into() {
+			return Err(revert("invalid permit"))
+		}
+		let mut signature = [0u8; 65];
+		signature[..32].copy_from_slice(r);
+		signature[32..64].copy_from_slice(s);
+		signature[64] = v.low_u32() as u8 - 27;
+		// ECRecover precompile cost.
+		handle.record_cost(3000)?;
+		let signer = sp_io::crypto::secp256k1_ecdsa_recover(&signature, &digest)
+			.map(|public| H160::from_slice(&keccak_256(&public)[12..]))
+			.map_err(|_| revert("invalid permit"))?;
+		if signer != owner {
+			return Err(revert("invalid permit"))
+		}
+
+		Nonces::insert(this, owner, nonce.saturating_add(U256::one()));
+		Self::set_allowance(handle, asset, owner, spender, amount)?;
+		succeed(Vec::new())
+	}
+
+	fn domain_separator(this: H160, asset: AssetIdOf<R>) -> [u8; 32] {
+		let mut domain = DOMAIN_TYPEHASH.to_vec();
+		domain.extend(keccak_256(&Assets::<R>::name(asset)));
+		domain.extend(VERSION_HASH);
+		domain.extend(encode_u256(R::ChainId::get().into()));
+		domain.extend(encode_address(this));
+		keccak_256(&domain)
+	}
+
+	fn allowance(asset: AssetIdOf<R>, owner: H160, spender: H160) -> AssetBalanceOf<R> {
+		<Assets<R> as InspectApprovals<R::AccountId>>::allowance(
+			asset,
+			&R::AddressMapping::into_account_id(owner),
+			&R::AddressMapping::into_account_id(spender),
+		)
+	}
+
+	/// Replace the approval of `spender` by `owner` with one of `amount`, capped to the largest
+	/// balance. `pallet_assets` adds approvals up, so an existing one is cancelled first.
+	fn set_allowance(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		owner: H160,
+		spender: H160,
+		amount: U256,
+	) -> Result<(), PrecompileFailure> {
+		let amount = AssetBalanceOf::<R>::try_from(amount).unwrap_or_else(|_| Bounded::max_value());
+		let (owner_account, spender_account) = (
+			R::AddressMapping::into_account_id(owner),
+			R::AddressMapping::into_account_id(spender),
+		);
+
+		handle.record_cost(db_read_cost::<R>())?;
+		if !Self::allowance(asset, owner, spender).is_zero() {
+			handle.record_cost(2 * db_read_cost::<R>() + 3 * db_write_cost::<R>())?;
+			Assets::<R>::cancel_approval(
+				RawOrigin::Signed(owner_account.clone()).into(),
+				asset.into(),
+				R::Lookup::unlookup(spender_account.clone()),
+			)
+			.map_err(|e| revert(e.into()))?;
+		}
+		if !amount.is_zero() {
+			handle.record_cost(2 * db_read_cost::<R>() + 3 * db_write_cost::<R>())?;
+			<Assets<R> as MutateApprovals<R::AccountId>>::approve(
+				asset,
+				&owner_account,
+				&spender_account,
+				amount,
+			)
+			.map_err(|e| revert(e.into()))?;
+		}
+
+		log(handle, vec![APPROVAL_TOPIC, owner.into(), spender.into()], amount.into())
+	}
+
+	fn move_balance(
+		handle: &mut impl PrecompileHandle,
+		asset: AssetIdOf<R>,
+		from: H160,
+		to: H160,
+		amount: U256,
+	) -> Result<(), PrecompileFailure> {
+		handle.record_cost(3 * db_read_cost::<R>() + 2 * db_write_cost::<R>())?;
+		let amount_balance = amount.try_into().map_err(|_| revert("amount is too large"))?;
+		<Assets<R> as Transfer<R::AccountId>>::transfer(
+			asset,
+			&R::AddressMapping::into_account_id(from),
+			&R::AddressMapping::into_account_id(to),
+			amount_balance,
+			false,
+		)
+		.map_err(|e| revert(e.into()))?;
+
+		log(handle, vec![TRANSFER_TOPIC, from.into(), to.into()], amount)
+	}
+}
@@ -0,0 +1,250 @@
+//! ERC-20 interface over the native currency.
+//!
+//! Balances are read from and moved in `pallet_evm::Config::Currency` directly, so the token
+//! is always in sync with native balances and nothing has to be wrapped. Allowances are the only
+//! state the precompile keeps.
+//!
+//! `deposit` and `withdraw` are kept for contracts written against WETH, which differ here:
+//! - `balanceOf` is the free native balance, including any locked or frozen part, and `totalSupply`
+//!   is the total issuance;
+//! - `deposit` hands the value sent with it straight back to the caller and only emits `Deposit`;
+//! - `withdraw` moves no funds, as the caller already holds the amount natively. It only emits
+//!   `Withdrawal`, and reverts if the caller owns less than the amount.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_evm::{PrecompileFailure, PrecompileHandle, PrecompileResult};
+use frame_support::{
+	pallet_prelude::{Blake2_128Concat, ValueQuery},
+	traits::{Currency, ExistenceRequirement},
+};
+use hex_literal::hex;
+use pallet_evm::{AddressMapping, Precompile};
+use precompile_utils::{
+	db_read_cost, db_write_cost, encode_bool, encode_string, encode_u256, ensure_mutable,
+	ensure_not_payable, log, revert, succeed, Arguments,
+};
+use sp_core::{H160, H256, U256};
+use sp_std::{marker::PhantomData, vec, vec::Vec};
+
+/// Code stored at the precompile address, so that `extcodesize` is not zero and Solidity
+/// calls go through: `PUSH1 0 PUSH1 0 REVERT`.
+pub const PRECOMPILE_CODE: [u8; 5] = [0x60, 0x00, 0x60, 0x00, 0xfd];
+
+/// Name, symbol and decimals reported by the token.
+pub trait Erc20Metadata {
+	/// Token name, e.g. "Wrapped GNF".
+	fn name() -> &'static str;
+	/// Token symbol, e.g. "WGNF".
+	fn symbol() -> &'static str;
+	/// Number of decimals, the same as the native currency.
+	fn decimals() -> u8;
+}
+
+mod selector {
+	pub const NAME: [u8; 4] = [0x06, 0xfd, 0xde, 0x03];
+	pub const SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41];
+	pub const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+	pub const TOTAL_SUPPLY: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+	pub const BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+	pub const ALLOWANCE: [u8; 4] = [0xdd, 0x62, 0xed, 0x3e];
+	pub const TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+	pub const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+	pub const TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+	pub const DEPOSIT: [u8; 4] = [0xd0, 0xe3, 0x0d, 0xb0];
+	pub const WITHDRAW: [u8; 4] = [0x2e, 0x1a, 0x7d, 0x4d];
+}
+
+/// `Transfer(address,address,uint256)`
+const TRANSFER_TOPIC: H256 =
+	H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+/// `Approval(address,address,uint256)`
+const APPROVAL_TOPIC: H256 =
+	H256(hex!("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925"));
+/// `Deposit(address,uint256)`
+const DEPOSIT_TOPIC: H256 =
+	H256(hex!("e1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"));
+/// `Withdrawal(address,uint256)`
+const WITHDRAWAL_TOPIC: H256 =
+	H256(hex!("7fcf532c15f0a6db0bd6d0e038bea71d30d808c7d98cb3bf7268a95bf5081b65"));
+
+/// Allowances, by owner then spender.
+#[frame_support::storage_alias]
+type Approvals =
+	StorageDoubleMap<NativeErc20, Blake2_128Concat, H160, Blake2_128Concat, H160, U256, ValueQuery>;
+
+/// Native balance type of runtime `R`.
+pub type BalanceOf<R> = <<R as pallet_evm::Config>::Currency as Currency<
+	<R as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// ERC-20 precompile over the native currency of runtime `R`.
+pub struct BalancesErc20Precompile<R, M>(PhantomData<(R, M)>);
+
+impl<R, M> Precompile for BalancesErc20Precompile<R, M>
+where
+	R: pallet_evm::Config,
+	BalanceOf<R>: TryFrom<U256> + Into<U256>,
+	M: Erc20Metadata,
+{
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("tried to parse selector out of bounds"))
+		}
+		let (selector, args) = input.split_at(4);
+		let args = Arguments(args);
+
+		match [selector[0], selector[1], selector[2], selector[3]] {
+			selector::NAME => succeed(encode_string(M::name().as_bytes())),
+			selector::SYMBOL => succeed(encode_string(M::symbol().as_bytes())),
+			selector::DECIMALS => succeed(encode_u256(M::decimals().into())),
+			selector::TOTAL_SUPPLY => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(R::Currency::total_issuance().into()))
+			},
+			selector::BALANCE_OF => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(Self::balance_of(args.address(0)?)))
+			},
+			selector::ALLOWANCE => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(encode_u256(Approvals::get(args.address(0)?, args.address(1)?)))
+			},
+			selector::TRANSFER => Self::transfer(handle, args),
+			selector::APPROVE => Self::approve(handle, args),
+			selector::TRANSFER_FROM => Self::transfer_from(handle, args),
+			selector::DEPOSIT => Self::deposit(handle),
+			selector::WITHDRAW => Self::withdraw(handle, args),
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+impl<R, M> BalancesErc20Precompile<R, M>
+where
+	R: pallet_evm::Config,
+	BalanceOf<R>: TryFrom<U256> + Into<U256>,
+	M: Erc20Metadata,
+{
+	/// Free native balance of `owner`.
+	fn balance_of(owner: H160) -> U256 {
+		R::Currency::free_balance(&R::AddressMapping::into_account_id(owner)).into()
+	}
+
+	fn transfer(handle: &mut impl PrecompileHandle, args: Arguments) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let to = args.address(0)?;
+		let amount = args.u256(1)?;
+
+		let from = handle.context().caller;
+		Self::move_balance(handle, from, to, amount)?;
+		succeed(encode_bool(true))
+	}
+
+	fn approve(handle: &mut impl PrecompileHandle, args: Arguments) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let spender = args.address(0)?;
+		let amount = args.u256(1)?;
+
+		handle.record_cost(db_write_cost::<R>())?;
+		let owner = handle.context().caller;
+		Approvals::insert(owner, spender, amount);
+
+		log(handle, vec![APPROVAL_TOPIC, owner.into(), spender.into()], amount)?;
+		succeed(encode_bool(true))
+	}
+
+	fn transfer_from(handle: &mut impl PrecompileHandle, args: Arguments) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let from = args.address(0)?;
+		let to = args.address(1)?;
+		let amount = args.u256(2)?;
+
+		let spender = handle.context().caller;
+		if spender != from {
+			handle.record_cost(db_read_cost::<R>())?;
+			let allowed = Approvals::get(from, spender);
+			if allowed < amount {
+				return Err(revert("trying to spend more than allowed"))
+			}
+			// An infinite approval is never spent down.
+			if allowed != U256::MAX {
+				handle.record_cost(db_write_cost::<R>())?;
+				Approvals::insert(from, spender, allowed - amount);
+			}
+		}
+
+		Self::move_balance(handle, from, to, amount)?;
+		succeed(encode_bool(true))
+	}
+
+	/// WETH `deposit()`: returns `msg.value` to the caller.
+	fn deposit(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		let context = handle.context();
+		let (caller, this, amount) = (context.caller, context.address, context.apparent_value);
+		if amount.is_zero() {
+			return Err(revert("deposited amount must be non-zero"))
+		}
+
+		// The EVM has already moved the value to the precompile account: give it back, the
+		// caller's native balance is its token balance.
+		handle.record_cost(2 * db_read_cost::<R>() + 2 * db_write_cost::<R>())?;
+		R::Currency::transfer(
+			&R::AddressMapping::into_account_id(this),
+			&R::AddressMapping::into_account_id(caller),
+			to_balance::<R>(amount)?,
+			ExistenceRequirement::AllowDeath,
+		)
+		.map_err(|e| revert(e.into()))?;
+
+		log(handle, vec![DEPOSIT_TOPIC, caller.into()], amount)?;
+		succeed(Vec::new())
+	}
+
+	/// WETH `withdraw(uint256)`: checks the balance and emits `Withdrawal`, moving nothing.
+	fn withdraw(handle: &mut impl PrecompileHandle, args: Arguments) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		ensure_not_payable(handle)?;
+		let amount = args.u256(0)?;
+
+		handle.record_cost(db_read_cost::<R>())?;
+		let caller = handle.context().caller;
+		if Self::balance_of(caller) < amount {
+			return Err(revert("trying to withdraw more than owned"))
+		}
+
+		log(handle, vec![WITHDRAWAL_TOPIC, caller.into()], amount)?;
+		succeed(Vec::new())
+	}
+
+	fn move_balance(
+		handle: &mut impl PrecompileHandle,
+		from: H160,
+		to: H160,
+		amount: U256,
+	) -> Result<(), PrecompileFailure> {
+		handle.record_cost(2 * db_read_cost::<R>() + 2 * db_write_cost::<R>())?;
+		R::Currency::transfer(
+			&R::AddressMapping::into_account_id(from),
+			&R::AddressMapping::into_account_id(to),
+			to_balance::<R>(amount)?,
+			ExistenceRequirement::AllowDeath,
+		)
+		.map_err(|e| revert(e.into()))?;
+
+		log(handle, vec![TRANSFER_TOPIC, from.into(), to.into()], amount)
+	}
+}
+
+fn to_balance<R>(amount: U256) -> Result<BalanceOf<R>, PrecompileFailure>
+where
+	R: pallet_evm::Config,
+	BalanceOf<R>: TryFrom<U256>,
+{
+	amount.try_into().map_err(|_| revert("amount is too large"))
+}
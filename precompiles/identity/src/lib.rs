@@ -0,0 +1,134 @@
+//! Read-only view of `pallet_identity` for Solidity contracts and explorers.
+//!
+//! ```solidity
+//! interface IdentityRegistry {
+//!     // Verified display name of `who`, or "" when it has none.
+//!     function displayName(address who) external view returns (string memory);
+//!     // Whether `who`, or the account it is a sub-account of, has a verified identity.
+//!     function isVerified(address who) external view returns (bool);
+//!     // The account `who` is a sub-account of and its name there, or the zero address.
+//!     function superOf(address who) external view returns (address, string memory);
+//! }
+//! ```
+//!
+//! An identity is verified when a registrar judged it `Reasonable` or `KnownGood` and none
+//! judged it `LowQuality` or `Erroneous`. Sub-accounts of a verified identity are displayed as
+//! `parent/sub`, the way wallets show them. Only names stored as raw bytes can be returned;
+//! hashed names resolve to "".
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_evm::{PrecompileFailure, PrecompileHandle, PrecompileResult};
+use pallet_evm::{AddressMapping, Precompile};
+use pallet_identity::{Data, Judgement};
+use precompile_utils::{
+	db_read_cost, encode_address, encode_bool, encode_bytes, encode_string, encode_u256,
+	ensure_not_payable, revert, succeed, Arguments,
+};
+use sp_core::H160;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+mod selector {
+	pub const DISPLAY_NAME: [u8; 4] = [0x5a, 0xe1, 0x3b, 0x00];
+	pub const IS_VERIFIED: [u8; 4] = [0xb9, 0x20, 0x9e, 0x33];
+	pub const SUPER_OF: [u8; 4] = [0xc1, 0x81, 0x10, 0xd6];
+}
+
+/// Identity precompile over runtime `R`.
+pub struct IdentityPrecompile<R>(PhantomData<R>);
+
+impl<R> Precompile for IdentityPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_identity::Config,
+	R::AccountId: Into<H160>,
+{
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("tried to parse selector out of bounds"))
+		}
+		let (selector, args) = input.split_at(4);
+		let who = Arguments(args).address(0)?;
+		ensure_not_payable(handle)?;
+
+		match [selector[0], selector[1], selector[2], selector[3]] {
+			selector::DISPLAY_NAME => {
+				let name = Self::verified_display_name(handle, who)?.unwrap_or_default();
+				succeed(encode_string(&name))
+			},
+			selector::IS_VERIFIED =>
+				succeed(encode_bool(Self::verified_display_name(handle, who)?.is_some())),
+			selector::SUPER_OF => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let account = R::AddressMapping::into_account_id(who);
+				let (parent, name) = match pallet_identity::Pallet::<R>::super_of(&account) {
+					Some((parent, name)) => (parent.into(), raw(&name)),
+					None => (H160::zero(), Vec::new()),
+				};
+
+				let mut output = encode_address(parent);
+				output.extend(encode_u256(64.into()));
+				output.extend(encode_bytes(&name));
+				succeed(output)
+			},
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+impl<R> IdentityPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_identity::Config,
+{
+	/// The display name of `who` if it, or the account it is a sub-account of, is verified.
+	///
+	/// Sub-accounts are named `parent/sub`.
+	fn verified_display_name(
+		handle: &mut impl PrecompileHandle,
+		who: H160,
+	) -> Result<Option<Vec<u8>>, PrecompileFailure> {
+		let account = R::AddressMapping::into_account_id(who);
+		if let Some(name) = Self::verified_name(handle, &account)? {
+			return Ok(Some(name))
+		}
+
+		handle.record_cost(db_read_cost::<R>())?;
+		let Some((parent, sub_name)) = pallet_identity::Pallet::<R>::super_of(&account) else {
+			return Ok(None)
+		};
+		Ok(Self::verified_name(handle, &parent)?.map(|mut name| {
+			name.push(b'/');
+			name.extend(raw(&sub_name));
+			name
+		}))
+	}
+
+	/// The display name of `account`, if its identity is verified.
+	fn verified_name(
+		handle: &mut impl PrecompileHandle,
+		account: &R::AccountId,
+	) -> Result<Option<Vec<u8>>, PrecompileFailure> {
+		handle.record_cost(db_read_cost::<R>())?;
+		let Some(registration) = pallet_identity::Pallet::<R>::identity(account) else {
+			return Ok(None)
+		};
+
+		let mut verified = false;
+		for (_, judgement) in registration.judgements.iter() {
+			match judgement {
+				Judgement::Reasonable | Judgement::KnownGood => verified = true,
+				Judgement::LowQuality | Judgement::Erroneous => return Ok(None),
+				_ => {},
+			}
+		}
+		Ok(verified.then(|| raw(&registration.info.display)))
+	}
+}
+
+/// The bytes of a name stored as raw data.
+fn raw(data: &Data) -> Vec<u8> {
+	match data {
+		Data::Raw(bytes) => bytes.to_vec(),
+		_ => Vec::new(),
+	}
+}
@@ -0,0 +1,296 @@
+//! Multicall3, the call batching contract tools expect at
+//! `0xcA11bde05977b3631167028862bE2a173976CA11` on every chain, as a precompile.
+//!
+//! It implements the whole Multicall3 interface with the same semantics and revert messages:
+//! `aggregate`, `tryAggregate`, `blockAndAggregate`, `tryBlockAndAggregate`, `aggregate3`,
+//! `aggregate3Value` and the block and balance getters. Calls are made from the precompile
+//! address, or from the calling contract's address when reached through DELEGATECALL, as the
+//! contract would make them. Like the contract, it keeps any value sent to it that the batch
+//! does not forward.
+//!
+//! Block values come from the runtime, as the EVM sees them: `getCurrentBlockDifficulty` is
+//! zero and `getBlockHash` is zero outside of the 256 blocks before the current one.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use evm::Transfer;
+use fp_evm::{Context, ExitReason, PrecompileFailure, PrecompileHandle, PrecompileResult};
+use frame_support::traits::Get;
+use pallet_evm::{BlockHashMapping, FeeCalculator, Precompile};
+use precompile_utils::{
+	db_read_cost, encode_bool, encode_bytes, encode_u256, ensure_not_payable, revert, succeed,
+	Arguments,
+};
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::UniqueSaturatedInto;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+mod selector {
+	pub const AGGREGATE: [u8; 4] = [0x25, 0x2d, 0xba, 0x42];
+	pub const TRY_AGGREGATE: [u8; 4] = [0xbc, 0xe3, 0x8b, 0xd7];
+	pub const BLOCK_AND_AGGREGATE: [u8; 4] = [0xc3, 0x07, 0x7f, 0xa9];
+	pub const TRY_BLOCK_AND_AGGREGATE: [u8; 4] = [0x39, 0x95, 0x42, 0xe9];
+	pub const AGGREGATE3: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+	pub const AGGREGATE3_VALUE: [u8; 4] = [0x17, 0x4d, 0xea, 0x71];
+	pub const GET_BASEFEE: [u8; 4] = [0x3e, 0x64, 0xa6, 0x96];
+	pub const GET_BLOCK_HASH: [u8; 4] = [0xee, 0x82, 0xac, 0x5e];
+	pub const GET_BLOCK_NUMBER: [u8; 4] = [0x42, 0xcb, 0xb1, 0x5c];
+	pub const GET_CHAIN_ID: [u8; 4] = [0x34, 0x08, 0xe4, 0x70];
+	pub const GET_CURRENT_BLOCK_COINBASE: [u8; 4] = [0xa8, 0xb0, 0x57, 0x4e];
+	pub const GET_CURRENT_BLOCK_DIFFICULTY: [u8; 4] = [0x72, 0x42, 0x5d, 0x9d];
+	pub const GET_CURRENT_BLOCK_GAS_LIMIT: [u8; 4] = [0x86, 0xd5, 0x16, 0xe8];
+	pub const GET_CURRENT_BLOCK_TIMESTAMP: [u8; 4] = [0x0f, 0x28, 0xc9, 0x7d];
+	pub const GET_ETH_BALANCE: [u8; 4] = [0x4d, 0x23, 0x01, 0xcc];
+	pub const GET_LAST_BLOCK_HASH: [u8; 4] = [0x27, 0xe8, 0x6d, 0x6e];
+}
+
+/// Fields of the call structs, after the target address.
+#[derive(Clone, Copy)]
+enum CallLayout {
+	/// `Call { address target; bytes callData; }`
+	Call,
+	/// `Call3 { address target; bool allowFailure; bytes callData; }`
+	Call3,
+	/// `Call3Value { address target; bool allowFailure; uint256 value; bytes callData; }`
+	Call3Value,
+}
+
+struct Call {
+	target: H160,
+	allow_failure: bool,
+	value: U256,
+	data: Vec<u8>,
+}
+
+/// Multicall3 precompile over runtime `R`.
+pub struct MulticallPrecompile<R>(PhantomData<R>);
+
+impl<R> Precompile for MulticallPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_timestamp::Config,
+{
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("tried to parse selector out of bounds"))
+		}
+		let (selector, args) = input.split_at(4);
+		let args = Arguments(args);
+
+		match [selector[0], selector[1], selector[2], selector[3]] {
+			selector::AGGREGATE => {
+				let calls = calls(&args, 0, CallLayout::Call)?;
+				let results = Self::aggregate(handle, calls, false)?;
+				let mut output = encode_u256(block_number::<R>());
+				output.extend(encode_u256(64.into()));
+				output
+					.extend(encode_array(results.into_iter().map(|(_, data)| encode_bytes(&data))));
+				succeed(output)
+			},
+			selector::TRY_AGGREGATE => {
+				let require_success = args.bool(0)?;
+				let calls = calls(&args, 1, CallLayout::Call)?;
+				let results = Self::aggregate(handle, calls, !require_success)?;
+				succeed(encode_results(results))
+			},
+			selector::BLOCK_AND_AGGREGATE => {
+				let calls = calls(&args, 0, CallLayout::Call)?;
+				Self::block_and_aggregate(handle, calls, false)
+			},
+			selector::TRY_BLOCK_AND_AGGREGATE => {
+				let require_success = args.bool(0)?;
+				let calls = calls(&args, 1, CallLayout::Call)?;
+				Self::block_and_aggregate(handle, calls, !require_success)
+			},
+			selector::AGGREGATE3 => {
+				let calls = calls(&args, 0, CallLayout::Call3)?;
+				succeed(encode_results(Self::aggregate(handle, calls, false)?))
+			},
+			selector::AGGREGATE3_VALUE => {
+				let calls = calls(&args, 0, CallLayout::Call3Value)?;
+				let total = calls
+					.iter()
+					.try_fold(U256::zero(), |total, call| total.checked_add(call.value))
+					.ok_or_else(|| revert("Multicall3: value mismatch"))?;
+				if total != handle.context().apparent_value {
+					return Err(revert("Multicall3: value mismatch"))
+				}
+				succeed(encode_results(Self::aggregate(handle, calls, false)?))
+			},
+			view => {
+				ensure_not_payable(handle)?;
+				Self::view(handle, view, args)
+			},
+		}
+	}
+}
+
+impl<R> MulticallPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_timestamp::Config,
+{
+	/// Make `calls` in order, reverting the batch on a failed call unless it allows failure
+	/// or `allow_failure` is set.
+	fn aggregate(
+		handle: &mut impl PrecompileHandle,
+		calls: Vec<Call>,
+		allow_failure: bool,
+	) -> Result<Vec<(bool, Vec<u8>)>, PrecompileFailure> {
+		let caller = handle.context().address;
+		let is_static = handle.is_static();
+		let mut results = Vec::with_capacity(calls.len());
+		for call in calls {
+			let transfer = (!call.value.is_zero()).then(|| Transfer {
+				source: caller,
+				target: call.target,
+				value: call.value,
+			});
+			let context = Context { address: call.target, caller, apparent_value: call.value };
+			let (reason, output) =
+				handle.call(call.target, transfer, call.data, None, is_static, &context);
+
+			let success = match reason {
+				ExitReason::Succeed(_) => true,
+				ExitReason::Fatal(exit_status) =>
+					return Err(PrecompileFailure::Fatal { exit_status }),
+				ExitReason::Revert(_) | ExitReason::Error(_) => false,
+			};
+			if !success && !allow_failure && !call.allow_failure {
+				return Err(revert("Multicall3: call failed"))
+			}
+			results.push((success, output));
+		}
+		Ok(results)
+	}
+
+	fn block_and_aggregate(
+		handle: &mut impl PrecompileHandle,
+		calls: Vec<Call>,
+		allow_failure: bool,
+	) -> PrecompileResult {
+		let results = Self::aggregate(handle, calls, allow_failure)?;
+		let number = block_number::<R>();
+		handle.record_cost(db_read_cost::<R>())?;
+		let mut output = encode_u256(number);
+		output.extend_from_slice(block_hash::<R>(number).as_bytes());
+		output.extend(encode_u256(96.into()));
+		output.extend(encode_result_array(results));
+		succeed(output)
+	}
+
+	fn view(
+		handle: &mut impl PrecompileHandle,
+		selector: [u8; 4],
+		args: Arguments,
+	) -> PrecompileResult {
+		match selector {
+			selector::GET_BASEFEE => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let (base_fee, _) = R::FeeCalculator::min_gas_price();
+				succeed(encode_u256(base_fee))
+			},
+			selector::GET_BLOCK_HASH => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(block_hash::<R>(args.u256(0)?).as_bytes().to_vec())
+			},
+			selector::GET_BLOCK_NUMBER => succeed(encode_u256(block_number::<R>())),
+			selector::GET_CHAIN_ID => succeed(encode_u256(R::ChainId::get().into())),
+			selector::GET_CURRENT_BLOCK_COINBASE => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(H256::from(pallet_evm::Pallet::<R>::find_author()).as_bytes().to_vec())
+			},
+			selector::GET_CURRENT_BLOCK_DIFFICULTY => succeed(encode_u256(U256::zero())),
+			selector::GET_CURRENT_BLOCK_GAS_LIMIT => succeed(encode_u256(R::BlockGasLimit::get())),
+			selector::GET_CURRENT_BLOCK_TIMESTAMP => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let now: u128 = pallet_timestamp::Pallet::<R>::get().unique_saturated_into();
+				succeed(encode_u256(U256::from(now / 1000)))
+			},
+			selector::GET_ETH_BALANCE => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let (account, _) = pallet_evm::Pallet::<R>::account_basic(&args.address(0)?);
+				succeed(encode_u256(account.balance))
+			},
+			selector::GET_LAST_BLOCK_HASH => {
+				handle.record_cost(db_read_cost::<R>())?;
+				let number = block_number::<R>().saturating_sub(U256::one());
+				succeed(block_hash::<R>(number).as_bytes().to_vec())
+			},
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+/// The array of call structs that argument `index` points to.
+fn calls(
+	args: &Arguments,
+	index: usize,
+	layout: CallLayout,
+) -> Result<Vec<Call>, PrecompileFailure> {
+	let array = args.usize_at(index * 32)?;
+	let len = args.usize_at(array)?;
+	// Every call takes at least a word, which bounds `len` by the input size.
+	let elements = array + 32;
+	let mut calls = Vec::with_capacity(len.min(args.0.len() / 32));
+	for i in 0..len {
+		let call = elements + args.usize_at(elements + 32 * i)?;
+		let target = args.address_at(call)?;
+		let (allow_failure, value, data) = match layout {
+			CallLayout::Call => (false, U256::zero(), call + 32),
+			CallLayout::Call3 => (args.bool_at(call + 32)?, U256::zero(), call + 64),
+			CallLayout::Call3Value => (
+				args.bool_at(call + 32)?,
+				U256::from_big_endian(args.word_at(call + 64)?),
+				call + 96,
+			),
+		};
+		let data = args.bytes_at(call + args.usize_at(data)?)?;
+		calls.push(Call { target, allow_failure, value, data });
+	}
+	Ok(calls)
+}
+
+fn block_number<R: pallet_evm::Config>() -> U256 {
+	let number: u128 = frame_system::Pallet::<R>::block_number().unique_saturated_into();
+	number.into()
+}
+
+/// The hash of block `number` as `BLOCKHASH` returns it: zero unless it is one of the 256
+/// blocks before the current one.
+fn block_hash<R: pallet_evm::Config>(number: U256) -> H256 {
+	let current = block_number::<R>();
+	if number >= current || current - number > U256::from(256) {
+		return H256::zero()
+	}
+	R::BlockHashMapping::block_hash(number.low_u32())
+}
+
+/// The tail of an array of dynamic values, given the encoding of each.
+fn encode_array(elements: impl Iterator<Item = Vec<u8>>) -> Vec<u8> {
+	let elements: Vec<_> = elements.collect();
+	let mut output = encode_u256(elements.len().into());
+	let mut offset = 32 * elements.len();
+	for element in &elements {
+		output.extend(encode_u256(offset.into()));
+		offset += element.len();
+	}
+	elements.into_iter().for_each(|element| output.extend(element));
+	output
+}
+
+/// A `Result { bool success; bytes returnData; }[]` as the only return value.
+fn encode_results(results: Vec<(bool, Vec<u8>)>) -> Vec<u8> {
+	let mut output = encode_u256(32.into());
+	output.extend(encode_result_array(results));
+	output
+}
+
+/// The tail of a `Result { bool success; bytes returnData; }[]`.
+fn encode_result_array(results: Vec<(bool, Vec<u8>)>) -> Vec<u8> {
+	encode_array(results.into_iter().map(|(success, data)| {
+		let mut result = encode_bool(success);
+		result.extend(encode_u256(64.into()));
+		result.extend(encode_bytes(&data));
+		result
+	}))
+}
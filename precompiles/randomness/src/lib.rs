@@ -0,0 +1,143 @@
+//! Randomness for Solidity contracts, from `pallet_randomness_beacon`.
+//!
+//! ```solidity
+//! interface RandomnessBeacon {
+//!     // The latest beacon output. Its block author knew it first: do not use it for anything
+//!     // worth more to that author than the block.
+//!     function currentRandomness() external view returns (bytes32);
+//!     // Ask for the randomness of the beacon round `delay` rounds from now, about one per block,
+//!     // to be delivered with `callbackGasLimit` gas.
+//!     function requestRandomness(uint32 delay, uint64 callbackGasLimit)
+//!         external
+//!         returns (uint256 requestId);
+//!     // Whether the round of `requestId` is out and `fulfill` would deliver it.
+//!     function isReady(uint256 requestId) external view returns (bool);
+//!     // Deliver the randomness of `requestId`; returns whether the callback succeeded.
+//!     function fulfill(uint256 requestId) external returns (bool);
+//! }
+//!
+//! interface RandomnessConsumer {
+//!     // Called by the precompile on the requesting contract.
+//!     function fulfillRandomness(uint256 requestId, bytes32 randomness) external;
+//! }
+//! ```
+//!
+//! Anyone can call `fulfill`, and pays the gas of the callback. `fulfill` reverts when it has
+//! less gas to give the callback than the request's `callbackGasLimit`, so that the caller
+//! cannot make the callback run out of gas. A request is consumed by `fulfill` even when the
+//! callback reverts, so a consumer cannot retry until it likes the outcome; consumers should
+//! check that `msg.sender` is the precompile. Requests not fulfilled within the beacon's
+//! `HistoryDepth` rounds expire. A contract has at most the beacon's `MaxRequestsPerRequester`
+//! requests pending.
+//!
+//! This is also the way for contracts to get the beacon's randomness: at this Frontier version
+//! the EVM reports a `DIFFICULTY`, or `PREVRANDAO`, of zero and offers no way to change it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_evm::{Context, ExitReason, PrecompileFailure, PrecompileHandle, PrecompileResult};
+use pallet_evm::Precompile;
+use pallet_randomness_beacon::Error as BeaconError;
+use precompile_utils::{
+	db_read_cost, db_write_cost, encode_bool, encode_u256, ensure_mutable, ensure_not_payable,
+	revert, succeed, Arguments,
+};
+use sp_core::U256;
+use sp_std::marker::PhantomData;
+
+mod selector {
+	pub const CURRENT_RANDOMNESS: [u8; 4] = [0x3d, 0xf5, 0x0c, 0x7a];
+	pub const REQUEST_RANDOMNESS: [u8; 4] = [0x52, 0x70, 0xfd, 0xa4];
+	pub const IS_READY: [u8; 4] = [0x50, 0xdf, 0x8f, 0x71];
+	pub const FULFILL: [u8; 4] = [0x75, 0x3b, 0x88, 0x07];
+}
+
+/// `fulfillRandomness(uint256,bytes32)`
+const FULFILL_RANDOMNESS: [u8; 4] = [0xdc, 0x2e, 0x45, 0x0c];
+
+type Beacon<R> = pallet_randomness_beacon::Pallet<R>;
+
+/// Randomness precompile over runtime `R`.
+pub struct RandomnessPrecompile<R>(PhantomData<R>);
+
+impl<R> Precompile for RandomnessPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_randomness_beacon::Config,
+{
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("tried to parse selector out of bounds"))
+		}
+		let (selector, args) = input.split_at(4);
+		ensure_not_payable(handle)?;
+
+		match [selector[0], selector[1], selector[2], selector[3]] {
+			selector::CURRENT_RANDOMNESS => {
+				handle.record_cost(db_read_cost::<R>())?;
+				succeed(Beacon::<R>::latest().randomness.as_bytes().to_vec())
+			},
+			selector::REQUEST_RANDOMNESS => {
+				ensure_mutable(handle)?;
+				let (delay, gas_limit) = (Arguments(args).u256(0)?, Arguments(args).u256(1)?);
+				if delay > U256::from(u32::MAX) {
+					return Err(revert("delay out of range"))
+				}
+				if gas_limit > U256::from(u64::MAX) {
+					return Err(revert("gas limit out of range"))
+				}
+
+				handle.record_cost(4 * db_read_cost::<R>() + 5 * db_write_cost::<R>())?;
+				let requester = handle.context().caller;
+				let id = Beacon::<R>::request(requester, delay.low_u32(), gas_limit.low_u64())
+					.map_err(|_| revert("invalid delay, or too many requests"))?;
+				succeed(encode_u256(id.into()))
+			},
+			selector::IS_READY => {
+				handle.record_cost(2 * db_read_cost::<R>())?;
+				let ready =
+					request_id(args)?.map_or(false, |id| Beacon::<R>::fulfillment(id).is_ok());
+				succeed(encode_bool(ready))
+			},
+			selector::FULFILL => Self::fulfill(handle, args),
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+impl<R> RandomnessPrecompile<R>
+where
+	R: pallet_evm::Config + pallet_randomness_beacon::Config,
+{
+	fn fulfill(handle: &mut impl PrecompileHandle, args: &[u8]) -> PrecompileResult {
+		ensure_mutable(handle)?;
+		handle.record_cost(3 * db_read_cost::<R>() + 3 * db_write_cost::<R>())?;
+		let id = request_id(args)?.ok_or_else(|| revert("unknown request"))?;
+		let (request, randomness) = Beacon::<R>::fulfillment(id).map_err(|err| match err {
+			BeaconError::<R>::NotReady => revert("randomness not ready"),
+			_ => revert("unknown request"),
+		})?;
+		if handle.remaining_gas() < request.gas_limit {
+			return Err(revert("not enough gas for the callback"))
+		}
+
+		// Consumed before the callback, which could otherwise fulfill it again.
+		Beacon::<R>::complete(id);
+
+		let mut input = FULFILL_RANDOMNESS.to_vec();
+		input.extend(encode_u256(id.into()));
+		input.extend_from_slice(randomness.as_bytes());
+		let requester = request.requester;
+		let context =
+			Context { address: requester, caller: handle.code_address(), apparent_value: 0.into() };
+		let (reason, _) =
+			handle.call(requester, None, input, Some(request.gas_limit), false, &context);
+
+		succeed(encode_bool(matches!(reason, ExitReason::Succeed(_))))
+	}
+}
+
+/// The request id argument, or `None` if no request can have it.
+fn request_id(args: &[u8]) -> Result<Option<u64>, PrecompileFailure> {
+	Ok(u64::try_from(Arguments(args).u256(0)?).ok())
+}
@@ -0,0 +1,156 @@
+//! Helpers shared by the chain-specific precompiles: reading ABI-encoded arguments, encoding
+//! return values and reverts, and charging gas for storage access.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_evm::{
+	ExitRevert, ExitSucceed, PrecompileFailure, PrecompileHandle, PrecompileOutput,
+	PrecompileResult,
+};
+use frame_support::traits::Get;
+use hex_literal::hex;
+use pallet_evm::GasWeightMapping;
+use sp_core::{H160, H256, U256};
+use sp_std::vec::Vec;
+
+/// ABI-encoded call arguments, after the selector.
+pub struct Arguments<'a>(pub &'a [u8]);
+
+impl<'a> Arguments<'a> {
+	/// The word at byte `offset`.
+	pub fn word_at(&self, offset: usize) -> Result<&'a [u8], PrecompileFailure> {
+		offset
+			.checked_add(32)
+			.and_then(|end| self.0.get(offset..end))
+			.ok_or_else(|| revert("input too short"))
+	}
+
+	/// The word at byte `offset`, as an offset or a length into the arguments.
+	pub fn usize_at(&self, offset: usize) -> Result<usize, PrecompileFailure> {
+		let value = U256::from_big_endian(self.word_at(offset)?);
+		if value > U256::from(self.0.len()) {
+			return Err(revert("offset out of bounds"))
+		}
+		Ok(value.as_usize())
+	}
+
+	pub fn address_at(&self, offset: usize) -> Result<H160, PrecompileFailure> {
+		let word = self.word_at(offset)?;
+		if word[..12].iter().any(|b| *b != 0) {
+			return Err(revert("invalid address"))
+		}
+		Ok(H160::from_slice(&word[12..]))
+	}
+
+	pub fn bool_at(&self, offset: usize) -> Result<bool, PrecompileFailure> {
+		match U256::from_big_endian(self.word_at(offset)?) {
+			value if value.is_zero() => Ok(false),
+			value if value == U256::one() => Ok(true),
+			_ => Err(revert("invalid bool")),
+		}
+	}
+
+	/// The `bytes` whose encoding starts at byte `offset`.
+	pub fn bytes_at(&self, offset: usize) -> Result<Vec<u8>, PrecompileFailure> {
+		let len = self.usize_at(offset)?;
+		let start = offset + 32;
+		let data = self.0.get(start..start + len).ok_or_else(|| revert("input too short"))?;
+		Ok(data.to_vec())
+	}
+
+	/// Argument `index`, as a raw word.
+	pub fn word(&self, index: usize) -> Result<&'a [u8], PrecompileFailure> {
+		self.word_at(index.saturating_mul(32))
+	}
+
+	pub fn address(&self, index: usize) -> Result<H160, PrecompileFailure> {
+		self.address_at(index.saturating_mul(32))
+	}
+
+	pub fn bool(&self, index: usize) -> Result<bool, PrecompileFailure> {
+		self.bool_at(index.saturating_mul(32))
+	}
+
+	pub fn u256(&self, index: usize) -> Result<U256, PrecompileFailure> {
+		Ok(U256::from_big_endian(self.word(index)?))
+	}
+}
+
+pub fn ensure_mutable(handle: &impl PrecompileHandle) -> Result<(), PrecompileFailure> {
+	if handle.is_static() {
+		return Err(revert("can't call non-static function in static context"))
+	}
+	Ok(())
+}
+
+pub fn ensure_not_payable(handle: &impl PrecompileHandle) -> Result<(), PrecompileFailure> {
+	if !handle.context().apparent_value.is_zero() {
+		return Err(revert("function is not payable"))
+	}
+	Ok(())
+}
+
+/// Gas charged for reading one storage item.
+pub fn db_read_cost<R: pallet_evm::Config>() -> u64 {
+	R::GasWeightMapping::weight_to_gas(<R as frame_system::Config>::DbWeight::get().reads(1))
+}
+
+/// Gas charged for writing one storage item.
+pub fn db_write_cost<R: pallet_evm::Config>() -> u64 {
+	R::GasWeightMapping::weight_to_gas(<R as frame_system::Config>::DbWeight::get().writes(1))
+}
+
+/// Emit a log with a single `uint256` in its data, charging the `LOG` opcode cost.
+pub fn log(
+	handle: &mut impl PrecompileHandle,
+	topics: Vec<H256>,
+	value: U256,
+) -> Result<(), PrecompileFailure> {
+	// G_LOG + G_LOGTOPIC per topic + G_LOGDATA per byte.
+	handle.record_cost(375 + 375 * topics.len() as u64 + 8 * 32)?;
+	let address = handle.context().address;
+	handle.log(address, topics, encode_u256(value))?;
+	Ok(())
+}
+
+pub fn succeed(output: Vec<u8>) -> PrecompileResult {
+	Ok(PrecompileOutput { exit_status: ExitSucceed::Returned, output })
+}
+
+/// A `revert` carrying a Solidity `Error(string)`.
+pub fn revert(message: &str) -> PrecompileFailure {
+	let mut output = hex!("08c379a0").to_vec();
+	output.extend(encode_string(message.as_bytes()));
+	PrecompileFailure::Revert { exit_status: ExitRevert::Reverted, output }
+}
+
+pub fn encode_u256(value: U256) -> Vec<u8> {
+	let mut word = [0u8; 32];
+	value.to_big_endian(&mut word);
+	word.to_vec()
+}
+
+pub fn encode_bool(value: bool) -> Vec<u8> {
+	encode_u256(if value { U256::one() } else { U256::zero() })
+}
+
+pub fn encode_address(value: H160) -> Vec<u8> {
+	let mut word = [0u8; 32];
+	word[12..].copy_from_slice(value.as_bytes());
+	word.to_vec()
+}
+
+/// A `string` return value: its offset, then its length and padded bytes.
+pub fn encode_string(value: &[u8]) -> Vec<u8> {
+	let mut output = encode_u256(32.into());
+	output.extend(encode_bytes(value));
+	output
+}
+
+/// The tail of a dynamic `string` or `bytes`: length, then the bytes padded to 32.
+pub fn encode_bytes(value: &[u8]) -> Vec<u8> {
+	let mut output = encode_u256(value.len().into());
+	output.extend_from_slice(value);
+	output.resize(output.len() + (32 - value.len() % 32) % 32, 0);
+	output
+}
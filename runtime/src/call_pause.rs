@@ -0,0 +1,15 @@
+//! Runtime API listing the calls governance paused through `pallet_call_pause`.
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// The API to query paused calls.
+	pub trait CallPauseApi<BlockNumber> where
+		BlockNumber: Codec,
+	{
+		/// Pauses in place, as `(pallet, call, until)`. A pause without a call covers the
+		/// whole pallet.
+		fn paused_calls() -> Vec<(Vec<u8>, Option<Vec<u8>>, BlockNumber)>;
+	}
+}
@@ -0,0 +1,20 @@
+//! Runtime API exposing the source and ABI metadata attached to EVM contracts.
+//!
+//! Explorers use it to show verified contracts; see `pallet_contract_metadata` for how the
+//! metadata gets on chain.
+
+use codec::Codec;
+pub use pallet_contract_metadata::{ContractMetadata, MetadataSource};
+use sp_core::H160;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// The API to query contract metadata.
+	pub trait ContractMetadataApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Metadata attached to `address`, if any.
+		fn contract_metadata(address: H160) -> Option<ContractMetadata<AccountId, Balance, Vec<u8>>>;
+	}
+}
@@ -0,0 +1,40 @@
+//! Runtime API reconciling the balance the EVM reports with `pallet_balances`.
+//!
+//! `eth_getBalance` reports the free balance, but reserves and locks from democracy deposits,
+//! treasury bonds or vesting limit what an EVM transaction can actually move. This API returns
+//! all of them for an `H160`, so wallets can explain an "insufficient funds" error.
+
+use codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::{H160, U256};
+use sp_runtime::RuntimeDebug;
+
+/// Balances of an account as seen by `pallet_balances` and by the EVM.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct EvmAccountBalance<Balance, Index> {
+	/// Free balance, including the frozen part.
+	pub free: Balance,
+	/// Reserved balance, e.g. democracy deposits and treasury bonds. Never visible to the EVM.
+	pub reserved: Balance,
+	/// Part of the free balance locked for transfers.
+	pub misc_frozen: Balance,
+	/// Part of the free balance locked for paying fees.
+	pub fee_frozen: Balance,
+	/// Balance reported by `eth_getBalance`.
+	pub evm_balance: U256,
+	/// Balance an EVM transaction can transfer out of the account.
+	pub evm_spendable: Balance,
+	/// Account nonce, shared by Substrate extrinsics and Ethereum transactions.
+	pub nonce: Index,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to reconcile EVM and `pallet_balances` balances.
+	pub trait EvmBalancesApi<Balance, Index> where
+		Balance: Codec,
+		Index: Codec,
+	{
+		/// Balances and nonce of `address`.
+		fn evm_account_balance(address: H160) -> EvmAccountBalance<Balance, Index>;
+	}
+}
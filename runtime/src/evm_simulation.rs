@@ -0,0 +1,295 @@
+//! Runtime API simulating EVM calls against overridden state.
+//!
+//! Backs the geth-compatible `eth_call` state overrides and `eth_createAccessList`. Overrides are
+//! written to storage before the call, which is fine because runtime API calls never commit.
+
+use codec::{Decode, Encode};
+use fp_evm::ExitReason;
+use frame_support::{
+	storage::{with_transaction, TransactionOutcome},
+	traits::Currency,
+};
+use pallet_evm::{AddressMapping, PrecompileSet, Runner};
+use sp_core::{H160, H256, U256};
+use sp_runtime::{traits::UniqueSaturatedInto, DispatchError, RuntimeDebug};
+use sp_std::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	vec::Vec,
+};
+
+use crate::{Balances, Runtime, EVM};
+
+/// Upper bound of the access list refinement rounds, as the list may never settle for
+/// contracts branching on gas.
+const MAX_ACCESS_LIST_ROUNDS: usize = 8;
+
+/// Call or contract creation to simulate.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct SimulationRequest {
+	/// Caller.
+	pub from: H160,
+	/// Callee, or `None` to create a contract.
+	pub to: Option<H160>,
+	/// Call data or init code.
+	pub data: Vec<u8>,
+	/// Transferred value.
+	pub value: U256,
+	/// Gas limit.
+	pub gas_limit: U256,
+	/// Maximum fee per gas.
+	pub max_fee_per_gas: Option<U256>,
+	/// Maximum priority fee per gas.
+	pub max_priority_fee_per_gas: Option<U256>,
+	/// Caller nonce, checked if given.
+	pub nonce: Option<U256>,
+	/// EIP-2930 access list.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+}
+
+/// Replacement state of one account, applied before the simulation.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, Default)]
+pub struct StateOverride {
+	/// Overridden account.
+	pub address: H160,
+	/// Free balance.
+	pub balance: Option<U256>,
+	/// Account nonce.
+	pub nonce: Option<U256>,
+	/// Contract code.
+	pub code: Option<Vec<u8>>,
+	/// Storage replacing the whole storage of the account.
+	pub state: Option<Vec<(H256, H256)>>,
+	/// Storage slots to change, leaving other slots untouched.
+	pub state_diff: Option<Vec<(H256, H256)>>,
+}
+
+/// Outcome of a simulated call or contract creation.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct SimulationResult {
+	/// Why execution stopped.
+	pub exit_reason: ExitReason,
+	/// Return data, or the deployed code of a contract creation.
+	pub value: Vec<u8>,
+	/// Gas used.
+	pub used_gas: U256,
+}
+
+/// Access list of a simulated call and the outcome of the call using it.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct AccessListResult {
+	/// Accounts and storage slots the call accesses.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+	/// Outcome of the call with `access_list` applied.
+	pub result: SimulationResult,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Simulate EVM execution with state overrides.
+	pub trait EvmSimulationApi {
+		/// Execute `request` after applying `overrides`.
+		fn simulate(
+			request: SimulationRequest,
+			overrides: Vec<StateOverride>,
+		) -> Result<SimulationResult, DispatchError>;
+
+		/// Execute `request` after applying `overrides` and collect the accounts and storage
+		/// slots it touches.
+		fn create_access_list(
+			request: SimulationRequest,
+			overrides: Vec<StateOverride>,
+		) -> Result<AccessListResult, DispatchError>;
+	}
+}
+
+/// Write `overrides` to storage.
+pub fn apply_overrides(overrides: Vec<StateOverride>) {
+	for account in overrides {
+		let address = account.address;
+		let account_id = <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address);
+
+		if let Some(balance) = account.balance {
+			Balances::make_free_balance_be(&account_id, balance.unique_saturated_into());
+		}
+		if let Some(nonce) = account.nonce {
+			frame_system::Account::<Runtime>::mutate(&account_id, |info| {
+				info.nonce = nonce.unique_saturated_into()
+			});
+		}
+		if let Some(code) = account.code {
+			pallet_evm::AccountCodes::<Runtime>::insert(address, code);
+		}
+		if let Some(state) = account.state {
+			let _ = pallet_evm::AccountStorages::<Runtime>::clear_prefix(address, u32::MAX, None);
+			for (index, value) in state {
+				pallet_evm::AccountStorages::<Runtime>::insert(address, index, value);
+			}
+		}
+		for (index, value) in account.state_diff.unwrap_or_default() {
+			pallet_evm::AccountStorages::<Runtime>::insert(address, index, value);
+		}
+	}
+}
+
+/// Execute `request` against the current state.
+pub fn execute(request: SimulationRequest) -> Result<SimulationResult, DispatchError> {
+	let config = <Runtime as pallet_evm::Config>::config();
+	let is_transactional = false;
+	let validate = false;
+
+	match request.to {
+		Some(to) => <Runtime as pallet_evm::Config>::Runner::call(
+			request.from,
+			to,
+			request.data,
+			request.value,
+			request.gas_limit.unique_saturated_into(),
+			request.max_fee_per_gas,
+			request.max_priority_fee_per_gas,
+			request.nonce,
+			request.access_list,
+			is_transactional,
+			validate,
+			config,
+		)
+		.map(|info| SimulationResult {
+			exit_reason: info.exit_reason,
+			value: info.value,
+			used_gas: info.used_gas,
+		})
+		.map_err(|err| err.error.into()),
+		None => <Runtime as pallet_evm::Config>::Runner::create(
+			request.from,
+			request.data,
+			request.value,
+			request.gas_limit.unique_saturated_into(),
+			request.max_fee_per_gas,
+			request.max_priority_fee_per_gas,
+			request.nonce,
+			request.access_list,
+			is_transactional,
+			validate,
+			config,
+		)
+		.map(|info| SimulationResult {
+			value: match info.exit_reason {
+				ExitReason::Succeed(_) => EVM::account_codes(info.value),
+				_ => Vec::new(),
+			},
+			exit_reason: info.exit_reason,
+			used_gas: info.used_gas,
+		})
+		.map_err(|err| err.error.into()),
+	}
+}
+
+/// Execute `request` and discard its state changes.
+fn execute_and_revert(request: SimulationRequest) -> Result<SimulationResult, DispatchError> {
+	with_transaction(|| TransactionOutcome::Rollback(execute(request)))
+}
+
+/// Build the access list of `request` the way geth does: run the call, add every account and
+/// slot it touched, and repeat with the new list until it no longer changes.
+pub fn create_access_list(request: SimulationRequest) -> Result<AccessListResult, DispatchError> {
+	let precompiles = <Runtime as pallet_evm::Config>::PrecompilesValue::get();
+	// The sender and the callee, or the created contract, are warm anyway.
+	let callee = request.to.unwrap_or_else(|| {
+		let nonce = request.nonce.unwrap_or_else(|| EVM::account_basic(&request.from).0.nonce);
+		create_address(request.from, nonce)
+	});
+	let excluded = BTreeSet::from([request.from, callee]);
+
+	let mut access_list = request.access_list.clone();
+	for _ in 0..MAX_ACCESS_LIST_ROUNDS {
+		let mut tracer = AccessListTracer::default();
+		let round = SimulationRequest { access_list: access_list.clone(), ..request.clone() };
+		let result = evm_runtime::tracing::using(&mut tracer, || execute_and_revert(round))?;
+
+		let found = tracer.into_access_list(&request.access_list, |address| {
+			excluded.contains(address) || precompiles.is_precompile(*address)
+		});
+		if found == access_list {
+			return Ok(AccessListResult { access_list, result })
+		}
+		access_list = found;
+	}
+
+	let result =
+		execute_and_revert(SimulationRequest { access_list: access_list.clone(), ..request })?;
+	Ok(AccessListResult { access_list, result })
+}
+
+/// Address of the contract `from` creates with `CREATE` at `nonce`: the last 20 bytes of the
+/// keccak-256 hash of `rlp([from, nonce])`.
+pub fn create_address(from: H160, nonce: U256) -> H160 {
+	let mut nonce_bytes = [0u8; 32];
+	nonce.to_big_endian(&mut nonce_bytes);
+	let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+
+	let mut item = Vec::with_capacity(33);
+	match nonce_bytes {
+		[byte] if *byte < 0x80 => item.push(*byte),
+		bytes => {
+			item.push(0x80 + bytes.len() as u8);
+			item.extend_from_slice(bytes);
+		},
+	}
+	let mut rlp = Vec::with_capacity(56);
+	rlp.push(0xc0 + 21 + item.len() as u8);
+	rlp.push(0x80 + 20);
+	rlp.extend_from_slice(from.as_bytes());
+	rlp.extend_from_slice(&item);
+	H160::from_slice(&sp_io::hashing::keccak_256(&rlp)[12..])
+}
+
+/// Collects the accounts and storage slots an execution touches.
+#[derive(Default)]
+struct AccessListTracer {
+	accessed: BTreeMap<H160, BTreeSet<H256>>,
+}
+
+impl AccessListTracer {
+	/// Merge the touched accounts into `initial`. Accounts that are warm anyway are only kept
+	/// when they have storage slots.
+	fn into_access_list(
+		self,
+		initial: &[(H160, Vec<H256>)],
+		warm: impl Fn(&H160) -> bool,
+	) -> Vec<(H160, Vec<H256>)> {
+		let mut accessed = self.accessed;
+		for (address, slots) in initial {
+			accessed.entry(*address).or_default().extend(slots.iter().copied());
+		}
+		accessed
+			.into_iter()
+			.filter(|(address, slots)| !slots.is_empty() || !warm(address))
+			.map(|(address, slots)| (address, slots.into_iter().collect()))
+			.collect()
+	}
+}
+
+impl evm_runtime::tracing::EventListener for AccessListTracer {
+	fn event(&mut self, event: evm_runtime::tracing::Event) {
+		use evm_runtime::{tracing::Event, Opcode};
+
+		match event {
+			Event::SLoad { address, index, .. } | Event::SStore { address, index, .. } => {
+				self.accessed.entry(address).or_default().insert(index);
+			},
+			Event::Step { opcode, stack, .. } => {
+				let position = match opcode {
+					Opcode::BALANCE |
+					Opcode::EXTCODESIZE |
+					Opcode::EXTCODECOPY |
+					Opcode::EXTCODEHASH |
+					Opcode::SUICIDE => 0,
+					Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => 1,
+					_ => return,
+				};
+				if let Ok(address) = stack.peek(position) {
+					self.accessed.entry(H160::from(address)).or_default();
+				}
+			},
+			_ => {},
+		}
+	}
+}
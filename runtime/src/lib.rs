@@ -16,6 +16,9 @@ use pallet_grandpa::{
 };
 
 use frame_system::EnsureRoot;
+use frame_support::traits::{fungibles, AsEnsureOriginWithArg, Contains, GetCallMetadata};
+use frame_support::dispatch::DispatchClass;
+use pallet_asset_tx_payment::HandleCredit;
 
 
 use codec::{Encode, Decode};
@@ -32,7 +35,7 @@ use sp_runtime::{
 		Verify 
 	},
 	transaction_validity::{
-		TransactionSource,TransactionPriority, TransactionValidity, TransactionValidityError}, ApplyExtrinsicResult, ConsensusEngineId,
+		InvalidTransaction, TransactionSource,TransactionPriority, TransactionValidity, TransactionValidityError}, ApplyExtrinsicResult, ConsensusEngineId,
 		SaturatedConversion,
 };
 
@@ -45,7 +48,7 @@ use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 
 
 use pallet_evm::{
-	EnsureAddressRoot, EnsureAddressNever, Account as EVMAccount, Runner,
+	EnsureAddressRoot, EnsureAddressNever, Account as EVMAccount, AddressMapping, Runner,
 	FeeCalculator,
 };
 
@@ -173,10 +176,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 102,
+	spec_version: 117,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 3,
 	state_version: 1,
 	
 };
@@ -211,18 +214,42 @@ const WEIGHT_PER_GAS: u64 = 20_000;
 
 mod precompiles;
 mod account;
+pub mod call_pause;
+pub mod contract_metadata;
+pub mod evm_balances;
+pub mod evm_simulation;
+pub mod migrations;
+pub mod randomness_beacon;
+pub mod system_contracts;
+pub mod validators;
 
 use precompiles::SubstratePrecompiles;
+pub use pallet_evm_precompile_balances_erc20::PRECOMPILE_CODE;
+pub use pallet_evm_precompile_assets_erc20::asset_address;
+pub use precompiles::{IDENTITY_ADDRESS, MULTICALL3_ADDRESS, RANDOMNESS_ADDRESS, WGNF_ADDRESS};
 
 parameter_types! {
 	pub const BlockHashCount: BlockNumber = 2400;
 	pub const Version: RuntimeVersion = VERSION;
 	/// We allow for 2 seconds of compute with a 6 second average block time.
-	pub BlockWeights: frame_system::limits::BlockWeights =
-		frame_system::limits::BlockWeights::with_sensible_defaults(
-			Weight::from_parts(2u64 * WEIGHT_REF_TIME_PER_SECOND, u64::MAX),
-			NORMAL_DISPATCH_RATIO,
-		);
+	pub BlockWeights: frame_system::limits::BlockWeights = {
+		let max_block = Weight::from_parts(2u64 * WEIGHT_REF_TIME_PER_SECOND, u64::MAX);
+		let max_normal = NORMAL_DISPATCH_RATIO * max_block;
+		// The limits of `BlockWeights::with_sensible_defaults`, with every extrinsic also paying
+		// for the `CallPause` check of the `BaseCallFilter`.
+		frame_system::limits::BlockWeights::builder()
+			.for_class(DispatchClass::all(), |weights| {
+				weights.base_extrinsic =
+					ExtrinsicBaseWeight::get().saturating_add(CallPause::filter_weight());
+			})
+			.for_class(DispatchClass::Normal, |weights| weights.max_total = Some(max_normal))
+			.for_class(DispatchClass::Operational, |weights| {
+				weights.max_total = Some(max_block);
+				weights.reserved = Some(max_block - max_normal);
+			})
+			.avg_block_initialization(Perbill::from_percent(10))
+			.build_or_panic()
+	};
 	pub BlockLength: frame_system::limits::BlockLength = frame_system::limits::BlockLength
 		::max_with_normal_ratio(5 * 1024 * 1024, NORMAL_DISPATCH_RATIO);
 	pub const SS58Prefix: u8 = 42;
@@ -231,8 +258,8 @@ parameter_types! {
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
-	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = frame_support::traits::Everything;
+	/// The basic call filter to use in dispatchable: everything but what governance paused.
+	type BaseCallFilter = CallPause;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = BlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -283,7 +310,38 @@ impl frame_system::Config for Runtime {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
-impl pallet_randomness_collective_flip::Config for Runtime {}
+/// Names the Aura author of a slot as the signer of its randomness beacon VRF.
+pub struct AuraSlotAuthor;
+
+impl pallet_randomness_beacon::SlotAuthor for AuraSlotAuthor {
+	fn current_slot() -> u64 {
+		Aura::current_slot().into()
+	}
+
+	fn author(slot: u64) -> Option<[u8; 32]> {
+		use sp_core::crypto::ByteArray;
+
+		let authorities = Aura::authorities();
+		let index = slot.checked_rem(authorities.len() as u64)?;
+		authorities.get(index as usize)?.as_slice().try_into().ok()
+	}
+}
+
+parameter_types! {
+	pub const RandomnessHistoryDepth: u32 = 256;
+	pub const MaxRandomnessRequestDelay: u32 = 128;
+	pub const MaxRandomnessRequestsPerRound: u32 = 64;
+	pub const MaxRandomnessRequestsPerRequester: u32 = 8;
+}
+
+impl pallet_randomness_beacon::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type SlotAuthor = AuraSlotAuthor;
+	type HistoryDepth = RandomnessHistoryDepth;
+	type MaxRequestDelay = MaxRandomnessRequestDelay;
+	type MaxRequestsPerRound = MaxRandomnessRequestsPerRound;
+	type MaxRequestsPerRequester = MaxRandomnessRequestsPerRequester;
+}
 
 
 
@@ -374,7 +432,9 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 
 impl pallet_transaction_payment::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type OnChargeTransaction = CurrencyAdapter<Balances, DealWithFees>;
+	// Sponsored EVM calls are paid by the contract they call.
+	type OnChargeTransaction =
+		pallet_evm_paymaster::SponsoredFees<Runtime, CurrencyAdapter<Balances, DealWithFees>>;
 	type OperationalFeeMultiplier = ConstU8<5>;
 	type WeightToFee = IdentityFee<Balance>;
 	type LengthToFee = IdentityFee<Balance>;
@@ -457,9 +517,9 @@ impl pallet_evm::Config for Runtime {
 	type PrecompilesValue = PrecompilesValue;
 	type ChainId = LeetChainId;
 	type BlockGasLimit = BlockGasLimit;
-	type Runner = pallet_evm::runner::stack::Runner<Self>;
+	type Runner = pallet_evm_deployers::Runner<Self, pallet_evm::runner::stack::Runner<Self>>;
 	type OnChargeTransaction = ();
-	type OnCreate = ();
+	type OnCreate = EvmDeployers;
 	type FindAuthor = StorageFindAuthor<pallet_session::FindAccountFromAuthorIndex<Self, Aura>>;
 }
 
@@ -468,6 +528,199 @@ impl pallet_ethereum::Config for Runtime {
 	type StateRoot = pallet_ethereum::IntermediateStateRoot<Self>;
 }
 
+impl pallet_evm_deployers::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type ManageOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>,
+	>;
+}
+
+/// Lets `pallet_contract_metadata` find deployers in the `pallet_evm_deployers` audit trail.
+pub struct RecordedDeployers;
+impl pallet_contract_metadata::DeployerLookup<AccountId> for RecordedDeployers {
+	fn deployer(contract: &H160) -> Option<AccountId> {
+		EvmDeployers::contracts(contract).map(|record| record.deployer.into())
+	}
+}
+
+parameter_types! {
+	pub const MetadataDepositPerItem: Balance = deposit(1, 0);
+	pub const MetadataDepositPerByte: Balance = deposit(0, 1);
+	// Enough for a CID, or for the compressed ABI of a large contract.
+	pub const MaxMetadataLen: u32 = 16 * 1024;
+}
+
+impl pallet_contract_metadata::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type Deployers = RecordedDeployers;
+	type ManageOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>,
+	>;
+	type DepositPerItem = MetadataDepositPerItem;
+	type DepositPerByte = MetadataDepositPerByte;
+	type MaxMetadataLen = MaxMetadataLen;
+}
+
+/// Pallets that keep working while calls are paused: inherents and unsigned validator
+/// messages, governance and sudo, session keys, and the pause itself.
+///
+/// A block missing a mandatory inherent, such as `RandomnessBeacon::set_vrf_output`, is invalid,
+/// so pausing one would stop block production.
+pub struct UnpausablePallets;
+impl Contains<pallet_call_pause::PauseTargetOf<Runtime>> for UnpausablePallets {
+	fn contains((pallet, _call): &pallet_call_pause::PauseTargetOf<Runtime>) -> bool {
+		matches!(
+			&pallet[..],
+			b"System" | b"Timestamp" | b"RandomnessBeacon" | b"Grandpa" | b"ImOnline" |
+				b"ValidatorLiveness" | b"Sudo" | b"Session" | b"Democracy" | b"Council" |
+				b"TechnicalCommittee" | b"Scheduler" | b"Preimage" | b"CallPause"
+		)
+	}
+}
+
+type PauseOrigin = EitherOfDiverse<
+	EnsureRoot<AccountId>,
+	EitherOfDiverse<
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, TechnicalCollective, 1, 2>,
+	>,
+>;
+
+parameter_types! {
+	// Longer incidents need the pause renewed, so it cannot be forgotten.
+	pub const MaxPauseDuration: BlockNumber = 7 * DAYS;
+	pub const MaxPaused: u32 = 64;
+	pub const MaxCallNameLen: u32 = 64;
+}
+
+impl pallet_call_pause::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PauseOrigin = PauseOrigin;
+	type UnpauseOrigin = PauseOrigin;
+	type WhitelistedCalls = UnpausablePallets;
+	type MaxPauseDuration = MaxPauseDuration;
+	type MaxPaused = MaxPaused;
+	type MaxNameLen = MaxCallNameLen;
+}
+
+parameter_types! {
+	// An identity with the usual fields is about 258 bytes; a sub-account entry is an
+	// `AccountId20` and a 32 byte name plus the reverse lookup.
+	pub const BasicDeposit: Balance = deposit(1, 258);
+	pub const FieldDeposit: Balance = deposit(0, 66);
+	pub const SubAccountDeposit: Balance = deposit(1, 41);
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl pallet_identity::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	type Slashed = Treasury;
+	type ForceOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionMoreThan<AccountId, CouncilCollective, 1, 2>,
+	>;
+	type RegistrarOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionMoreThan<AccountId, CouncilCollective, 1, 2>,
+	>;
+	type WeightInfo = pallet_identity::weights::SubstrateWeight<Runtime>;
+}
+
+/// Identifier of an asset.
+pub type AssetId = u32;
+
+parameter_types! {
+	// `AssetDetails` holds four `AccountId20` roles next to its balances and counters.
+	pub const AssetDeposit: Balance = deposit(1, 190);
+	pub const AssetAccountDeposit: Balance = deposit(1, 16);
+	pub const AssetMetadataDepositBase: Balance = deposit(1, 68);
+	pub const AssetMetadataDepositPerByte: Balance = deposit(0, 1);
+	pub const AssetApprovalDeposit: Balance = deposit(1, 32);
+	pub const AssetsStringLimit: u32 = 50;
+}
+
+impl pallet_assets::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type AssetIdParameter = codec::Compact<AssetId>;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+	type ForceOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionMoreThan<AccountId, CouncilCollective, 1, 2>,
+	>;
+	type AssetDeposit = AssetDeposit;
+	type AssetAccountDeposit = AssetAccountDeposit;
+	type MetadataDepositBase = AssetMetadataDepositBase;
+	type MetadataDepositPerByte = AssetMetadataDepositPerByte;
+	type ApprovalDeposit = AssetApprovalDeposit;
+	type StringLimit = AssetsStringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type RemoveItemsLimit = ConstU32<1000>;
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl pallet_asset_rates::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AssetId = AssetId;
+	type Balance = Balance;
+	type UpdateOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionMoreThan<AccountId, CouncilCollective, 1, 2>,
+	>;
+}
+
+/// Splits fees paid in an asset like `DealWithFees` splits GNF fees.
+///
+/// A share too small for its recipient to hold, below the asset's minimum balance, is burnt.
+pub struct DealWithAssetFees;
+impl HandleCredit<AccountId, Assets> for DealWithAssetFees {
+	fn handle_credit(credit: fungibles::CreditOf<AccountId, Assets>) {
+		let (to_treasury, to_author) = credit.ration(20, 80);
+		let to_treasury = match Authorship::author() {
+			Some(author) => {
+				let _ = <Assets as fungibles::Balanced<AccountId>>::resolve(&author, to_author);
+				to_treasury
+			},
+			None => to_treasury.merge(to_author),
+		};
+		let treasury = Treasury::account_id();
+		let _ = <Assets as fungibles::Balanced<AccountId>>::resolve(&treasury, to_treasury);
+	}
+}
+
+impl pallet_asset_tx_payment::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Fungibles = Assets;
+	type OnChargeAssetTransaction =
+		pallet_asset_tx_payment::FungiblesAdapter<AssetRates, DealWithAssetFees>;
+}
+
+impl pallet_evm_paymaster::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Deployers = RecordedDeployers;
+	type ManageOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>,
+	>;
+}
+
 parameter_types! {
 	pub DefaultElasticity: Permill = Permill::zero();
 	pub DefaultBaseFeePerGas: U256 = U256::from(1_000_000_000);
@@ -526,7 +779,7 @@ where
 			frame_system::CheckEra::<Runtime>::from(era),
 			frame_system::CheckNonce::<Runtime>::from(nonce),
 			frame_system::CheckWeight::<Runtime>::new(),
-			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+			pallet_asset_tx_payment::ChargeAssetTxPayment::<Runtime>::from(tip, None),
 		);
 
 		let raw_payload = SignedPayload::new(call, extra)
@@ -559,7 +812,7 @@ impl pallet_im_online::Config for Runtime {
 	// type Event = Event;
 	type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
 	type ValidatorSet = ValidatorSet;
-	type ReportUnresponsiveness = ValidatorSet;
+	type ReportUnresponsiveness = ValidatorLiveness;
 	type UnsignedPriority = ImOnlineUnsignedPriority;
 	type WeightInfo = pallet_im_online::weights::SubstrateWeight<Runtime>;
 	type MaxKeys = MaxKeys;
@@ -569,6 +822,45 @@ impl pallet_im_online::Config for Runtime {
 
 }
 
+/// Lets `pallet_validator_liveness` add and remove validators through `validator_set`.
+pub struct ValidatorSetAdapter;
+impl pallet_validator_liveness::ValidatorSetManager<AccountId> for ValidatorSetAdapter {
+	fn add(who: AccountId) -> frame_support::dispatch::DispatchResult {
+		ValidatorSet::add_validator(RuntimeOrigin::root(), who)
+	}
+
+	fn remove(who: AccountId) -> frame_support::dispatch::DispatchResult {
+		ValidatorSet::remove_validator(RuntimeOrigin::root(), who)
+	}
+
+	fn purge_keys(who: AccountId) -> frame_support::dispatch::DispatchResult {
+		Session::purge_keys(RuntimeOrigin::signed(who))
+	}
+}
+
+/// The index of the current session.
+pub struct CurrentSessionIndex;
+impl frame_support::traits::Get<u32> for CurrentSessionIndex {
+	fn get() -> u32 {
+		Session::current_index()
+	}
+}
+
+impl pallet_validator_liveness::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type ValidatorSet = ValidatorSetAdapter;
+	type ValidatorRegistration = Session;
+	type ReinstateOrigin = EitherOfDiverse<
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>,
+	>;
+	type CurrentSession = CurrentSessionIndex;
+	// A removed validator sits out at least one full session.
+	type SessionsBeforeReinstate = ConstU32<2>;
+	// Tolerate one missed session, e.g. a maintenance window.
+	type DefaultMissesBeforeRemoval = ConstU32<2>;
+}
+
 
 impl pallet_authorship::Config for Runtime {
 	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
@@ -807,7 +1099,8 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system,
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
+		// Took the index of `RandomnessCollectiveFlip`, which it replaced.
+		RandomnessBeacon: pallet_randomness_beacon,
 		Timestamp: pallet_timestamp,
 
 		Balances: pallet_balances,
@@ -838,6 +1131,17 @@ construct_runtime!(
 		Council: pallet_collective::<Instance1>,
 		TechnicalCommittee: pallet_collective::<Instance2>,
 		Preimage: pallet_preimage,
+
+		// Appended so that existing pallet indices stay unchanged
+		ValidatorLiveness: pallet_validator_liveness,
+		EvmDeployers: pallet_evm_deployers,
+		ContractMetadata: pallet_contract_metadata,
+		CallPause: pallet_call_pause,
+		Identity: pallet_identity,
+		Assets: pallet_assets,
+		AssetRates: pallet_asset_rates,
+		AssetTxPayment: pallet_asset_tx_payment,
+		EvmPaymaster: pallet_evm_paymaster,
 	}
 );
 
@@ -881,7 +1185,7 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
 );
 
 // impl pallet_offences::Config for Runtime {
@@ -908,6 +1212,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	migrations::Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -932,6 +1237,49 @@ mod benches {
 	);
 }
 
+/// `InvalidTransaction::Custom` code for a contract creation from a sender that is not
+/// allowed to deploy.
+pub const DEPLOYMENT_NOT_ALLOWED: u8 = 1;
+/// `InvalidTransaction::Custom` code for a transaction without an EIP-155 chain id, once
+/// `EvmDeployers::set_reject_unprotected(true)` was called.
+pub const UNPROTECTED_TRANSACTION: u8 = 2;
+/// `InvalidTransaction::Custom` code for a transaction whose call is paused.
+pub const CALL_PAUSED: u8 = 3;
+
+/// Checks this runtime adds to the ones `pallet_ethereum` does.
+fn check_ethereum_transaction(
+	transaction: &EthereumTransaction,
+	sender: &H160,
+) -> Result<(), pallet_evm_deployers::Error<Runtime>> {
+	if system_contracts::is_allowed_presigned_deployment(transaction, sender) {
+		return Ok(())
+	}
+	if EvmDeployers::reject_unprotected() && system_contracts::is_unprotected(transaction) {
+		return Err(pallet_evm_deployers::Error::UnprotectedTransaction)
+	}
+	if is_contract_creation(transaction) && !EvmDeployers::can_deploy(sender) {
+		return Err(pallet_evm_deployers::Error::DeploymentNotAllowed)
+	}
+	Ok(())
+}
+
+fn invalid_ethereum_transaction(error: pallet_evm_deployers::Error<Runtime>) -> InvalidTransaction {
+	match error {
+		pallet_evm_deployers::Error::UnprotectedTransaction =>
+			InvalidTransaction::Custom(UNPROTECTED_TRANSACTION),
+		_ => InvalidTransaction::Custom(DEPLOYMENT_NOT_ALLOWED),
+	}
+}
+
+fn is_contract_creation(transaction: &EthereumTransaction) -> bool {
+	let action = match transaction {
+		EthereumTransaction::Legacy(t) => &t.action,
+		EthereumTransaction::EIP2930(t) => &t.action,
+		EthereumTransaction::EIP1559(t) => &t.action,
+	};
+	matches!(action, pallet_ethereum::TransactionAction::Create)
+}
+
 impl fp_self_contained::SelfContainedCall for RuntimeCall {
 	type SignedInfo = H160;
 
@@ -956,7 +1304,18 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
 		len: usize,
 	) -> Option<TransactionValidity> {
 		match self {
-			RuntimeCall::Ethereum(call) => call.validate_self_contained(info, dispatch_info, len),
+			RuntimeCall::Ethereum(call) => {
+				// Dispatch would fail on the call filter, without charging any fee.
+				if CallPause::is_paused(self.get_call_metadata()) {
+					return Some(Err(InvalidTransaction::Custom(CALL_PAUSED).into()))
+				}
+				if let transact { transaction } = call {
+					if let Err(e) = check_ethereum_transaction(transaction, info) {
+						return Some(Err(invalid_ethereum_transaction(e).into()))
+					}
+				}
+				call.validate_self_contained(info, dispatch_info, len)
+			},
 			_ => None,
 		}
 	}
@@ -966,9 +1325,16 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
 		info: Self::SignedInfo,
 	) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
 		match self {
-			call @ RuntimeCall::Ethereum(pallet_ethereum::Call::transact { .. }) => Some(call.dispatch(
-				RuntimeOrigin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info)),
-			)),
+			call @ RuntimeCall::Ethereum(pallet_ethereum::Call::transact { .. }) => {
+				if let RuntimeCall::Ethereum(transact { transaction }) = &call {
+					if let Err(e) = check_ethereum_transaction(transaction, &info) {
+						return Some(Err(e.into()))
+					}
+				}
+				Some(call.dispatch(
+					RuntimeOrigin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info)),
+				))
+			},
 			_ => None,
 		}
 	}
@@ -1119,6 +1485,24 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl evm_simulation::EvmSimulationApi<Block> for Runtime {
+		fn simulate(
+			request: evm_simulation::SimulationRequest,
+			overrides: Vec<evm_simulation::StateOverride>,
+		) -> Result<evm_simulation::SimulationResult, sp_runtime::DispatchError> {
+			evm_simulation::apply_overrides(overrides);
+			evm_simulation::execute(request)
+		}
+
+		fn create_access_list(
+			request: evm_simulation::SimulationRequest,
+			overrides: Vec<evm_simulation::StateOverride>,
+		) -> Result<evm_simulation::AccessListResult, sp_runtime::DispatchError> {
+			evm_simulation::apply_overrides(overrides);
+			evm_simulation::create_access_list(request)
+		}
+	}
+
 	impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
 		fn convert_transaction(transaction: EthereumTransaction) -> <Block as BlockT>::Extrinsic {
 			UncheckedExtrinsic::new_unsigned(
@@ -1236,6 +1620,113 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl evm_balances::EvmBalancesApi<Block, Balance, Index> for Runtime {
+		fn evm_account_balance(address: H160) -> evm_balances::EvmAccountBalance<Balance, Index> {
+			use frame_support::traits::tokens::fungible::Inspect;
+
+			let account_id =
+				<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address);
+			let account = System::account(&account_id);
+			let (evm_account, _) = EVM::account_basic(&address);
+
+			evm_balances::EvmAccountBalance {
+				free: account.data.free,
+				reserved: account.data.reserved,
+				misc_frozen: account.data.misc_frozen,
+				fee_frozen: account.data.fee_frozen,
+				evm_balance: evm_account.balance,
+				// EVM transfers may reap the account, but cannot touch locked funds.
+				evm_spendable: <Balances as Inspect<AccountId>>::reducible_balance(
+					&account_id,
+					false,
+				),
+				nonce: account.nonce,
+			}
+		}
+	}
+
+	impl contract_metadata::ContractMetadataApi<Block, AccountId, Balance> for Runtime {
+		fn contract_metadata(
+			address: H160,
+		) -> Option<contract_metadata::ContractMetadata<AccountId, Balance, Vec<u8>>> {
+			ContractMetadata::contract_metadata(address)
+		}
+	}
+
+	impl call_pause::CallPauseApi<Block, BlockNumber> for Runtime {
+		fn paused_calls() -> Vec<(Vec<u8>, Option<Vec<u8>>, BlockNumber)> {
+			CallPause::paused_calls()
+		}
+	}
+
+	impl randomness_beacon::RandomnessBeaconApi<Block> for Runtime {
+		fn vrf_input(slot: u64) -> Option<([u8; 32], H256)> {
+			RandomnessBeacon::vrf_input(slot)
+		}
+	}
+
+	impl validators::ValidatorSetApi<Block, AccountId, opaque::SessionKeys, BlockNumber> for Runtime {
+		fn current_validators() -> Vec<validators::ValidatorInfo<AccountId, opaque::SessionKeys>> {
+			// `NextKeys` may already hold keys set during this session. The keys in use are the
+			// authorities the session handed to each consensus pallet, in validator order.
+			let aura = Aura::authorities();
+			let grandpa = Grandpa::grandpa_authorities();
+			let im_online = ImOnline::keys();
+			Session::validators()
+				.into_iter()
+				.enumerate()
+				.map(|(index, account)| validators::ValidatorInfo {
+					keys: aura.get(index).zip(grandpa.get(index)).zip(im_online.get(index)).map(
+						|((aura, (grandpa, _)), im_online)| opaque::SessionKeys {
+							aura: aura.clone(),
+							grandpa: grandpa.clone(),
+							im_online: im_online.clone(),
+						},
+					),
+					is_online: ImOnline::is_online(index as u32),
+					account,
+				})
+				.collect()
+		}
+
+		fn queued_validators() -> Vec<validators::ValidatorInfo<AccountId, opaque::SessionKeys>> {
+			let current = Session::validators();
+			validator_set::Validators::<Runtime>::get()
+				.into_iter()
+				.map(|account| validators::ValidatorInfo {
+					keys: pallet_session::NextKeys::<Runtime>::get(&account),
+					is_online: current
+						.iter()
+						.position(|v| v == &account)
+						.map_or(false, |index| ImOnline::is_online(index as u32)),
+					account,
+				})
+				.collect()
+		}
+
+		fn session_schedule() -> validators::SessionSchedule<BlockNumber> {
+			use frame_support::traits::EstimateNextSessionRotation;
+
+			let now = System::block_number();
+			let (next_rotation, _) =
+				<pallet_session::PeriodicSessions<Period, Offset> as EstimateNextSessionRotation<
+					BlockNumber,
+				>>::estimate_next_session_rotation(now);
+
+			validators::SessionSchedule {
+				current_index: Session::current_index(),
+				current_block: now,
+				period: Period::get(),
+				next_rotation,
+				blocks_until_next: next_rotation.map(|at| at.saturating_sub(now)),
+			}
+		}
+
+		fn removed_validators() -> Vec<(AccountId, validators::RemovalRecord)> {
+			ValidatorLiveness::removed_validators()
+		}
+	}
+
 	impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
 		fn account_nonce(account: AccountId) -> Index {
 			System::account_nonce(account)
@@ -1323,6 +1814,13 @@ impl_runtime_apis! {
 			// have a backtrace here. If any of the pre/post migration checks fail, we shall stop
 			// right here and right now.
 			let weight = Executive::try_runtime_upgrade().unwrap();
+			// Check the pallet invariants on the migrated state as well, so an offline
+			// `upgrade-check` covers what `execute-block` would.
+			<AllPalletsWithSystem as frame_support::traits::TryState<BlockNumber>>::try_state(
+				System::block_number(),
+				frame_support::traits::TryStateSelect::All,
+			)
+			.expect("try_state failed after the runtime upgrade");
 			(weight, BlockWeights::get().max_block)
 		}
 
@@ -0,0 +1,202 @@
+//! Storage migrations run by `Executive` on runtime upgrades.
+//!
+//! Migrations are grouped by the `spec_version` that first shipped them. [`Unreleased`] collects
+//! the ones for the next release; when a release goes out, its tuple gets a `V<spec_version>`
+//! name and is kept in [`Migrations`] until every live chain has been upgraded past it.
+//!
+//! Pallet storage migrations are wrapped in [`VersionedMigration`], so they only run when the
+//! on-chain storage version is the one they migrate from. [`CheckStorageVersions`] runs last and,
+//! under `try-runtime`, fails if any pallet is left at a storage version other than the one its
+//! code expects.
+
+use frame_support::{
+	log,
+	traits::{GetStorageVersion, OnRuntimeUpgrade, PalletInfoAccess, StorageVersion},
+	weights::Weight,
+};
+use sp_core::Get;
+use sp_std::marker::PhantomData;
+#[cfg(feature = "try-runtime")]
+use sp_std::vec::Vec;
+
+use crate::{system_contracts, Democracy, Preimage, Runtime};
+
+const LOG_TARGET: &str = "runtime::migrations";
+
+/// Every migration the runtime runs, oldest release first.
+pub type Migrations = (Unreleased, CheckStorageVersions);
+
+/// Migrations for the next release.
+pub type Unreleased = (
+	system_contracts::InjectSystemContracts,
+	RemoveRandomnessCollectiveFlip,
+	VersionedMigration<0, 1, pallet_preimage::migration::v1::Migration<Runtime>, Preimage>,
+	VersionedMigration<0, 1, pallet_democracy::migrations::v1::Migration<Runtime>, Democracy>,
+);
+
+/// Runs `Inner` only while `Pallet`'s on-chain storage version is `FROM`, then sets it to `TO`.
+///
+/// Chains that started on a newer version of the pallet skip the migration, pre and post checks
+/// included.
+pub struct VersionedMigration<const FROM: u16, const TO: u16, Inner, Pallet>(
+	PhantomData<(Inner, Pallet)>,
+);
+
+impl<const FROM: u16, const TO: u16, Inner, Pallet> OnRuntimeUpgrade
+	for VersionedMigration<FROM, TO, Inner, Pallet>
+where
+	Inner: OnRuntimeUpgrade,
+	Pallet: GetStorageVersion + PalletInfoAccess,
+{
+	fn on_runtime_upgrade() -> Weight {
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+		if Pallet::on_chain_storage_version() != FROM {
+			log::info!(
+				target: LOG_TARGET,
+				"{}: skipping v{} -> v{}, on-chain version is {:?}",
+				Pallet::name(),
+				FROM,
+				TO,
+				Pallet::on_chain_storage_version(),
+			);
+			return db.reads(1)
+		}
+
+		log::info!(target: LOG_TARGET, "{}: migrating v{} -> v{}", Pallet::name(), FROM, TO);
+		let weight = Inner::on_runtime_upgrade();
+		StorageVersion::new(TO).put::<Pallet>();
+		weight.saturating_add(db.reads_writes(1, 1))
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+		// The first byte tells `post_upgrade` whether `Inner` ran.
+		if Pallet::on_chain_storage_version() != FROM {
+			return Ok(sp_std::vec![0])
+		}
+		let mut state = sp_std::vec![1];
+		state.extend(Inner::pre_upgrade()?);
+		Ok(state)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+		match state.split_first() {
+			Some((0, _)) => Ok(()),
+			Some((1, inner_state)) => {
+				Inner::post_upgrade(inner_state.to_vec())?;
+				frame_support::ensure!(
+					Pallet::on_chain_storage_version() == TO,
+					"storage version was not updated by the migration"
+				);
+				Ok(())
+			},
+			_ => Err("invalid pre-upgrade state"),
+		}
+	}
+}
+
+/// Clears the storage of `pallet_randomness_collective_flip`, replaced by
+/// `pallet_randomness_beacon`.
+pub struct RemoveRandomnessCollectiveFlip;
+
+impl OnRuntimeUpgrade for RemoveRandomnessCollectiveFlip {
+	fn on_runtime_upgrade() -> Weight {
+		// Its only item, `RandomMaterial`, is a single value, so one unbounded pass is cheap.
+		let removed = frame_support::storage::unhashed::clear_prefix(
+			&sp_io::hashing::twox_128(b"RandomnessCollectiveFlip"),
+			None,
+			None,
+		);
+		log::info!(
+			target: LOG_TARGET,
+			"RandomnessCollectiveFlip: removed {} storage keys",
+			removed.unique,
+		);
+		<Runtime as frame_system::Config>::DbWeight::get()
+			.reads_writes(removed.loops.into(), removed.unique.into())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		frame_support::ensure!(
+			!frame_support::storage::migration::have_storage_value(
+				b"RandomnessCollectiveFlip",
+				b"RandomMaterial",
+				b"",
+			),
+			"RandomnessCollectiveFlip storage is left after the upgrade"
+		);
+		Ok(())
+	}
+}
+
+/// Checks that every pallet's on-chain storage version matches its code.
+///
+/// Does nothing outside `try-runtime`; a mismatch there means a migration is missing from
+/// [`Migrations`].
+pub struct CheckStorageVersions;
+
+impl OnRuntimeUpgrade for CheckStorageVersions {
+	fn on_runtime_upgrade() -> Weight {
+		Weight::zero()
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+		use crate::{
+			AssetRates, AssetTxPayment, Assets, Aura, Authorship, Balances, BaseFee, CallPause,
+			ContractMetadata, Council, EvmDeployers, EvmPaymaster, Ethereum, Grandpa, Identity,
+			ImOnline, RandomnessBeacon, Scheduler, Session, Sudo, System,
+			TechnicalCommittee, Timestamp, TransactionPayment, Treasury, ValidatorLiveness,
+			ValidatorSet, EVM,
+		};
+
+		fn check<P: GetStorageVersion + PalletInfoAccess>() -> Result<(), &'static str> {
+			let (on_chain, current) = (P::on_chain_storage_version(), P::current_storage_version());
+			if on_chain != current {
+				log::error!(
+					target: LOG_TARGET,
+					"{}: on-chain storage version {:?}, code expects {:?}",
+					P::name(),
+					on_chain,
+					current,
+				);
+				return Err("pallet storage version does not match its code")
+			}
+			Ok(())
+		}
+
+		check::<System>()?;
+		check::<RandomnessBeacon>()?;
+		check::<Timestamp>()?;
+		check::<Balances>()?;
+		check::<TransactionPayment>()?;
+		check::<ValidatorSet>()?;
+		check::<Authorship>()?;
+		check::<Session>()?;
+		check::<Aura>()?;
+		check::<Grandpa>()?;
+		check::<Sudo>()?;
+		check::<EVM>()?;
+		check::<Ethereum>()?;
+		check::<BaseFee>()?;
+		check::<ImOnline>()?;
+		check::<ValidatorLiveness>()?;
+		check::<Treasury>()?;
+		check::<Democracy>()?;
+		check::<Scheduler>()?;
+		check::<Council>()?;
+		check::<TechnicalCommittee>()?;
+		check::<Preimage>()?;
+		check::<EvmDeployers>()?;
+		check::<ContractMetadata>()?;
+		check::<CallPause>()?;
+		check::<Identity>()?;
+		check::<Assets>()?;
+		check::<AssetRates>()?;
+		check::<AssetTxPayment>()?;
+		check::<EvmPaymaster>()?;
+		Ok(())
+	}
+}
@@ -1,11 +1,56 @@
+use fp_evm::{ExitError, PrecompileFailure};
 use pallet_evm::{Precompile, PrecompileHandle, PrecompileResult, PrecompileSet};
-use sp_core::H160;
+use sp_core::{H160, U256};
 use sp_std::marker::PhantomData;
 
+use pallet_evm_precompile_assets_erc20::{AssetBalanceOf, AssetIdOf, AssetsErc20Precompile};
+use pallet_evm_precompile_balances_erc20::{BalanceOf, BalancesErc20Precompile, Erc20Metadata};
+use pallet_evm_precompile_identity::IdentityPrecompile;
 use pallet_evm_precompile_modexp::Modexp;
+use pallet_evm_precompile_multicall::MulticallPrecompile;
+use pallet_evm_precompile_randomness::RandomnessPrecompile;
 use pallet_evm_precompile_sha3fips::Sha3FIPS256;
 use pallet_evm_precompile_simple::{ECRecover, ECRecoverPublicKey, Identity, Ripemd160, Sha256};
 
+/// Address of the wrapped GNF token, an ERC-20 view of native balances.
+pub const WGNF_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x02,
+]);
+
+/// Address of the identity registry, resolving addresses to verified display names.
+pub const IDENTITY_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x03,
+]);
+
+/// Address of the randomness beacon, serving randomness requests from contracts.
+pub const RANDOMNESS_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x04,
+]);
+
+/// Address of Multicall3, the same as on every other chain.
+pub const MULTICALL3_ADDRESS: H160 = H160([
+	0xca, 0x11, 0xbd, 0xe0, 0x59, 0x77, 0xb3, 0x63, 0x11, 0x67,
+	0x02, 0x88, 0x62, 0xbe, 0x2a, 0x17, 0x39, 0x76, 0xca, 0x11,
+]);
+
+pub struct WrappedGnfMetadata;
+impl Erc20Metadata for WrappedGnfMetadata {
+	fn name() -> &'static str {
+		"Wrapped GNF"
+	}
+
+	fn symbol() -> &'static str {
+		"WGNF"
+	}
+
+	fn decimals() -> u8 {
+		18
+	}
+}
+
 pub struct SubstratePrecompiles<R>(PhantomData<R>);
 
 impl<R> SubstratePrecompiles<R>
@@ -16,18 +61,32 @@ where
 		Self(Default::default())
 	}
 	pub fn used_addresses() -> sp_std::vec::Vec<H160> {
-		sp_std::vec![1, 2, 3, 4, 5, 1024, 1025]
+		sp_std::vec![1, 2, 3, 4, 5, 1024, 1025, 2050, 2051, 2052]
 			.into_iter()
 			.map(hash)
+			.chain([MULTICALL3_ADDRESS])
 			.collect()
 	}
 }
 impl<R> PrecompileSet for SubstratePrecompiles<R>
 where
-	R: pallet_evm::Config,
+	R: pallet_evm::Config
+		+ pallet_identity::Config
+		+ pallet_assets::Config
+		+ pallet_timestamp::Config
+		+ pallet_randomness_beacon::Config,
+	R::AccountId: Into<H160>,
+	R::Moment: Into<U256>,
+	BalanceOf<R>: TryFrom<U256> + Into<U256>,
+	AssetIdOf<R>: TryFrom<u128>,
+	AssetBalanceOf<R>: TryFrom<U256> + Into<U256>,
 {
 	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
-		match handle.code_address() {
+		let address = handle.code_address();
+		// Chain-specific precompiles act for the caller and keep state at their own address,
+		// neither of which holds when they run through DELEGATECALL or CALLCODE.
+		let delegated = address != handle.context().address;
+		match address {
 			// Ethereum precompiles :
 			a if a == hash(1) => Some(ECRecover::execute(handle)),
 			a if a == hash(2) => Some(Sha256::execute(handle)),
@@ -37,13 +96,38 @@ where
 			// Non-Frontier specific nor Ethereum precompiles :
 			a if a == hash(1024) => Some(Sha3FIPS256::execute(handle)),
 			a if a == hash(1025) => Some(ECRecoverPublicKey::execute(handle)),
-			_ => None,
+			// Chain-specific precompiles :
+			a if a == WGNF_ADDRESS => Some(direct_only(delegated, || {
+				BalancesErc20Precompile::<R, WrappedGnfMetadata>::execute(handle)
+			})),
+			a if a == IDENTITY_ADDRESS =>
+				Some(direct_only(delegated, || IdentityPrecompile::<R>::execute(handle))),
+			a if a == RANDOMNESS_ADDRESS =>
+				Some(direct_only(delegated, || RandomnessPrecompile::<R>::execute(handle))),
+			// Calls from the address it runs at, so DELEGATECALL acts for the delegating contract
+			// as the Multicall3 contract would.
+			a if a == MULTICALL3_ADDRESS => Some(MulticallPrecompile::<R>::execute(handle)),
+			// Every asset of `pallet_assets`, at its own address.
+			a => AssetsErc20Precompile::<R>::asset_at(a).map(|asset| {
+				direct_only(delegated, || AssetsErc20Precompile::<R>::execute(handle, asset))
+			}),
 		}
 	}
 
 	fn is_precompile(&self, address: H160) -> bool {
-		Self::used_addresses().contains(&address)
+		Self::used_addresses().contains(&address) ||
+			AssetsErc20Precompile::<R>::asset_at(address).is_some()
+	}
+}
+
+/// Run `execute`, unless the precompile was reached through DELEGATECALL or CALLCODE.
+fn direct_only(delegated: bool, execute: impl FnOnce() -> PrecompileResult) -> PrecompileResult {
+	if delegated {
+		return Err(PrecompileFailure::Error {
+			exit_status: ExitError::Other("cannot be called with DELEGATECALL or CALLCODE".into()),
+		})
 	}
+	execute()
 }
 
 fn hash(a: u64) -> H160 {
@@ -0,0 +1,11 @@
+//! Runtime API block authors use to sign the VRF of `pallet_randomness_beacon`.
+
+use sp_core::H256;
+
+sp_api::decl_runtime_apis! {
+	/// The API to get the input of the randomness beacon VRF.
+	pub trait RandomnessBeaconApi {
+		/// The sr25519 key that signs the VRF of `slot`, and the beacon output the VRF extends.
+		fn vrf_input(slot: u64) -> Option<([u8; 32], H256)>;
+	}
+}
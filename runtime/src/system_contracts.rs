@@ -0,0 +1,133 @@
+//! Contracts that tools expect at the same address on every chain.
+//!
+//! - The deterministic deployment proxy, the `CREATE2` factory Foundry and Hardhat deploy
+//!   through, is injected as code: into genesis by the chain spec, and into live chains by
+//!   [`InjectSystemContracts`].
+//! - Multicall3 is a precompile at its canonical address with the same interface, so it is in
+//!   place from genesis and after [`InjectSystemContracts`] without a deployment to fund.
+//! - Precompiles that Solidity calls directly, Multicall3 included, get a stub code in the same
+//!   way, so the `extcodesize` check Solidity makes before a call passes.
+//!
+//! Transactions without an EIP-155 chain id can be replayed from any other chain. Governance can
+//! reject them with `EvmDeployers::set_reject_unprotected(true)`; the canonical presigned
+//! deployment of the proxy is still accepted then, once governance calls
+//! `EvmDeployers::set_presigned_deployments(true)` and its sender has been funded.
+
+use frame_support::{log, traits::OnRuntimeUpgrade, weights::Weight};
+use hex_literal::hex;
+use pallet_ethereum::Transaction as EthereumTransaction;
+use pallet_evm::AddressMapping;
+use sp_core::{Get, H160};
+
+use crate::{
+	EvmDeployers, Runtime, EVM, IDENTITY_ADDRESS, MULTICALL3_ADDRESS, PRECOMPILE_CODE,
+	RANDOMNESS_ADDRESS, WGNF_ADDRESS,
+};
+
+const LOG_TARGET: &str = "runtime::system-contracts";
+
+/// Address of the deterministic deployment proxy.
+pub const DETERMINISTIC_DEPLOYMENT_PROXY: H160 =
+	H160(hex!("4e59b44847b379578588920ca78fbf26c0b4956c"));
+
+/// Runtime code of the deterministic deployment proxy.
+pub const DETERMINISTIC_DEPLOYMENT_PROXY_CODE: &[u8] = &hex!(
+	"7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe036016000816020823780358282"
+	"34f58015156039578182fd5b8082525050506014600cf3"
+);
+
+/// Contracts injected as code, by address.
+pub const SYSTEM_CONTRACTS: [(H160, &[u8]); 1] =
+	[(DETERMINISTIC_DEPLOYMENT_PROXY, DETERMINISTIC_DEPLOYMENT_PROXY_CODE)];
+
+/// Precompiles that get [`PRECOMPILE_CODE`].
+pub const PRECOMPILES_WITH_CODE: [H160; 4] =
+	[WGNF_ADDRESS, IDENTITY_ADDRESS, RANDOMNESS_ADDRESS, MULTICALL3_ADDRESS];
+
+/// Senders of the canonical presigned deployment transactions.
+pub const PRESIGNED_DEPLOYERS: [H160; 1] = [
+	// Deterministic deployment proxy, for chains that did not get it injected.
+	H160(hex!("3fab184622dc19b6109349b94811493bf2a45362")),
+];
+
+/// Whether `transaction` lacks an EIP-155 chain id and could be replayed from another chain.
+pub fn is_unprotected(transaction: &EthereumTransaction) -> bool {
+	match transaction {
+		EthereumTransaction::Legacy(t) => t.signature.chain_id().is_none(),
+		EthereumTransaction::EIP2930(_) | EthereumTransaction::EIP1559(_) => false,
+	}
+}
+
+/// Whether `transaction` from `sender` is a presigned deployment that governance allows.
+pub fn is_allowed_presigned_deployment(transaction: &EthereumTransaction, sender: &H160) -> bool {
+	EvmDeployers::presigned_deployments() &&
+		PRESIGNED_DEPLOYERS.contains(sender) &&
+		crate::is_contract_creation(transaction)
+}
+
+/// Puts the code of [`SYSTEM_CONTRACTS`] and [`PRECOMPILES_WITH_CODE`] in place on chains that
+/// do not have it yet.
+///
+/// Addresses that already hold code are left alone.
+pub struct InjectSystemContracts;
+
+impl OnRuntimeUpgrade for InjectSystemContracts {
+	fn on_runtime_upgrade() -> Weight {
+		let mut weight = Weight::zero();
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+
+		for (address, code) in SYSTEM_CONTRACTS {
+			weight = weight.saturating_add(db.reads(1));
+			if !EVM::account_codes(address).is_empty() {
+				continue
+			}
+
+			// As in genesis, which also makes the account sufficient.
+			EVM::create_account(address, code.to_vec());
+			// Contract accounts start at nonce 1 (EIP-161).
+			let account_id =
+				<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address);
+			frame_system::Pallet::<Runtime>::inc_account_nonce(&account_id);
+			weight = weight.saturating_add(db.reads_writes(3, 3));
+
+			log::info!(target: LOG_TARGET, "injected system contract at {:?}", address);
+		}
+
+		for address in PRECOMPILES_WITH_CODE {
+			weight = weight.saturating_add(db.reads(1));
+			if !EVM::account_codes(address).is_empty() {
+				continue
+			}
+
+			EVM::create_account(address, PRECOMPILE_CODE.to_vec());
+			weight = weight.saturating_add(db.reads_writes(2, 2));
+			log::info!(target: LOG_TARGET, "injected precompile code at {:?}", address);
+		}
+
+		weight
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: sp_std::vec::Vec<u8>) -> Result<(), &'static str> {
+		let sufficient = |address| {
+			let account_id =
+				<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address);
+			frame_system::Account::<Runtime>::get(account_id).sufficients > 0
+		};
+		for (address, code) in SYSTEM_CONTRACTS {
+			frame_support::ensure!(
+				EVM::account_codes(address) == code,
+				"system contract code is missing after the upgrade"
+			);
+			frame_support::ensure!(sufficient(address), "system contract is not sufficient");
+		}
+		for address in PRECOMPILES_WITH_CODE {
+			frame_support::ensure!(
+				!EVM::account_codes(address).is_empty(),
+				"precompile code is missing after the upgrade"
+			);
+			frame_support::ensure!(sufficient(address), "precompile account is not sufficient");
+		}
+		Ok(())
+	}
+}
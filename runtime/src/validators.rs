@@ -0,0 +1,66 @@
+//! Runtime API exposing the validator set and the session schedule.
+//!
+//! Aura and GRANDPA only report bare authority keys. This API ties those keys back to the
+//! `AccountId20` that registered them and tells clients when the next session rotation is due.
+
+use codec::{Codec, Decode, Encode};
+pub use pallet_validator_liveness::{RemovalReason, RemovalRecord};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// A validator together with its registered session keys.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ValidatorInfo<AccountId, Keys> {
+	/// The account that controls the validator.
+	pub account: AccountId,
+	/// Session keys of the validator, if any.
+	///
+	/// The keys in use for validators of the current session, and the keys registered through
+	/// `Session::set_keys` for queued validators.
+	pub keys: Option<Keys>,
+	/// Whether the validator has been seen by `ImOnline` in the current session.
+	///
+	/// Always `false` for validators that are not part of the current session.
+	pub is_online: bool,
+}
+
+/// Where the chain is in its session schedule.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct SessionSchedule<BlockNumber> {
+	/// Index of the current session.
+	pub current_index: u32,
+	/// Block the schedule was computed at.
+	pub current_block: BlockNumber,
+	/// Length of a session in blocks.
+	pub period: BlockNumber,
+	/// Block at which the next session is expected to start.
+	pub next_rotation: Option<BlockNumber>,
+	/// Number of blocks left until `next_rotation`.
+	pub blocks_until_next: Option<BlockNumber>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to query the validator set and the session schedule.
+	pub trait ValidatorSetApi<AccountId, Keys, BlockNumber> where
+		AccountId: Codec,
+		Keys: Codec,
+		BlockNumber: Codec,
+	{
+		/// Validators of the current session, in authority index order.
+		fn current_validators() -> Vec<ValidatorInfo<AccountId, Keys>>;
+
+		/// Validators that `validator_set` will hand to the session at the next rotation.
+		fn queued_validators() -> Vec<ValidatorInfo<AccountId, Keys>>;
+
+		/// The current session index and the expected next rotation.
+		fn session_schedule() -> SessionSchedule<BlockNumber>;
+
+		/// Validators removed for missing heartbeats and not reinstated yet.
+		fn removed_validators() -> Vec<(AccountId, RemovalRecord)>;
+	}
+}